@@ -1,23 +1,37 @@
 use super::{AsyncPushSender, HandleContainer, RedisFuture};
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use crate::caching::CacheManager;
 use crate::{
     aio::{check_resp3, ConnectionLike, MultiplexedConnection, Runtime},
     cmd,
     subscription_tracker::{SubscriptionAction, SubscriptionTracker},
     types::{RedisError, RedisResult, Value},
-    AsyncConnectionConfig, Client, Cmd, Pipeline, ProtocolVersion, PushInfo, PushKind, ToRedisArgs,
+    AsyncConnectionConfig, Client, Cmd, Pipeline, ProtocolVersion, PushInfo, PushKind,
+    RedisConnectionInfo, ToRedisArgs,
+};
+#[cfg(feature = "sentinel")]
+use crate::{
+    sentinel::{is_readonly_result, SentinelClient, SentinelServerType},
+    ConnectionInfo,
 };
 use arc_swap::ArcSwap;
 use backon::{ExponentialBuilder, Retryable};
 use futures_channel::oneshot;
 use futures_util::future::{self, BoxFuture, FutureExt, Shared};
+#[cfg(feature = "sentinel")]
+use futures_util::StreamExt;
 use std::sync::Arc;
+#[cfg(feature = "sentinel")]
+use std::sync::Weak;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 use tokio::sync::Mutex;
 
 type OptionalPushSender = Option<Arc<dyn AsyncPushSender>>;
 
+/// How long to wait before subscribing again, after the connections to all sentinels failed.
+#[cfg(feature = "sentinel")]
+const SENTINEL_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// The configuration for reconnect mechanism and request timing for the [ConnectionManager]
 #[derive(Clone)]
 pub struct ConnectionManagerConfig {
@@ -41,7 +55,7 @@ pub struct ConnectionManagerConfig {
     /// if true, the manager should resubscribe automatically to all pubsub channels after reconnect.
     resubscribe_automatically: bool,
     tcp_settings: crate::io::tcp::TcpSettings,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     pub(crate) cache_config: Option<crate::caching::CacheConfig>,
 }
 
@@ -57,7 +71,7 @@ impl std::fmt::Debug for ConnectionManagerConfig {
             push_sender,
             resubscribe_automatically,
             tcp_settings,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_config,
         } = &self;
         let mut str = f.debug_struct("ConnectionManagerConfig");
@@ -78,7 +92,7 @@ impl std::fmt::Debug for ConnectionManagerConfig {
             )
             .field("tcp_settings", &tcp_settings);
 
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         str.field("cache_config", &cache_config);
 
         str.finish()
@@ -187,7 +201,7 @@ impl ConnectionManagerConfig {
     }
 
     /// Set the cache behavior.
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     pub fn set_cache_config(self, cache_config: crate::caching::CacheConfig) -> Self {
         Self {
             cache_config: Some(cache_config),
@@ -208,15 +222,104 @@ impl Default for ConnectionManagerConfig {
             push_sender: None,
             resubscribe_automatically: false,
             tcp_settings: Default::default(),
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_config: None,
         }
     }
 }
 
+/// The server a [ConnectionManager] connects, and reconnects, to.
+enum ConnectionTarget {
+    Client(Client),
+    /// The master or a replica of a service monitored by sentinels, resolved again on every reconnection.
+    #[cfg(feature = "sentinel")]
+    Sentinel {
+        client: Box<Mutex<SentinelClient>>,
+        redis_connection_info: RedisConnectionInfo,
+        follows_master: bool,
+    },
+}
+
+impl ConnectionTarget {
+    fn redis_connection_info(&self) -> &RedisConnectionInfo {
+        match self {
+            ConnectionTarget::Client(client) => &client.connection_info().redis,
+            #[cfg(feature = "sentinel")]
+            ConnectionTarget::Sentinel {
+                redis_connection_info,
+                ..
+            } => redis_connection_info,
+        }
+    }
+
+    async fn connect(
+        &self,
+        connection_config: &AsyncConnectionConfig,
+    ) -> RedisResult<MultiplexedConnection> {
+        match self {
+            ConnectionTarget::Client(client) => {
+                client
+                    .get_multiplexed_async_connection_with_config(connection_config)
+                    .await
+            }
+            #[cfg(feature = "sentinel")]
+            ConnectionTarget::Sentinel { client, .. } => {
+                client
+                    .lock()
+                    .await
+                    .get_async_connection_with_config(connection_config)
+                    .await
+            }
+        }
+    }
+
+    /// Identifies the server of the values in a shared cache storage. A service monitored by sentinels is identified
+    /// by its name, since its address changes with failovers.
+    #[cfg(feature = "cache")]
+    async fn cache_scope(&self) -> String {
+        match self {
+            ConnectionTarget::Client(client) => client.connection_info().addr.to_string(),
+            #[cfg(feature = "sentinel")]
+            ConnectionTarget::Sentinel { client, .. } => {
+                format!("sentinel:{}", client.lock().await.service_name())
+            }
+        }
+    }
+
+    /// Whether the result shows that the connected server is no longer the desired one, i.e. a master which was
+    /// demoted to a replica by a failover. Replies hold the errors returned by the server, like READONLY.
+    #[cfg_attr(not(feature = "sentinel"), allow(unused_variables))]
+    fn is_stale_server_result(&self, result: Result<&[Value], &RedisError>) -> bool {
+        match self {
+            ConnectionTarget::Client(_) => false,
+            #[cfg(feature = "sentinel")]
+            ConnectionTarget::Sentinel { follows_master, .. } => {
+                *follows_master && is_readonly_result(result)
+            }
+        }
+    }
+}
+
+/// The replies of a command, or of the commands of a pipeline.
+trait AsReplies {
+    fn as_replies(&self) -> &[Value];
+}
+
+impl AsReplies for Value {
+    fn as_replies(&self) -> &[Value] {
+        std::slice::from_ref(self)
+    }
+}
+
+impl AsReplies for Vec<Value> {
+    fn as_replies(&self) -> &[Value] {
+        self
+    }
+}
+
 struct Internals {
     /// Information used for the connection. This is needed to be able to reconnect.
-    client: Client,
+    target: ConnectionTarget,
     /// The connection future.
     ///
     /// The `ArcSwap` is required to be able to replace the connection
@@ -227,9 +330,11 @@ struct Internals {
     retry_strategy: ExponentialBuilder,
     connection_config: AsyncConnectionConfig,
     subscription_tracker: Option<Mutex<SubscriptionTracker>>,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     cache_manager: Option<CacheManager>,
     _task_handle: HandleContainer,
+    #[cfg(feature = "sentinel")]
+    _failover_task_handle: Option<HandleContainer>,
 }
 
 /// A `ConnectionManager` is a proxy that wraps a [multiplexed
@@ -272,10 +377,11 @@ type SharedRedisFuture<T> = Shared<BoxFuture<'static, CloneableRedisResult<T>>>;
 /// Handle a command result. If the connection was dropped, reconnect.
 macro_rules! reconnect_if_dropped {
     ($self:expr, $result:expr, $current:expr) => {
-        if let Err(ref e) = $result {
-            if e.is_unrecoverable_error() {
-                $self.reconnect($current);
-            }
+        let result = $result.as_ref().map(AsReplies::as_replies);
+        if result.is_err_and(|e| e.is_unrecoverable_error())
+            || $self.0.target.is_stale_server_result(result)
+        {
+            $self.reconnect($current);
         }
     };
 }
@@ -372,6 +478,50 @@ impl ConnectionManager {
     pub async fn new_with_config(
         client: Client,
         config: ConnectionManagerConfig,
+    ) -> RedisResult<Self> {
+        Self::new_with_target(ConnectionTarget::Client(client), config).await
+    }
+
+    /// Connect to the server resolved by the sentinel client and store the connection inside the returned
+    /// `ConnectionManager`.
+    ///
+    /// Each reconnection asks the sentinels for the current address of the server, and the manager subscribes to
+    /// `+switch-master` on the sentinels, so that it reconnects as soon as a failover of the service is announced,
+    /// instead of waiting for the old connection to fail. A manager of a master also reconnects when the server
+    /// answers with a `READONLY` error, which means that the master was demoted to a replica. Channels tracked by
+    /// [ConnectionManagerConfig::set_automatic_resubscription] are subscribed to again on the new server.
+    ///
+    /// ```rust,no_run
+    /// # async fn run() -> redis::RedisResult<()> {
+    /// use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+    /// use redis::sentinel::{SentinelClient, SentinelServerType};
+    ///
+    /// let nodes = vec!["redis://127.0.0.1:26379/", "redis://127.0.0.1:26380/"];
+    /// let client = SentinelClient::build(nodes, String::from("master1"), None, SentinelServerType::Master)?;
+    /// let mut manager = ConnectionManager::new_with_sentinel(client, ConnectionManagerConfig::new()).await?;
+    /// redis::cmd("SET").arg("key").arg("value").exec_async(&mut manager).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    pub async fn new_with_sentinel(
+        client: SentinelClient,
+        config: ConnectionManagerConfig,
+    ) -> RedisResult<Self> {
+        let redis_connection_info = client.redis_connection_info();
+        let follows_master = matches!(client.server_type(), SentinelServerType::Master);
+        let target = ConnectionTarget::Sentinel {
+            client: Box::new(Mutex::new(client)),
+            redis_connection_info,
+            follows_master,
+        };
+        Self::new_with_target(target, config).await
+    }
+
+    async fn new_with_target(
+        target: ConnectionTarget,
+        config: ConnectionManagerConfig,
     ) -> RedisResult<Self> {
         // Create a MultiplexedConnection and wait for it to be established
         let runtime = Runtime::locate();
@@ -397,14 +547,19 @@ impl ConnectionManager {
             connection_config = connection_config.set_response_timeout(response_timeout);
         }
         connection_config = connection_config.set_tcp_settings(config.tcp_settings);
-        #[cfg(feature = "cache-aio")]
-        let cache_manager = config
-            .cache_config
-            .as_ref()
-            .map(|cache_config| CacheManager::new(*cache_config));
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
+        let cache_manager = match &config.cache_config {
+            Some(cache_config) => Some(CacheManager::new(cache_config.clone()).with_scope(
+                &target.cache_scope().await,
+                target.redis_connection_info().db,
+            )),
+            None => None,
+        };
+        #[cfg(feature = "cache")]
         if let Some(cache_manager) = cache_manager.as_ref() {
-            connection_config = connection_config.set_cache_manager(cache_manager.clone());
+            // Each connection has its own epoch, so values it cached aren't used once it's replaced.
+            connection_config =
+                connection_config.set_cache_manager(cache_manager.clone_and_increase_epoch());
         }
 
         let (oneshot_sender, oneshot_receiver) = oneshot::channel();
@@ -415,7 +570,7 @@ impl ConnectionManager {
         let mut components_for_reconnection_on_push = None;
         if let Some(push_sender) = config.push_sender.clone() {
             check_resp3!(
-                client.connection_info.redis.protocol,
+                target.redis_connection_info().protocol,
                 "Can only pass push sender to a connection using RESP3"
             );
 
@@ -424,7 +579,7 @@ impl ConnectionManager {
 
             connection_config =
                 connection_config.set_push_sender_internal(Arc::new(internal_sender));
-        } else if client.connection_info.redis.protocol != ProtocolVersion::RESP2 {
+        } else if target.redis_connection_info().protocol != ProtocolVersion::RESP2 {
             let (internal_sender, internal_receiver) = unbounded_channel();
             components_for_reconnection_on_push = Some((internal_receiver, None));
 
@@ -433,25 +588,47 @@ impl ConnectionManager {
         }
 
         let connection =
-            Self::new_connection(&client, retry_strategy, &connection_config, None).await?;
+            Self::new_connection(&target, retry_strategy, &connection_config, None).await?;
         let subscription_tracker = if config.resubscribe_automatically {
             Some(Mutex::new(SubscriptionTracker::default()))
         } else {
             None
         };
 
+        #[cfg(feature = "sentinel")]
+        let (failover_sender, _failover_task_handle) = match &target {
+            ConnectionTarget::Sentinel { client, .. } => {
+                let client = client.lock().await;
+                let (sender, receiver) = oneshot::channel();
+                let task = runtime.spawn(Self::follow_sentinel_failovers(
+                    receiver,
+                    client.service_name().to_string(),
+                ));
+                (Some(sender), Some(HandleContainer::new(task)))
+            }
+            ConnectionTarget::Client(_) => (None, None),
+        };
+
         let new_self = Self(Arc::new(Internals {
-            client,
+            target,
             connection: ArcSwap::from_pointee(future::ok(connection).boxed().shared()),
             runtime,
             retry_strategy,
             connection_config,
             subscription_tracker,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_manager,
             _task_handle,
+            #[cfg(feature = "sentinel")]
+            _failover_task_handle,
         }));
 
+        #[cfg(feature = "sentinel")]
+        if let Some(failover_sender) = failover_sender {
+            // The task only holds a weak reference, so that it doesn't keep the manager alive.
+            let _ = failover_sender.send(Arc::downgrade(&new_self.0));
+        }
+
         if let Some((internal_receiver, external_sender)) = components_for_reconnection_on_push {
             oneshot_sender
                 .send((new_self.clone(), internal_receiver, external_sender))
@@ -467,17 +644,13 @@ impl ConnectionManager {
     }
 
     async fn new_connection(
-        client: &Client,
+        target: &ConnectionTarget,
         exponential_backoff: ExponentialBuilder,
         connection_config: &AsyncConnectionConfig,
         additional_commands: Option<Pipeline>,
     ) -> RedisResult<MultiplexedConnection> {
         let connection_config = connection_config.clone();
-        let get_conn = || async {
-            client
-                .get_multiplexed_async_connection_with_config(&connection_config)
-                .await
-        };
+        let get_conn = || async { target.connect(&connection_config).await };
         let mut conn = get_conn
             .retry(exponential_backoff)
             .sleep(|duration| async move { Runtime::locate().sleep(duration).await })
@@ -495,11 +668,11 @@ impl ConnectionManager {
     /// when the connection loss was detected.
     fn reconnect(&self, current: arc_swap::Guard<Arc<SharedRedisFuture<MultiplexedConnection>>>) {
         let self_clone = self.clone();
-        #[cfg(not(feature = "cache-aio"))]
+        #[cfg(not(feature = "cache"))]
         let connection_config = self_clone.0.connection_config.clone();
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         let mut connection_config = self_clone.0.connection_config.clone();
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         if let Some(manager) = self.0.cache_manager.as_ref() {
             let new_cache_manager = manager.clone_and_increase_epoch();
             connection_config = connection_config.set_cache_manager(new_cache_manager);
@@ -516,7 +689,7 @@ impl ConnectionManager {
             };
 
             let con = Self::new_connection(
-                &self_clone.0.client,
+                &self_clone.0.target,
                 self_clone.0.retry_strategy,
                 &connection_config,
                 additional_commands,
@@ -561,6 +734,66 @@ impl ConnectionManager {
         }
     }
 
+    /// Reconnects whenever the sentinels announce a failover of the service, until the manager is dropped.
+    #[cfg(feature = "sentinel")]
+    async fn follow_sentinel_failovers(
+        receiver: oneshot::Receiver<Weak<Internals>>,
+        service_name: String,
+    ) {
+        let Ok(internals) = receiver.await else {
+            return;
+        };
+        loop {
+            // The sentinels are read on every round, so that the sentinels discovered by the client are watched too.
+            let Some(sentinels) = Self::current_sentinels(&internals).await else {
+                return;
+            };
+            for sentinel in &sentinels {
+                // A sentinel which can't be reached, or whose connection dropped, is replaced by the next one.
+                let _ = Self::watch_sentinel(&internals, sentinel, &service_name).await;
+                if internals.strong_count() == 0 {
+                    return;
+                }
+            }
+            Runtime::locate().sleep(SENTINEL_RETRY_DELAY).await;
+        }
+    }
+
+    #[cfg(feature = "sentinel")]
+    async fn current_sentinels(internals: &Weak<Internals>) -> Option<Vec<ConnectionInfo>> {
+        let internals = internals.upgrade()?;
+        match &internals.target {
+            ConnectionTarget::Sentinel { client, .. } => {
+                Some(client.lock().await.sentinels_connection_info().to_vec())
+            }
+            ConnectionTarget::Client(_) => None,
+        }
+    }
+
+    #[cfg(feature = "sentinel")]
+    async fn watch_sentinel(
+        internals: &Weak<Internals>,
+        sentinel: &ConnectionInfo,
+        service_name: &str,
+    ) -> RedisResult<()> {
+        let mut pubsub = Client::open(sentinel.clone())?.get_async_pubsub().await?;
+        pubsub.subscribe("+switch-master").await?;
+        let mut messages = pubsub.into_on_message();
+        while let Some(msg) = messages.next().await {
+            // The payload is `<master name> <old ip> <old port> <new ip> <new port>`.
+            let payload: String = msg.get_payload()?;
+            if payload.split(' ').next() != Some(service_name) {
+                continue;
+            }
+            let Some(internals) = internals.upgrade() else {
+                return Ok(());
+            };
+            let this = ConnectionManager(internals);
+            this.reconnect(this.0.connection.load());
+        }
+        Ok(())
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
     pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
@@ -620,7 +853,7 @@ impl ConnectionManager {
     /// It should be noted that unless [ConnectionManagerConfig::set_automatic_resubscription] was called,
     /// the subscription will be removed on a disconnect and must be re-subscribed.
     pub async fn subscribe(&mut self, channel_name: impl ToRedisArgs) -> RedisResult<()> {
-        check_resp3!(self.0.client.connection_info.redis.protocol);
+        check_resp3!(self.0.target.redis_connection_info().protocol);
         let mut cmd = cmd("SUBSCRIBE");
         cmd.arg(&channel_name);
         cmd.exec_async(self).await?;
@@ -634,7 +867,7 @@ impl ConnectionManager {
     ///
     /// This method is only available when the connection is using RESP3 protocol, and will return an error otherwise.
     pub async fn unsubscribe(&mut self, channel_name: impl ToRedisArgs) -> RedisResult<()> {
-        check_resp3!(self.0.client.connection_info.redis.protocol);
+        check_resp3!(self.0.target.redis_connection_info().protocol);
         let mut cmd = cmd("UNSUBSCRIBE");
         cmd.arg(&channel_name);
         cmd.exec_async(self).await?;
@@ -652,7 +885,7 @@ impl ConnectionManager {
     /// It should be noted that unless [ConnectionManagerConfig::set_automatic_resubscription] was called,
     /// the subscription will be removed on a disconnect and must be re-subscribed.
     pub async fn psubscribe(&mut self, channel_pattern: impl ToRedisArgs) -> RedisResult<()> {
-        check_resp3!(self.0.client.connection_info.redis.protocol);
+        check_resp3!(self.0.target.redis_connection_info().protocol);
         let mut cmd = cmd("PSUBSCRIBE");
         cmd.arg(&channel_pattern);
         cmd.exec_async(self).await?;
@@ -665,7 +898,7 @@ impl ConnectionManager {
     ///
     /// This method is only available when the connection is using RESP3 protocol, and will return an error otherwise.
     pub async fn punsubscribe(&mut self, channel_pattern: impl ToRedisArgs) -> RedisResult<()> {
-        check_resp3!(self.0.client.connection_info.redis.protocol);
+        check_resp3!(self.0.target.redis_connection_info().protocol);
         let mut cmd = cmd("PUNSUBSCRIBE");
         cmd.arg(&channel_pattern);
         cmd.exec_async(self).await?;
@@ -675,11 +908,58 @@ impl ConnectionManager {
     }
 
     /// Gets [`crate::caching::CacheStatistics`] for current connection if caching is enabled.
-    #[cfg(feature = "cache-aio")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
     pub fn get_cache_statistics(&self) -> Option<crate::caching::CacheStatistics> {
         self.0.cache_manager.as_ref().map(|cm| cm.statistics())
     }
+
+    /// Removes given keys from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_keys<K: ToRedisArgs>(&self, keys: K) {
+        if let Some(cache_manager) = &self.0.cache_manager {
+            cache_manager.invalidate_keys(&keys.to_redis_args());
+        }
+    }
+
+    /// Removes keys matching the glob-style pattern from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_pattern(&self, pattern: impl AsRef<[u8]>) {
+        if let Some(cache_manager) = &self.0.cache_manager {
+            cache_manager.invalidate_pattern(pattern.as_ref());
+        }
+    }
+
+    /// Removes every value from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn flush_cache(&self) {
+        if let Some(cache_manager) = &self.0.cache_manager {
+            cache_manager.flush();
+        }
+    }
+
+    /// Reads given keys with `GET` and stores their values in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_keys<K: ToRedisArgs>(&mut self, keys: K) -> RedisResult<()> {
+        let commands = crate::caching::cmd::prefetch_key_commands(keys.to_redis_args());
+        self.prefetch_cache_commands(&commands).await
+    }
+
+    /// Sends given commands and stores their replies in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_commands(&mut self, commands: &[Cmd]) -> RedisResult<()> {
+        if self.0.cache_manager.is_none() || commands.is_empty() {
+            return Ok(());
+        }
+        let pipeline = crate::caching::cmd::prefetch_pipeline(commands);
+        let _: Vec<Value> = pipeline.query_async(self).await?;
+        Ok(())
+    }
 }
 
 impl ConnectionLike for ConnectionManager {
@@ -697,6 +977,6 @@ impl ConnectionLike for ConnectionManager {
     }
 
     fn get_db(&self) -> i64 {
-        self.0.client.connection_info().redis.db
+        self.0.target.redis_connection_info().db
     }
 }
@@ -129,7 +129,7 @@ where
 pub(super) async fn setup_connection<T>(
     codec: &mut T,
     connection_info: &RedisConnectionInfo,
-    #[cfg(feature = "cache-aio")] cache_config: Option<crate::caching::CacheConfig>,
+    #[cfg(feature = "cache")] cache_config: Option<&crate::caching::CacheConfig>,
 ) -> RedisResult<()>
 where
     T: Sink<Vec<u8>, Error = RedisError>,
@@ -141,7 +141,7 @@ where
         connection_setup_pipeline(
             connection_info,
             true,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_config,
         ),
     )
@@ -153,7 +153,7 @@ where
             connection_setup_pipeline(
                 connection_info,
                 false,
-                #[cfg(feature = "cache-aio")]
+                #[cfg(feature = "cache")]
                 cache_config,
             ),
         )
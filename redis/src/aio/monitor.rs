@@ -31,7 +31,7 @@ impl Monitor {
         setup_connection(
             &mut codec,
             connection_info,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             None,
         )
         .await?;
@@ -1,7 +1,7 @@
 use super::{AsyncPushSender, ConnectionLike, Runtime, SharedHandleContainer, TaskHandle};
 use crate::aio::{check_resp3, setup_connection};
-#[cfg(feature = "cache-aio")]
-use crate::caching::{CacheManager, CacheStatistics, PrepareCacheResult};
+#[cfg(feature = "cache")]
+use crate::caching::{cmd::CacheableCommand, CacheManager, CacheStatistics, PrepareCacheResult};
 use crate::cmd::Cmd;
 use crate::parser::ValueCodec;
 use crate::types::{closed_connection_error, RedisError, RedisFuture, RedisResult, Value};
@@ -93,7 +93,7 @@ impl Debug for Pipeline {
     }
 }
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 pin_project! {
     struct PipelineSink<T> {
         #[pin]
@@ -105,7 +105,7 @@ pin_project! {
     }
 }
 
-#[cfg(not(feature = "cache-aio"))]
+#[cfg(not(feature = "cache"))]
 pin_project! {
     struct PipelineSink<T> {
         #[pin]
@@ -133,7 +133,7 @@ where
     fn new(
         sink_stream: T,
         push_sender: Option<Arc<dyn AsyncPushSender>>,
-        #[cfg(feature = "cache-aio")] cache_manager: Option<CacheManager>,
+        #[cfg(feature = "cache")] cache_manager: Option<CacheManager>,
     ) -> Self
     where
         T: Sink<Vec<u8>, Error = RedisError> + Stream<Item = RedisResult<Value>> + 'static,
@@ -143,7 +143,7 @@ where
             in_flight: VecDeque::new(),
             error: None,
             push_sender,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_manager,
         }
     }
@@ -173,7 +173,7 @@ where
         let result = match result {
             // If this push message isn't a reply, we'll pass it as-is to the push manager and stop iterating
             Ok(Value::Push { kind, data }) if !kind.has_reply() => {
-                #[cfg(feature = "cache-aio")]
+                #[cfg(feature = "cache")]
                 if let Some(cache_manager) = &self_.cache_manager {
                     cache_manager.handle_push_value(&kind, &data);
                 }
@@ -352,7 +352,7 @@ impl Pipeline {
     fn new<T>(
         sink_stream: T,
         push_sender: Option<Arc<dyn AsyncPushSender>>,
-        #[cfg(feature = "cache-aio")] cache_manager: Option<CacheManager>,
+        #[cfg(feature = "cache")] cache_manager: Option<CacheManager>,
     ) -> (Self, impl Future<Output = ()>)
     where
         T: Sink<Vec<u8>, Error = RedisError>,
@@ -365,7 +365,7 @@ impl Pipeline {
         let sink = PipelineSink::new(
             sink_stream,
             push_sender,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_manager,
         );
         let f = stream::poll_fn(move |cx| receiver.poll_recv(cx))
@@ -411,6 +411,31 @@ impl Pipeline {
         }
         .map_err(|err| err.unwrap_or_else(closed_connection_error))
     }
+
+    #[cfg(feature = "cache")]
+    async fn send_cacheable_command(
+        &mut self,
+        cache_manager: &CacheManager,
+        cacheable_command: CacheableCommand<'_>,
+        timeout: Option<Duration>,
+    ) -> RedisResult<Value> {
+        let mut pipeline = crate::Pipeline::new();
+        cacheable_command.pack_command(cache_manager, &mut pipeline);
+
+        let result = self
+            .send_recv(
+                pipeline.get_packed_pipeline(),
+                Some(PipelineResponseExpectation {
+                    skipped_response_count: 0,
+                    expected_response_count: pipeline.commands.len(),
+                    is_transaction: false,
+                }),
+                timeout,
+            )
+            .await?;
+        let replies: Vec<Value> = crate::types::from_owned_redis_value(result)?;
+        cacheable_command.resolve(cache_manager, replies.into_iter())
+    }
 }
 
 /// A connection object which can be cloned, allowing requests to be be sent concurrently
@@ -435,7 +460,7 @@ pub struct MultiplexedConnection {
     // This handle is only set for connection whose task was spawned by the crate, not for users who spawned their own
     // task.
     _task_handle: Option<SharedHandleContainer>,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     pub(crate) cache_manager: Option<CacheManager>,
 }
 
@@ -500,13 +525,12 @@ impl MultiplexedConnection {
             );
         }
 
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         let cache_config = config.cache.as_ref().map(|cache| match cache {
-            crate::client::Cache::Config(cache_config) => *cache_config,
-            #[cfg(any(feature = "connection-manager", feature = "cluster-async"))]
-            crate::client::Cache::Manager(cache_manager) => cache_manager.cache_config,
+            crate::client::Cache::Config(cache_config) => cache_config.clone(),
+            crate::client::Cache::Manager(cache_manager) => (*cache_manager.cache_config).clone(),
         });
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         let cache_manager_opt = config
             .cache
             .map(|cache| {
@@ -518,7 +542,6 @@ impl MultiplexedConnection {
                     crate::client::Cache::Config(cache_config) => {
                         Ok(CacheManager::new(cache_config))
                     }
-                    #[cfg(any(feature = "connection-manager", feature = "cluster-async"))]
                     crate::client::Cache::Manager(cache_manager) => Ok(cache_manager),
                 }
             })
@@ -527,8 +550,8 @@ impl MultiplexedConnection {
         setup_connection(
             &mut codec,
             connection_info,
-            #[cfg(feature = "cache-aio")]
-            cache_config,
+            #[cfg(feature = "cache")]
+            cache_config.as_ref(),
         )
         .await?;
         if config.push_sender.is_some() {
@@ -541,7 +564,7 @@ impl MultiplexedConnection {
         let (pipeline, driver) = Pipeline::new(
             codec,
             config.push_sender,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_manager_opt.clone(),
         );
         let con = MultiplexedConnection {
@@ -550,7 +573,7 @@ impl MultiplexedConnection {
             response_timeout: config.response_timeout,
             protocol: connection_info.protocol,
             _task_handle: None,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_manager: cache_manager_opt,
         };
 
@@ -563,6 +586,12 @@ impl MultiplexedConnection {
         self._task_handle = Some(SharedHandleContainer::new(handle));
     }
 
+    /// Returns true if the task driving the connection has stopped, so the connection can't be used anymore.
+    #[cfg(feature = "deadpool")]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.pipeline.sender.is_closed()
+    }
+
     /// Sets the time that the multiplexer will wait for responses on operations before failing.
     pub fn set_response_timeout(&mut self, timeout: std::time::Duration) {
         self.response_timeout = Some(timeout);
@@ -571,28 +600,44 @@ impl MultiplexedConnection {
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
     pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         if let Some(cache_manager) = &self.cache_manager {
-            match cache_manager.get_cached_cmd(cmd) {
+            match cache_manager.get_cached_cmd_allowing_stale(cmd) {
                 PrepareCacheResult::Cached(value) => return Ok(value),
+                PrepareCacheResult::Stale(value) => {
+                    if let Some(refresh_guard) = cache_manager.start_refresh(&cmd.data) {
+                        let cache_manager = cache_manager.clone();
+                        let mut pipeline = self.pipeline.clone();
+                        let response_timeout = self.response_timeout;
+                        let cmd = cmd.clone();
+                        Runtime::locate()
+                            .spawn(async move {
+                                let _refresh_guard = refresh_guard;
+                                if let PrepareCacheResult::NotCached(cacheable_command) =
+                                    cache_manager.get_refresh_cmd(&cmd)
+                                {
+                                    let _ = pipeline
+                                        .send_cacheable_command(
+                                            &cache_manager,
+                                            cacheable_command,
+                                            response_timeout,
+                                        )
+                                        .await;
+                                }
+                            })
+                            .detach();
+                    }
+                    return Ok(value);
+                }
                 PrepareCacheResult::NotCached(cacheable_command) => {
-                    let mut pipeline = crate::Pipeline::new();
-                    cacheable_command.pack_command(cache_manager, &mut pipeline);
-
-                    let result = self
+                    return self
                         .pipeline
-                        .send_recv(
-                            pipeline.get_packed_pipeline(),
-                            Some(PipelineResponseExpectation {
-                                skipped_response_count: 0,
-                                expected_response_count: pipeline.commands.len(),
-                                is_transaction: false,
-                            }),
+                        .send_cacheable_command(
+                            cache_manager,
+                            cacheable_command,
                             self.response_timeout,
                         )
-                        .await?;
-                    let replies: Vec<Value> = crate::types::from_owned_redis_value(result)?;
-                    return cacheable_command.resolve(cache_manager, replies.into_iter());
+                        .await;
                 }
                 _ => (),
             }
@@ -611,7 +656,7 @@ impl MultiplexedConnection {
         offset: usize,
         count: usize,
     ) -> RedisResult<Vec<Value>> {
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         if let Some(cache_manager) = &self.cache_manager {
             let (cacheable_pipeline, pipeline, (skipped_response_count, expected_response_count)) =
                 cache_manager.get_cached_pipeline(cmd);
@@ -649,11 +694,58 @@ impl MultiplexedConnection {
     }
 
     /// Gets [`CacheStatistics`] for current connection if caching is enabled.
-    #[cfg(feature = "cache-aio")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
     pub fn get_cache_statistics(&self) -> Option<CacheStatistics> {
         self.cache_manager.as_ref().map(|cm| cm.statistics())
     }
+
+    /// Removes given keys from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_keys<K: ToRedisArgs>(&self, keys: K) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.invalidate_keys(&keys.to_redis_args());
+        }
+    }
+
+    /// Removes keys matching the glob-style pattern from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_pattern(&self, pattern: impl AsRef<[u8]>) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.invalidate_pattern(pattern.as_ref());
+        }
+    }
+
+    /// Removes every value from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn flush_cache(&self) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.flush();
+        }
+    }
+
+    /// Reads given keys with `GET` and stores their values in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_keys<K: ToRedisArgs>(&mut self, keys: K) -> RedisResult<()> {
+        let commands = crate::caching::cmd::prefetch_key_commands(keys.to_redis_args());
+        self.prefetch_cache_commands(&commands).await
+    }
+
+    /// Sends given commands and stores their replies in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_commands(&mut self, commands: &[Cmd]) -> RedisResult<()> {
+        if self.cache_manager.is_none() || commands.is_empty() {
+            return Ok(());
+        }
+        let pipeline = crate::caching::cmd::prefetch_pipeline(commands);
+        let _: Vec<Value> = pipeline.query_async(self).await?;
+        Ok(())
+    }
 }
 
 impl ConnectionLike for MultiplexedConnection {
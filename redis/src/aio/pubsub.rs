@@ -385,7 +385,7 @@ impl PubSub {
         setup_connection(
             &mut codec,
             connection_info,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             None,
         )
         .await?;
@@ -48,6 +48,21 @@ pub(crate) enum TaskHandle {
     Smol(smol::Task<()>),
 }
 
+impl TaskHandle {
+    /// Lets the task run in the background after the handle is dropped.
+    #[cfg(feature = "cache")]
+    pub(crate) fn detach(self) {
+        match self {
+            #[cfg(feature = "tokio-comp")]
+            TaskHandle::Tokio(_) => {}
+            #[cfg(feature = "async-std-comp")]
+            TaskHandle::AsyncStd(_) => {}
+            #[cfg(feature = "smol-comp")]
+            TaskHandle::Smol(task) => task.detach(),
+        }
+    }
+}
+
 pub(crate) struct HandleContainer(Option<TaskHandle>);
 
 impl HandleContainer {
@@ -1,53 +1,154 @@
 use super::cmd::{CacheableCommand, CacheablePipeline, MultipleCachedCommandPart};
+use super::commands::KeySpec;
 use super::sharded_lru::*;
-use super::{CacheConfig, CacheMode, CacheStatistics};
+use super::statistics::DetailedStatistics;
+use super::{CacheConfig, CacheEpoch, CacheMode, CacheStatistics, CacheStorage, InvalidationEvent};
 use crate::cmd::{cmd_len, Cmd};
-use crate::commands::is_readonly_cmd;
 use crate::{Pipeline, PushKind, RedisResult, Value};
+use std::borrow::Cow;
 use std::cmp::min;
+#[cfg(feature = "aio")]
+use std::collections::HashSet;
 use std::ops::Add;
 use std::sync::Arc;
+#[cfg(feature = "aio")]
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
+#[cfg_attr(not(feature = "aio"), allow(dead_code))]
 pub(crate) enum PrepareCacheResult<'a> {
     Cached(Value),
+    /// Value is expired but it can be used while it's refreshed, only returned by [`CacheManager::get_cached_cmd_allowing_stale`].
+    Stale(Value),
     NotCached(CacheableCommand<'a>),
     NotCacheable,
     Ignored,
 }
 
+/// Decides how cached values are used while preparing a command.
+#[derive(Clone, Copy, PartialEq, Eq)]
+// Only async connections can refresh values in the background.
+#[cfg_attr(not(feature = "aio"), allow(dead_code))]
+enum CacheLookup {
+    /// Only values which aren't expired are used.
+    Fresh,
+    /// Expired values within stale-while-revalidate duration are used too.
+    AllowStale,
+    /// Cache isn't read, so the value is fetched from the server and cached again.
+    Refresh,
+}
+
 #[derive(Clone)]
 pub(crate) struct CacheManager {
-    lru: Arc<ShardedLRU>,
-    pub(crate) cache_config: CacheConfig,
-    epoch: usize,
+    storage: Arc<dyn CacheStorage>,
+    pub(crate) cache_config: Arc<CacheConfig>,
+    // Cached values are trusted while the connection which owns this is alive, see [`CacheEpoch`].
+    epoch: Arc<()>,
+    // Prefix of the redis keys in a custom storage, which keeps the values of different servers and databases apart
+    // when the storage is shared between clients.
+    scope: Option<Arc<[u8]>>,
+    detailed_statistics: Arc<DetailedStatistics>,
+    // Command keys which are being refreshed in the background.
+    #[cfg(feature = "aio")]
+    refreshing: Arc<Mutex<HashSet<Vec<u8>>>>,
+}
+
+/// Marks a command as being refreshed in the background, returned by [`CacheManager::start_refresh`].
+///
+/// The command is unmarked once this is dropped, so it can be refreshed again even if the refresh fails or panics.
+#[cfg(feature = "aio")]
+pub(crate) struct RefreshGuard {
+    refreshing: Arc<Mutex<HashSet<Vec<u8>>>>,
+    cmd_key: Vec<u8>,
+}
+
+#[cfg(feature = "aio")]
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        self.refreshing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.cmd_key);
+    }
 }
 
 impl CacheManager {
     pub(crate) fn new(cache_config: CacheConfig) -> Self {
-        let lru = Arc::new(ShardedLRU::new(cache_config.size));
-        let epoch = lru.increase_epoch();
+        let storage = match &cache_config.storage {
+            Some(storage) => storage.clone(),
+            None => Arc::new(ShardedLRU::new(cache_config.size, cache_config.max_memory)),
+        };
+        let detailed_statistics = Arc::new(DetailedStatistics::new(&cache_config));
         CacheManager {
-            lru,
-            cache_config,
-            epoch,
+            storage,
+            cache_config: Arc::new(cache_config),
+            epoch: Arc::new(()),
+            scope: None,
+            detailed_statistics,
+            #[cfg(feature = "aio")]
+            refreshing: Default::default(),
         }
     }
 
-    // Clone the CacheManager and increase epoch from LRU,
-    // this will eventually remove all keys created with previous
-    // CacheManager's epoch.
-    #[cfg(any(feature = "connection-manager", feature = "cluster-async"))]
+    /// Scopes the values in a custom storage to the server (or cluster) and database, so clients which share the
+    /// storage only serve values of the same database to each other.
+    pub(crate) fn with_scope(mut self, server: &str, db: i64) -> Self {
+        if self.cache_config.storage.is_some() {
+            // The database is terminated by `/` and the server is prefixed by its length, so no scope is a prefix
+            // of another scope.
+            let scope = format!("{db}/{}:{server}", server.len());
+            self.scope = Some(scope.into_bytes().into());
+        }
+        self
+    }
+
+    /// Returns the key of `redis_key` in the storage.
+    fn storage_key<'a>(&self, redis_key: &'a [u8]) -> Cow<'a, [u8]> {
+        match &self.scope {
+            Some(scope) => Cow::Owned([scope, redis_key].concat()),
+            None => Cow::Borrowed(redis_key),
+        }
+    }
+
+    // Clone the CacheManager with a new epoch for a new connection,
+    // keys created with previous connection's epoch are removed
+    // once that connection is dropped.
+    #[cfg(any(feature = "connection-manager", feature = "cluster"))]
     pub(crate) fn clone_and_increase_epoch(&self) -> CacheManager {
         CacheManager {
-            lru: self.lru.clone(),
-            cache_config: self.cache_config,
-            epoch: self.lru.increase_epoch(),
+            storage: self.storage.clone(),
+            cache_config: self.cache_config.clone(),
+            epoch: Arc::new(()),
+            scope: self.scope.clone(),
+            detailed_statistics: self.detailed_statistics.clone(),
+            #[cfg(feature = "aio")]
+            refreshing: Default::default(),
         }
     }
 
     pub(crate) fn get<'a>(&self, redis_key: &'a [u8], redis_cmd: &'a [u8]) -> Option<Value> {
-        self.lru.get(redis_key, redis_cmd, self.epoch)
+        self.lookup(redis_key, redis_cmd, false)
+            .map(|(value, _)| value)
+    }
+
+    /// Returns the cached value, and whether it's stale (expired but still within stale-while-revalidate duration).
+    /// Stale values are only returned if `allow_stale` is true, otherwise they're counted as misses.
+    fn lookup(
+        &self,
+        redis_key: &[u8],
+        redis_cmd: &[u8],
+        allow_stale: bool,
+    ) -> Option<(Value, bool)> {
+        let now = Instant::now();
+        // Values expired within this client's stale-while-revalidate duration are still usable as stale values.
+        let min_expire_time = match (allow_stale, self.cache_config.stale_while_revalidate) {
+            (true, Some(duration)) => now.checked_sub(duration).unwrap_or(now),
+            _ => now,
+        };
+        let entry = self
+            .storage
+            .get(&self.storage_key(redis_key), redis_cmd, min_expire_time)?;
+        Some((entry.value, now > entry.expire_time))
     }
 
     pub(crate) fn insert(
@@ -67,36 +168,129 @@ impl CacheManager {
             }
             _ => client_side_expire_time,
         };
-        self.lru
-            .insert(redis_key, cmd_key, value, expire_time, self.epoch);
+        let expire_time = match (&value, self.cache_config.nil_ttl) {
+            (Value::Nil, Some(nil_ttl)) if nil_ttl.is_zero() => return,
+            (Value::Nil, Some(nil_ttl)) => min(Instant::now().add(nil_ttl), expire_time),
+            _ => expire_time,
+        };
+        let stale_until = match self.cache_config.stale_while_revalidate {
+            Some(duration) => expire_time.add(duration),
+            None => expire_time,
+        };
+        self.storage.insert(
+            &self.storage_key(redis_key),
+            cmd_key,
+            value,
+            expire_time,
+            stale_until,
+            CacheEpoch::new(&self.epoch),
+        );
+    }
+
+    /// Marks the command as being refreshed until the returned guard is dropped,
+    /// returns None if it's already being refreshed.
+    #[cfg(feature = "aio")]
+    pub(crate) fn start_refresh(&self, cmd_key: &[u8]) -> Option<RefreshGuard> {
+        let started = self
+            .refreshing
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(cmd_key.to_vec());
+        started.then(|| RefreshGuard {
+            refreshing: self.refreshing.clone(),
+            cmd_key: cmd_key.to_vec(),
+        })
     }
 
     pub(crate) fn statistics(&self) -> CacheStatistics {
-        self.lru.statistics.clone().into()
+        let mut statistics = self.storage.statistics();
+        self.detailed_statistics.fill(&mut statistics);
+        statistics
+    }
+
+    pub(crate) fn invalidate_keys(&self, redis_keys: &[Vec<u8>]) {
+        for redis_key in redis_keys {
+            self.storage.invalidate(&self.storage_key(redis_key));
+        }
+    }
+
+    pub(crate) fn invalidate_pattern(&self, pattern: &[u8]) {
+        match &self.scope {
+            Some(scope) => self.storage.invalidate_matching(&|storage_key| {
+                storage_key
+                    .strip_prefix(&scope[..])
+                    .is_some_and(|redis_key| glob_match(pattern, redis_key))
+            }),
+            None => self
+                .storage
+                .invalidate_matching(&|redis_key| glob_match(pattern, redis_key)),
+        }
+    }
+
+    /// Removes the values of the scope, or every value if the storage isn't scoped.
+    pub(crate) fn flush(&self) {
+        match &self.scope {
+            Some(scope) => self
+                .storage
+                .invalidate_matching(&|storage_key| storage_key.starts_with(scope)),
+            None => self.storage.flush(),
+        }
     }
 
     pub(crate) fn handle_push_value(&self, kind: &PushKind, data: &[Value]) {
-        if kind == &PushKind::Invalidate {
-            if let Some(Value::Array(redis_key)) = data.first() {
-                if let Some(redis_key) = redis_key.first() {
-                    if let Ok(redis_key) = crate::FromRedisValue::from_redis_value(redis_key) {
-                        self.lru.invalidate(&redis_key)
-                    }
+        if kind != &PushKind::Invalidate {
+            return;
+        }
+        match data.first() {
+            // Server might send multiple keys in a single message, especially in broadcasting mode.
+            Some(Value::Array(redis_keys)) => {
+                let redis_keys: Vec<Vec<u8>> = redis_keys
+                    .iter()
+                    .filter_map(|redis_key| crate::FromRedisValue::from_redis_value(redis_key).ok())
+                    .collect();
+                self.invalidate_keys(&redis_keys);
+                if let Some(sender) = &self.cache_config.invalidation_sender {
+                    sender.send(InvalidationEvent::Keys(redis_keys));
+                }
+            }
+            // Null is sent when the whole database is flushed (FLUSHALL/FLUSHDB).
+            Some(Value::Nil) => {
+                self.flush();
+                if let Some(sender) = &self.cache_config.invalidation_sender {
+                    sender.send(InvalidationEvent::Flush);
                 }
             }
+            _ => {}
         }
     }
 
     pub(crate) fn get_cached_cmd<'a>(&self, cmd: &'a Cmd) -> PrepareCacheResult<'a> {
+        self.prepare_cmd(cmd, CacheLookup::Fresh)
+    }
+
+    /// Same as [`CacheManager::get_cached_cmd`] but it returns [`PrepareCacheResult::Stale`] for single key commands
+    /// which are expired within stale-while-revalidate duration, caller must refresh these with [`CacheManager::get_refresh_cmd`].
+    #[cfg(feature = "aio")]
+    pub(crate) fn get_cached_cmd_allowing_stale<'a>(&self, cmd: &'a Cmd) -> PrepareCacheResult<'a> {
+        self.prepare_cmd(cmd, CacheLookup::AllowStale)
+    }
+
+    /// Prepares the command to be fetched from the server without reading the cache, so its cached value is replaced.
+    #[cfg(feature = "aio")]
+    pub(crate) fn get_refresh_cmd<'a>(&self, cmd: &'a Cmd) -> PrepareCacheResult<'a> {
+        self.prepare_cmd(cmd, CacheLookup::Refresh)
+    }
+
+    fn prepare_cmd<'a>(&self, cmd: &'a Cmd, lookup: CacheLookup) -> PrepareCacheResult<'a> {
         match self.cache_config.mode {
-            CacheMode::All => self.get_cached_cmd_inner(cmd),
+            CacheMode::All => self.get_cached_cmd_inner(cmd, lookup),
             CacheMode::OptIn => {
                 let has_opt_in = cmd
                     .get_cache_config()
                     .as_ref()
                     .is_some_and(|c| c.enable_cache);
                 if has_opt_in {
-                    self.get_cached_cmd_inner(cmd)
+                    self.get_cached_cmd_inner(cmd, lookup)
                 } else {
                     PrepareCacheResult::NotCacheable
                 }
@@ -119,13 +313,13 @@ impl CacheManager {
         buffer: &mut Vec<u8>,
         single_command_name: &[u8],
         redis_key: &[u8],
-        json_path_key: Option<&[u8]>,
+        tail_args: &[&[u8]],
     ) {
         buffer.clear();
         buffer.extend_from_slice(single_command_name);
         buffer.extend_from_slice(redis_key);
-        if let Some(json_path_key) = json_path_key {
-            buffer.extend_from_slice(json_path_key);
+        for tail_arg in tail_args {
+            buffer.extend_from_slice(tail_arg);
         }
     }
 
@@ -139,35 +333,42 @@ impl CacheManager {
             .collect()
     }
 
-    fn process_multi_key_arguments<'a>(
+    fn handle_multi_key_command<'a>(
         &self,
         cmd: &'a Cmd,
-        is_json_command: bool,
+        command_name: &'a [u8],
         single_command_name: &[u8],
-        commands: &mut Vec<(usize, MultipleCachedCommandPart<'a>)>,
-        response: &mut Vec<Value>,
-        tail_args: &mut Vec<&'a [u8]>,
-    ) {
-        let mut arguments = self.extract_simple_arguments(cmd);
-        let json_path_key = is_json_command
-            .then(|| {
-                arguments.pop().map(|k| {
-                    tail_args.push(k);
-                    k
-                })
-            })
-            .flatten();
-        let mut key_test_buffer: Vec<u8> = Vec::new();
+        trailing_args: usize,
+        client_side_expire: Instant,
+    ) -> PrepareCacheResult<'a> {
+        let mut redis_keys = self.extract_simple_arguments(cmd);
+        if redis_keys.len() <= trailing_args {
+            return PrepareCacheResult::NotCacheable;
+        }
+        let tail_args = redis_keys.split_off(redis_keys.len() - trailing_args);
+
+        if !redis_keys
+            .iter()
+            .all(|redis_key| self.cache_config.is_key_tracked(redis_key))
+        {
+            return PrepareCacheResult::NotCacheable;
+        }
 
-        for (i, redis_key) in arguments.iter().enumerate() {
+        let mut commands = Vec::new();
+        let mut response = Vec::new();
+        let mut key_test_buffer: Vec<u8> = Vec::new();
+        for (i, redis_key) in redis_keys.into_iter().enumerate() {
             self.prepare_key_buffer(
                 &mut key_test_buffer,
                 single_command_name,
                 redis_key,
-                json_path_key,
+                &tail_args,
             );
 
-            match self.get(redis_key, &key_test_buffer) {
+            let cached_value = self.get(redis_key, &key_test_buffer);
+            self.detailed_statistics
+                .record(command_name, redis_key, cached_value.is_some());
+            match cached_value {
                 Some(value) => response.push(value),
                 None => {
                     response.push(Value::Nil);
@@ -181,36 +382,13 @@ impl CacheManager {
                 }
             }
         }
-    }
-
-    fn handle_multi_key_command<'a>(
-        &self,
-        cmd: &'a Cmd,
-        command_name_str: &'a str,
-        single_command_name: &[u8],
-        client_side_expire: Instant,
-    ) -> PrepareCacheResult<'a> {
-        let mut commands = Vec::new();
-        let mut tail_args: Vec<&'a [u8]> = Vec::new();
-        let mut response = Vec::new();
-
-        let is_json_command = command_name_str.starts_with("JSON");
-
-        self.process_multi_key_arguments(
-            cmd,
-            is_json_command,
-            single_command_name,
-            &mut commands,
-            &mut response,
-            &mut tail_args,
-        );
 
         if commands.is_empty() {
             return PrepareCacheResult::Cached(Value::Array(response));
         }
 
         PrepareCacheResult::NotCached(CacheableCommand::Multiple {
-            command_name: command_name_str,
+            command_name,
             commands,
             response,
             client_side_expire,
@@ -221,17 +399,35 @@ impl CacheManager {
     fn handle_single_key_command<'a>(
         &self,
         cmd: &'a Cmd,
+        key_index: usize,
         client_side_expire: Instant,
+        lookup: CacheLookup,
     ) -> PrepareCacheResult<'a> {
-        let redis_key = match cmd.arg_idx(1) {
-            Some(key) => key,
-            None => return PrepareCacheResult::NotCacheable,
+        let redis_key = match cmd.arg_idx(key_index) {
+            Some(key) if key_index > 0 => key,
+            _ => return PrepareCacheResult::NotCacheable,
         };
 
+        if !self.cache_config.is_key_tracked(redis_key) {
+            return PrepareCacheResult::NotCacheable;
+        }
+
         let cmd_key = cmd.data.as_slice();
 
-        if let Some(value) = self.get(redis_key, cmd_key) {
-            return PrepareCacheResult::Cached(value);
+        if lookup != CacheLookup::Refresh {
+            let command_name = cmd.arg_idx(0).unwrap_or_default();
+            let allow_stale = lookup == CacheLookup::AllowStale;
+            let result = self
+                .lookup(redis_key, cmd_key, allow_stale)
+                .map(|(value, is_stale)| match is_stale {
+                    true => PrepareCacheResult::Stale(value),
+                    false => PrepareCacheResult::Cached(value),
+                });
+            self.detailed_statistics
+                .record(command_name, redis_key, result.is_some());
+            if let Some(result) = result {
+                return result;
+            }
         }
 
         PrepareCacheResult::NotCached(CacheableCommand::Single(
@@ -249,7 +445,11 @@ impl CacheManager {
     /// If there isn't enough information in cache but Cmd is cacheable then packs enough information
     /// into CacheableCommand and returns PrepareCacheResult::NotCached.
     /// If Cmd doesn't support client side caching then it returns PrepareCacheResult::NotCacheable.
-    fn get_cached_cmd_inner<'a>(&self, cmd: &'a Cmd) -> PrepareCacheResult<'a> {
+    fn get_cached_cmd_inner<'a>(
+        &self,
+        cmd: &'a Cmd,
+        lookup: CacheLookup,
+    ) -> PrepareCacheResult<'a> {
         if cmd_len(cmd) < 2 {
             return PrepareCacheResult::NotCacheable;
         }
@@ -261,20 +461,22 @@ impl CacheManager {
 
         let client_side_expire = self.calculate_expiration_time(cmd);
 
-        if let Some((command_name_str, single_command_name)) = is_multi_key(command_name) {
-            return self.handle_multi_key_command(
+        match self.cache_config.key_spec(command_name) {
+            Some(KeySpec::Single { key_index }) => {
+                self.handle_single_key_command(cmd, key_index, client_side_expire, lookup)
+            }
+            Some(KeySpec::Multiple {
+                single_command,
+                trailing_args,
+            }) => self.handle_multi_key_command(
                 cmd,
-                command_name_str,
-                single_command_name,
+                command_name,
+                single_command,
+                trailing_args,
                 client_side_expire,
-            );
+            ),
+            None => PrepareCacheResult::NotCacheable,
         }
-
-        if is_readonly_cmd(command_name) {
-            return self.handle_single_key_command(cmd, client_side_expire);
-        }
-
-        PrepareCacheResult::NotCacheable
     }
 
     /// Creates new Pipeline and stores enough information in CacheablePipeline
@@ -323,20 +525,85 @@ impl CacheManager {
     }
 }
 
-/// Checks if the given command is cacheable and supports multiple keys.
-/// If the command is cacheable, it returns a tuple containing the command name as a string
-/// and the single variant of the command (e.g., "MGET" maps to "GET").
-fn is_multi_key(command_name: &[u8]) -> Option<(&'static str, &'static [u8])> {
-    match command_name {
-        b"MGET" => Some(("MGET", b"GET")),
-        b"JSON.MGET" => Some(("JSON.MGET", b"JSON.GET")),
-        _ => None,
+/// Matches the string against a glob-style pattern in the same way as `KEYS` command,
+/// `*`, `?`, `[abc]`, `[^abc]`, `[a-z]` and `\` escapes are supported.
+fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Position in the pattern after the last `*` and position in the string it started matching from.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while s < string.len() {
+        if p < pattern.len() {
+            let consumed = match pattern[p] {
+                b'*' => {
+                    backtrack = Some((p + 1, s));
+                    p += 1;
+                    continue;
+                }
+                b'?' => Some(1),
+                b'[' => match match_class(&pattern[p..], string[s]) {
+                    Some((matched, class_len)) => matched.then_some(class_len),
+                    // Unterminated class is matched literally.
+                    None => (string[s] == b'[').then_some(1),
+                },
+                b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == string[s]).then_some(2),
+                c => (c == string[s]).then_some(1),
+            };
+            if let Some(consumed) = consumed {
+                p += consumed;
+                s += 1;
+                continue;
+            }
+        }
+        match backtrack {
+            Some((star_p, star_s)) => {
+                backtrack = Some((star_p, star_s + 1));
+                p = star_p;
+                s = star_s + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Checks the byte against the character class at the start of the pattern,
+/// returns whether it matches and the length of the class or None if the class is unterminated.
+fn match_class(pattern: &[u8], byte: u8) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = pattern.get(i) == Some(&b'^');
+    if negate {
+        i += 1;
+    }
+    let mut matched = false;
+    loop {
+        match *pattern.get(i)? {
+            b']' => break,
+            b'\\' => {
+                matched |= *pattern.get(i + 1)? == byte;
+                i += 2;
+            }
+            start
+                if pattern.get(i + 1) == Some(&b'-')
+                    && pattern.get(i + 2).is_some_and(|end| *end != b']') =>
+            {
+                let end = pattern[i + 2];
+                matched |= (start.min(end)..=start.max(end)).contains(&byte);
+                i += 3;
+            }
+            c => {
+                matched |= c == byte;
+                i += 1;
+            }
+        }
     }
+    Some((matched != negate, i + 1))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::caching::{CacheHitStatistics, CommandKeys};
+    use std::collections::HashMap;
 
     #[test]
     fn test_custom_command_ttl() {
@@ -376,6 +643,510 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_nil_ttl() {
+        let redis_key = b"test_redis_key".as_slice();
+        let redis_key_2 = b"test_redis_key_2".as_slice();
+        let cmd_key = b"test_cmd_key".as_slice();
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+
+        let cache_manager =
+            CacheManager::new(CacheConfig::new().set_nil_ttl(Duration::from_millis(5)));
+        cache_manager.insert(redis_key, cmd_key, Value::Nil, secs_10, &Value::Int(-2));
+        cache_manager.insert(
+            redis_key_2,
+            cmd_key,
+            Value::Int(1),
+            secs_10,
+            &Value::Int(-1),
+        );
+        assert_eq!(cache_manager.get(redis_key, cmd_key), Some(Value::Nil));
+        std::thread::sleep(Duration::from_millis(6));
+        assert_eq!(
+            cache_manager.get(redis_key, cmd_key),
+            None,
+            "Nil must be expired with its own TTL"
+        );
+        assert_eq!(cache_manager.get(redis_key_2, cmd_key), Some(Value::Int(1)));
+
+        let cache_manager = CacheManager::new(CacheConfig::new().set_nil_ttl(Duration::ZERO));
+        cache_manager.insert(redis_key, cmd_key, Value::Nil, secs_10, &Value::Int(-2));
+        assert_eq!(
+            cache_manager.get(redis_key, cmd_key),
+            None,
+            "Nil must not be cached"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "aio")]
+    fn test_stale_while_revalidate() {
+        let cache_manager = CacheManager::new(
+            CacheConfig::new().set_stale_while_revalidate(Duration::from_millis(50)),
+        );
+        let mut cmd = crate::cmd("GET");
+        cmd.arg("test_redis_key");
+        let is_stale = |result: PrepareCacheResult| match result {
+            PrepareCacheResult::Cached(_) => false,
+            PrepareCacheResult::Stale(_) => true,
+            _ => panic!("Value must be returned from the cache"),
+        };
+
+        cache_manager.insert(
+            b"test_redis_key",
+            &cmd.data,
+            Value::Int(1),
+            Instant::now().add(Duration::from_millis(5)),
+            &Value::Int(-1),
+        );
+        assert!(!is_stale(cache_manager.get_cached_cmd_allowing_stale(&cmd)));
+        assert!(!is_stale(cache_manager.get_cached_cmd(&cmd)));
+
+        std::thread::sleep(Duration::from_millis(6));
+        assert!(is_stale(cache_manager.get_cached_cmd_allowing_stale(&cmd)));
+        assert!(
+            matches!(
+                cache_manager.get_cached_cmd(&cmd),
+                PrepareCacheResult::NotCached(_)
+            ),
+            "Stale value must only be used if it's allowed"
+        );
+        assert!(matches!(
+            cache_manager.get_refresh_cmd(&cmd),
+            PrepareCacheResult::NotCached(_)
+        ));
+        let statistics = cache_manager.statistics();
+        assert_eq!(statistics.hit, 3);
+        assert_eq!(
+            statistics.miss, 1,
+            "Rejected stale value must be counted as a miss"
+        );
+
+        let refresh_guard = cache_manager.start_refresh(&cmd.data);
+        assert!(refresh_guard.is_some());
+        assert!(
+            cache_manager.start_refresh(&cmd.data).is_none(),
+            "Command must be refreshed only once at a time"
+        );
+        drop(refresh_guard);
+        assert!(cache_manager.start_refresh(&cmd.data).is_some());
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(matches!(
+            cache_manager.get_cached_cmd_allowing_stale(&cmd),
+            PrepareCacheResult::NotCached(_)
+        ));
+    }
+
+    #[test]
+    fn test_detailed_statistics() {
+        let cache_manager = CacheManager::new(
+            CacheConfig::new()
+                .set_command_statistics(true)
+                .set_prefix_statistics(["user:", "user:admin:"]),
+        );
+        let expire_time = Instant::now().add(Duration::from_secs(10));
+        let get_cmd = crate::cmd("GET").arg("user:admin:1").clone();
+        cache_manager.insert(
+            b"user:admin:1",
+            &get_cmd.data,
+            Value::Int(1),
+            expire_time,
+            &Value::Int(-1),
+        );
+
+        let _ = cache_manager.get_cached_cmd(&get_cmd);
+        let _ = cache_manager.get_cached_cmd(crate::cmd("HGET").arg("user:2").arg("name"));
+        let _ = cache_manager.get_cached_cmd(crate::cmd("MGET").arg("user:admin:1").arg("cfg"));
+
+        let statistics = cache_manager.statistics();
+        assert_eq!(statistics.hit, 2);
+        assert_eq!(statistics.miss, 2);
+        assert_eq!(statistics.entry_count, 1);
+        assert_eq!(statistics.hit_ratio(), 0.5);
+        assert_eq!(
+            statistics.commands,
+            HashMap::from([
+                ("GET".to_string(), CacheHitStatistics { hit: 1, miss: 0 }),
+                ("HGET".to_string(), CacheHitStatistics { hit: 0, miss: 1 }),
+                ("MGET".to_string(), CacheHitStatistics { hit: 1, miss: 1 }),
+            ])
+        );
+        assert_eq!(
+            statistics.prefixes,
+            HashMap::from([
+                ("user:".to_string(), CacheHitStatistics { hit: 2, miss: 1 }),
+                (
+                    "user:admin:".to_string(),
+                    CacheHitStatistics { hit: 2, miss: 0 }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_broadcast_prefixes() {
+        let cache_manager =
+            CacheManager::new(CacheConfig::new().set_broadcast_prefixes(["cfg:", "feature:"]));
+
+        assert!(matches!(
+            cache_manager.get_cached_cmd(crate::cmd("GET").arg("cfg:timeout")),
+            PrepareCacheResult::NotCached(_)
+        ));
+        assert!(matches!(
+            cache_manager.get_cached_cmd(crate::cmd("GET").arg("user:name")),
+            PrepareCacheResult::NotCacheable
+        ));
+        assert!(matches!(
+            cache_manager.get_cached_cmd(crate::cmd("MGET").arg("cfg:a").arg("feature:b")),
+            PrepareCacheResult::NotCached(_)
+        ));
+        assert!(
+            matches!(
+                cache_manager.get_cached_cmd(crate::cmd("MGET").arg("cfg:a").arg("user:b")),
+                PrepareCacheResult::NotCacheable
+            ),
+            "All keys must match one of the prefixes"
+        );
+    }
+
+    #[test]
+    fn test_cacheable_commands() {
+        let cache_manager = CacheManager::new(CacheConfig::new());
+        let is_cacheable = |cmd: &Cmd| {
+            matches!(
+                cache_manager.get_cached_cmd(cmd),
+                PrepareCacheResult::NotCached(_)
+            )
+        };
+
+        assert!(is_cacheable(crate::cmd("SMEMBERS").arg("set")));
+        assert!(is_cacheable(
+            crate::cmd("LRANGE").arg("list").arg(0).arg(-1)
+        ));
+        assert!(is_cacheable(
+            crate::cmd("ZRANGEBYSCORE").arg("zset").arg(0).arg(10)
+        ));
+        assert!(is_cacheable(crate::cmd("HSTRLEN").arg("hash").arg("field")));
+        assert!(is_cacheable(
+            crate::cmd("GETRANGE").arg("key").arg(0).arg(5)
+        ));
+        assert!(is_cacheable(crate::cmd("JSON.GET").arg("json").arg("$")));
+        assert!(
+            !is_cacheable(crate::cmd("SRANDMEMBER").arg("set")),
+            "Random replies must not be cached"
+        );
+        assert!(
+            !is_cacheable(crate::cmd("TTL").arg("key")),
+            "Time dependent replies must not be cached"
+        );
+        assert!(
+            !is_cacheable(crate::cmd("SINTER").arg("set_1").arg("set_2")),
+            "Replies depending on multiple keys must not be cached"
+        );
+    }
+
+    #[test]
+    fn test_registered_commands() {
+        let cache_manager = CacheManager::new(
+            CacheConfig::new()
+                .register_command("MOD.GET", CommandKeys::Single { key_index: 2 })
+                .register_command(
+                    "MOD.MGET",
+                    CommandKeys::Multiple {
+                        single_command: "MOD.GET".to_string(),
+                        trailing_args: 2,
+                    },
+                )
+                .register_command(
+                    "GET",
+                    CommandKeys::Multiple {
+                        single_command: "GET".to_string(),
+                        trailing_args: 1,
+                    },
+                ),
+        );
+
+        match cache_manager.get_cached_cmd(crate::cmd("MOD.GET").arg("opt").arg("key")) {
+            PrepareCacheResult::NotCached(CacheableCommand::Single(cmd)) => {
+                assert_eq!(cmd.redis_key, b"key")
+            }
+            _ => panic!("Command must be cacheable with single key"),
+        }
+
+        match cache_manager.get_cached_cmd(
+            crate::cmd("MOD.MGET")
+                .arg("key_1")
+                .arg("key_2")
+                .arg("field")
+                .arg("opt"),
+        ) {
+            PrepareCacheResult::NotCached(CacheableCommand::Multiple {
+                command_name,
+                commands,
+                tail_args,
+                ..
+            }) => {
+                assert_eq!(command_name, b"MOD.MGET");
+                assert_eq!(
+                    commands
+                        .iter()
+                        .map(|(_, part)| part.redis_key)
+                        .collect::<Vec<_>>(),
+                    vec![b"key_1".as_slice(), b"key_2".as_slice()]
+                );
+                assert_eq!(commands[0].1.cmd_key, b"MOD.GETkey_1fieldopt");
+                assert_eq!(tail_args, vec![b"field".as_slice(), b"opt".as_slice()]);
+            }
+            _ => panic!("Command must be cacheable with multiple keys"),
+        }
+
+        assert!(
+            matches!(
+                cache_manager.get_cached_cmd(crate::cmd("MOD.MGET").arg("field").arg("opt")),
+                PrepareCacheResult::NotCacheable
+            ),
+            "Command without keys must not be cacheable"
+        );
+        assert!(
+            matches!(
+                cache_manager.get_cached_cmd(crate::cmd("GET").arg("key")),
+                PrepareCacheResult::NotCacheable
+            ),
+            "Registered command must override built-in command"
+        );
+    }
+
+    #[test]
+    fn test_handle_push_value() {
+        let redis_key = b"test_redis_key".as_slice();
+        let redis_key_2 = b"test_redis_key_2".as_slice();
+        let redis_key_3 = b"test_redis_key_3".as_slice();
+        let cmd_key = b"test_cmd_key".as_slice();
+
+        let cache_manager = CacheManager::new(CacheConfig::new());
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+        let insert_all = || {
+            for redis_key in [redis_key, redis_key_2, redis_key_3] {
+                cache_manager.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+            }
+        };
+
+        insert_all();
+        cache_manager.handle_push_value(
+            &PushKind::Invalidate,
+            &[Value::Array(vec![
+                Value::BulkString(redis_key.to_vec()),
+                Value::BulkString(redis_key_2.to_vec()),
+            ])],
+        );
+        assert_eq!(cache_manager.get(redis_key, cmd_key), None);
+        assert_eq!(cache_manager.get(redis_key_2, cmd_key), None);
+        assert_eq!(cache_manager.get(redis_key_3, cmd_key), Some(Value::Int(1)));
+        assert_eq!(cache_manager.statistics().invalidate, 2);
+
+        insert_all();
+        cache_manager.handle_push_value(&PushKind::Invalidate, &[Value::Nil]);
+        assert_eq!(cache_manager.get(redis_key, cmd_key), None);
+        assert_eq!(cache_manager.get(redis_key_2, cmd_key), None);
+        assert_eq!(cache_manager.get(redis_key_3, cmd_key), None);
+        assert_eq!(cache_manager.statistics().invalidate, 5);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        let matches =
+            |pattern: &str, string: &str| glob_match(pattern.as_bytes(), string.as_bytes());
+
+        assert!(matches("*", ""));
+        assert!(matches("user:*", "user:1"));
+        assert!(matches("user:*:name", "user:1:2:name"));
+        assert!(!matches("user:*:name", "user:1:age"));
+        assert!(matches("h?llo", "hello"));
+        assert!(!matches("h?llo", "hllo"));
+        assert!(matches("h[ae]llo", "hallo"));
+        assert!(!matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo"));
+        assert!(!matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-c]llo", "hbllo"));
+        assert!(!matches("h[a-c]llo", "hdllo"));
+        assert!(matches("h\\*llo", "h*llo"));
+        assert!(!matches("h\\*llo", "hello"));
+        assert!(matches("h[llo", "h[llo"));
+    }
+
+    #[test]
+    fn test_manual_invalidation() {
+        let cmd_key = b"test_cmd_key".as_slice();
+        let cache_manager = CacheManager::new(CacheConfig::new());
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+        let redis_keys = [
+            b"user:1".as_slice(),
+            b"user:2".as_slice(),
+            b"cfg:1".as_slice(),
+        ];
+        let insert_all = || {
+            for redis_key in redis_keys {
+                cache_manager.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+            }
+        };
+        let cached_keys = || {
+            redis_keys
+                .into_iter()
+                .filter(|redis_key| cache_manager.get(redis_key, cmd_key).is_some())
+                .collect::<Vec<_>>()
+        };
+
+        insert_all();
+        cache_manager.invalidate_keys(&[b"user:1".to_vec(), b"missing".to_vec()]);
+        assert_eq!(cached_keys(), vec![b"user:2".as_slice(), b"cfg:1"]);
+
+        insert_all();
+        cache_manager.invalidate_pattern(b"user:*");
+        assert_eq!(cached_keys(), vec![b"cfg:1".as_slice()]);
+
+        insert_all();
+        cache_manager.flush();
+        assert!(cached_keys().is_empty());
+    }
+
+    #[test]
+    fn test_invalidation_sender() {
+        let redis_key = b"test_redis_key".as_slice();
+        let cmd_key = b"test_cmd_key".as_slice();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let cache_manager = CacheManager::new(CacheConfig::new().set_invalidation_sender(tx));
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+
+        cache_manager.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+        cache_manager.handle_push_value(
+            &PushKind::Invalidate,
+            &[Value::Array(vec![Value::BulkString(redis_key.to_vec())])],
+        );
+        assert_eq!(
+            rx.try_recv(),
+            Ok(InvalidationEvent::Keys(vec![redis_key.to_vec()]))
+        );
+        assert_eq!(
+            cache_manager.get(redis_key, cmd_key),
+            None,
+            "Value must be removed before the event is sent"
+        );
+
+        cache_manager.handle_push_value(&PushKind::Invalidate, &[Value::Nil]);
+        assert_eq!(rx.try_recv(), Ok(InvalidationEvent::Flush));
+
+        cache_manager.handle_push_value(&PushKind::Message, &[Value::Nil]);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_shared_custom_storage() {
+        let redis_key = b"test_redis_key".as_slice();
+        let cmd_key = b"test_cmd_key".as_slice();
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+
+        let storage = Arc::new(ShardedLRU::new(
+            std::num::NonZeroUsize::new(8).unwrap(),
+            None,
+        ));
+        let cache_manager_1 = CacheManager::new(CacheConfig::new().set_storage(storage.clone()));
+        let cache_manager_2 = CacheManager::new(CacheConfig::new().set_storage(storage.clone()));
+
+        cache_manager_1.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+        assert_eq!(cache_manager_1.get(redis_key, cmd_key), Some(Value::Int(1)));
+        // Values are visible to every client which shares the storage
+        assert_eq!(cache_manager_2.get(redis_key, cmd_key), Some(Value::Int(1)));
+
+        // Invalidation received by any client removes the value for all of them
+        cache_manager_2.handle_push_value(
+            &PushKind::Invalidate,
+            &[Value::Array(vec![Value::BulkString(redis_key.to_vec())])],
+        );
+        assert_eq!(cache_manager_1.get(redis_key, cmd_key), None);
+        assert_eq!(cache_manager_2.get(redis_key, cmd_key), None);
+
+        // Values of a dropped connection can't be trusted by the other clients
+        cache_manager_1.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+        drop(cache_manager_1);
+        assert_eq!(cache_manager_2.get(redis_key, cmd_key), None);
+        assert_eq!(storage.statistics(), cache_manager_2.statistics());
+        // One from the invalidation message and one from the dropped connection
+        assert_eq!(cache_manager_2.statistics().invalidate, 2);
+    }
+
+    #[test]
+    fn test_shared_custom_storage_is_scoped_by_server_and_db() {
+        let redis_key = b"test_redis_key".as_slice();
+        let cmd_key = b"test_cmd_key".as_slice();
+        let secs_10 = Instant::now().add(Duration::from_secs(10));
+
+        let storage = Arc::new(ShardedLRU::new(
+            std::num::NonZeroUsize::new(8).unwrap(),
+            None,
+        ));
+        let config = CacheConfig::new().set_storage(storage.clone());
+        let db_0 = CacheManager::new(config.clone()).with_scope("127.0.0.1:6379", 0);
+        let db_0_clone = CacheManager::new(config.clone()).with_scope("127.0.0.1:6379", 0);
+        let db_1 = CacheManager::new(config.clone()).with_scope("127.0.0.1:6379", 1);
+        let other_server = CacheManager::new(config).with_scope("127.0.0.1:6380", 0);
+
+        db_0.insert(redis_key, cmd_key, Value::Int(0), secs_10, &Value::Int(-1));
+        db_1.insert(redis_key, cmd_key, Value::Int(1), secs_10, &Value::Int(-1));
+        assert_eq!(db_0_clone.get(redis_key, cmd_key), Some(Value::Int(0)));
+        assert_eq!(db_1.get(redis_key, cmd_key), Some(Value::Int(1)));
+        assert_eq!(other_server.get(redis_key, cmd_key), None);
+
+        // A flush of one server only removes the values of its scope.
+        other_server.handle_push_value(&PushKind::Invalidate, &[Value::Nil]);
+        db_1.invalidate_pattern(b"test_*");
+        assert_eq!(db_0.get(redis_key, cmd_key), Some(Value::Int(0)));
+        assert_eq!(db_1.get(redis_key, cmd_key), None);
+    }
+
+    #[test]
+    #[cfg(feature = "aio")]
+    fn test_stale_while_revalidate_on_shared_storage() {
+        let storage = Arc::new(ShardedLRU::new(
+            std::num::NonZeroUsize::new(8).unwrap(),
+            None,
+        ));
+        let config = CacheConfig::new().set_storage(storage.clone());
+        let with_stale = CacheManager::new(
+            config
+                .clone()
+                .set_stale_while_revalidate(Duration::from_millis(50)),
+        );
+        let without_stale = CacheManager::new(config);
+        let mut cmd = crate::cmd("GET");
+        cmd.arg("test_redis_key");
+
+        with_stale.insert(
+            b"test_redis_key",
+            &cmd.data,
+            Value::Int(1),
+            Instant::now().add(Duration::from_millis(5)),
+            &Value::Int(-1),
+        );
+        assert!(matches!(
+            without_stale.get_cached_cmd_allowing_stale(&cmd),
+            PrepareCacheResult::Cached(_)
+        ));
+
+        std::thread::sleep(Duration::from_millis(6));
+        assert!(matches!(
+            with_stale.get_cached_cmd_allowing_stale(&cmd),
+            PrepareCacheResult::Stale(_)
+        ));
+        assert!(
+            matches!(
+                without_stale.get_cached_cmd_allowing_stale(&cmd),
+                PrepareCacheResult::NotCached(_)
+            ),
+            "Expired value must not be used by a client without stale-while-revalidate"
+        );
+    }
+
     #[test]
     #[cfg(feature = "connection-manager")]
     fn test_epoch_on_shared_cache_managers() {
@@ -404,30 +1175,22 @@ mod tests {
             assert_eq!(cm3.get(redis_key_3, cmd_key), Some(Value::Int(3)));
         };
 
-        let do_miss_gets = |cm1: &CacheManager, cm2: &CacheManager, cm3: &CacheManager| {
-            assert_eq!(cm1.get(redis_key, cmd_key), None);
-            assert_eq!(cm2.get(redis_key_2, cmd_key), None);
-            assert_eq!(cm3.get(redis_key_3, cmd_key), None);
-        };
-
         do_inserts(&cache_manager_1, &cache_manager_2, &cache_manager_3);
         do_hit_gets(&cache_manager_1, &cache_manager_2, &cache_manager_3);
-        // Different CacheManagers has different epochs so all must return None
-        do_miss_gets(&cache_manager_2, &cache_manager_3, &cache_manager_1);
+        // Values are visible to the other connections while the connection which cached them is alive
+        do_hit_gets(&cache_manager_2, &cache_manager_3, &cache_manager_1);
 
-        // Check when only one CacheManager has increased the epoch
-        do_inserts(&cache_manager_1, &cache_manager_2, &cache_manager_3);
-        do_hit_gets(&cache_manager_1, &cache_manager_2, &cache_manager_3);
-
-        let cache_manager_1 = cache_manager_1.clone_and_increase_epoch();
+        // Check when only one connection is recreated
+        drop(cache_manager_1);
+        let cache_manager_1 = shared_cache_manager.clone_and_increase_epoch();
         assert_eq!(cache_manager_1.get(redis_key, cmd_key), None);
 
         assert_eq!(
-            cache_manager_2.get(redis_key_2, cmd_key),
+            cache_manager_1.get(redis_key_2, cmd_key),
             Some(Value::Int(2))
         );
         assert_eq!(
-            cache_manager_3.get(redis_key_3, cmd_key),
+            cache_manager_1.get(redis_key_3, cmd_key),
             Some(Value::Int(3))
         );
     }
@@ -1,7 +1,7 @@
 use crate::{Cmd, ErrorKind, Pipeline, RedisError, RedisResult, Value};
 use std::{iter::zip, time::Instant};
 
-use super::{CacheManager, CacheMode, PrepareCacheResult};
+use super::{CacheManager, PrepareCacheResult};
 
 /// Holds enough information to resolve Cached Pipeline requests.
 pub(crate) struct CacheablePipeline<'a> {
@@ -27,7 +27,9 @@ impl CacheablePipeline<'_> {
         let mut response = vec![];
         for prepared_cache_result in self.commands {
             match prepared_cache_result {
-                PrepareCacheResult::Cached(reply) => response.push(reply),
+                PrepareCacheResult::Cached(reply) | PrepareCacheResult::Stale(reply) => {
+                    response.push(reply)
+                }
                 PrepareCacheResult::NotCached(cacheable_command) => {
                     let reply = cacheable_command.resolve(cache_manager, &mut replies)?;
                     response.push(reply);
@@ -62,7 +64,7 @@ pub(crate) struct MultipleCachedCommandPart<'a> {
 pub(crate) enum CacheableCommand<'a> {
     Single(SingleCachedCommand<'a>),
     Multiple {
-        command_name: &'a str,
+        command_name: &'a [u8],
         commands: Vec<(usize, MultipleCachedCommandPart<'a>)>,
         response: Vec<Value>,
         client_side_expire: Instant,
@@ -76,7 +78,7 @@ impl CacheableCommand<'_> {
         cache_manager: &CacheManager,
         mut replies: impl Iterator<Item = Value>,
     ) -> RedisResult<Value> {
-        if cache_manager.cache_config.mode == CacheMode::OptIn {
+        if cache_manager.cache_config.requires_caching_yes() {
             // Skipping response of CLIENT CACHING YES
             let _ = get_next_reply(&mut replies)?;
         }
@@ -135,7 +137,7 @@ impl CacheableCommand<'_> {
     }
 
     pub(crate) fn pack_command(&self, cache_manager: &CacheManager, pipeline: &mut Pipeline) {
-        if cache_manager.cache_config.mode == CacheMode::OptIn {
+        if cache_manager.cache_config.requires_caching_yes() {
             let mut cmd = Cmd::new();
             cmd.arg("CLIENT").arg("CACHING").arg("YES");
             pipeline.add_command(cmd);
@@ -170,6 +172,23 @@ impl CacheableCommand<'_> {
     }
 }
 
+/// Creates `GET` commands for given keys, which are sent by `prefetch_cache_keys`.
+pub(crate) fn prefetch_key_commands(redis_keys: Vec<Vec<u8>>) -> Vec<Cmd> {
+    redis_keys.into_iter().map(Cmd::get).collect()
+}
+
+/// Creates a pipeline which sends given commands, so their replies will be cached when it's sent.
+pub(crate) fn prefetch_pipeline<'a>(commands: impl IntoIterator<Item = &'a Cmd>) -> Pipeline {
+    let mut pipeline = Pipeline::new();
+    for cmd in commands {
+        let mut cmd = cmd.clone();
+        // Commands must be cached even if CacheMode::OptIn is used.
+        cmd.set_cache_config(crate::CommandCacheConfig::new().set_enable_cache(true));
+        pipeline.add_command(cmd);
+    }
+    pipeline
+}
+
 fn get_next_reply(mut replies: impl Iterator<Item = Value>) -> RedisResult<Value> {
     if let Some(reply) = replies.next() {
         Ok(reply)
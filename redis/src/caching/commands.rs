@@ -0,0 +1,138 @@
+/// Describes which arguments of a cacheable command are redis keys.
+///
+/// Cached replies are invalidated by redis key, so a command can be cached only if its reply depends
+/// on the keys described here and nothing else (e.g. random or time dependent replies can't be cached).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CommandKeys {
+    /// Reply depends on a single redis key, `key_index` is the argument index of the key
+    /// where the first argument after command name has index 1 (e.g. 1 for `HGET key field`).
+    Single {
+        /// Argument index of the redis key.
+        key_index: usize,
+    },
+    /// Every argument is a redis key except the last `trailing_args` arguments,
+    /// and reply is an array which has a value for each key in same order (e.g. `MGET key [key ...]`).
+    ///
+    /// Each key is cached separately as if `single_command` was called with the key and trailing arguments,
+    /// so only missing keys are requested from the server.
+    Multiple {
+        /// Name of the command which returns the value of a single key (e.g. `GET` for `MGET`).
+        single_command: String,
+        /// Count of arguments after the keys (e.g. 1 for `JSON.MGET key [key ...] path`).
+        trailing_args: usize,
+    },
+}
+
+/// Borrowed form of [`CommandKeys`], so built-in commands can be described without allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeySpec<'a> {
+    Single {
+        key_index: usize,
+    },
+    Multiple {
+        single_command: &'a [u8],
+        trailing_args: usize,
+    },
+}
+
+impl CommandKeys {
+    pub(crate) fn as_key_spec(&self) -> KeySpec<'_> {
+        match self {
+            CommandKeys::Single { key_index } => KeySpec::Single {
+                key_index: *key_index,
+            },
+            CommandKeys::Multiple {
+                single_command,
+                trailing_args,
+            } => KeySpec::Multiple {
+                single_command: single_command.as_bytes(),
+                trailing_args: *trailing_args,
+            },
+        }
+    }
+}
+
+/// Built-in cacheable commands with multiple keys, which are cached as a single key command per key.
+const MULTIPLE_KEY_COMMANDS: &[&[u8]] = &[b"MGET", b"JSON.MGET"];
+
+/// Built-in cacheable commands with a single key at index 1.
+const SINGLE_KEY_COMMANDS: &[&[u8]] = &[
+    b"GET",
+    b"GETRANGE",
+    b"SUBSTR",
+    b"STRLEN",
+    b"GETBIT",
+    b"BITCOUNT",
+    b"BITPOS",
+    b"BITFIELD_RO",
+    b"HGET",
+    b"HMGET",
+    b"HGETALL",
+    b"HKEYS",
+    b"HVALS",
+    b"HLEN",
+    b"HEXISTS",
+    b"HSTRLEN",
+    b"LINDEX",
+    b"LLEN",
+    b"LPOS",
+    b"LRANGE",
+    b"SCARD",
+    b"SISMEMBER",
+    b"SMISMEMBER",
+    b"SMEMBERS",
+    b"ZCARD",
+    b"ZCOUNT",
+    b"ZLEXCOUNT",
+    b"ZMSCORE",
+    b"ZRANGE",
+    b"ZRANGEBYLEX",
+    b"ZRANGEBYSCORE",
+    b"ZRANK",
+    b"ZREVRANGE",
+    b"ZREVRANGEBYLEX",
+    b"ZREVRANGEBYSCORE",
+    b"ZREVRANK",
+    b"ZSCORE",
+    b"GEODIST",
+    b"GEOHASH",
+    b"GEOPOS",
+    b"GEOSEARCH",
+    b"GEORADIUS_RO",
+    b"GEORADIUSBYMEMBER_RO",
+    b"XLEN",
+    b"XRANGE",
+    b"XREVRANGE",
+    b"JSON.GET",
+    b"JSON.TYPE",
+    b"JSON.STRLEN",
+    b"JSON.ARRLEN",
+    b"JSON.OBJLEN",
+    b"JSON.OBJKEYS",
+];
+
+/// Returns how keys of a built-in cacheable command are placed.
+/// Only read-only commands whose reply depends solely on the content of their keys are cacheable.
+pub(crate) fn builtin_key_spec(command_name: &[u8]) -> Option<KeySpec<'static>> {
+    match command_name {
+        b"MGET" => Some(KeySpec::Multiple {
+            single_command: b"GET",
+            trailing_args: 0,
+        }),
+        b"JSON.MGET" => Some(KeySpec::Multiple {
+            single_command: b"JSON.GET",
+            trailing_args: 1,
+        }),
+        _ if SINGLE_KEY_COMMANDS.contains(&command_name) => Some(KeySpec::Single { key_index: 1 }),
+        _ => None,
+    }
+}
+
+/// Returns names of all built-in cacheable commands.
+pub(crate) fn builtin_command_names() -> impl Iterator<Item = &'static [u8]> {
+    MULTIPLE_KEY_COMMANDS
+        .iter()
+        .chain(SINGLE_KEY_COMMANDS)
+        .copied()
+}
@@ -1,4 +1,9 @@
+use super::commands::{builtin_key_spec, KeySpec};
+use super::{CacheStorage, CommandKeys, InvalidationSender};
+use std::collections::HashMap;
+use std::fmt;
 use std::num::NonZeroUsize;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Defines the behavior of the cache regarding which commands should be cached.
@@ -14,11 +19,23 @@ pub enum CacheMode {
 }
 
 /// Configuration for client side caching.
-#[derive(Clone, Debug, Copy)]
+///
+/// `CacheConfig` isn't `Copy`, since it owns the broadcast prefixes, registered commands, custom storage
+/// and invalidation sender. Cloning it is cheap, the storage and the sender are shared between clones.
+#[derive(Clone)]
 pub struct CacheConfig {
     pub(crate) mode: CacheMode,
     pub(crate) size: NonZeroUsize,
+    pub(crate) max_memory: Option<NonZeroUsize>,
     pub(crate) default_client_ttl: Duration,
+    pub(crate) nil_ttl: Option<Duration>,
+    pub(crate) stale_while_revalidate: Option<Duration>,
+    pub(crate) broadcast_prefixes: Option<Vec<Vec<u8>>>,
+    pub(crate) storage: Option<Arc<dyn CacheStorage>>,
+    pub(crate) registered_commands: HashMap<Vec<u8>, CommandKeys>,
+    pub(crate) invalidation_sender: Option<Arc<dyn InvalidationSender>>,
+    pub(crate) command_statistics: bool,
+    pub(crate) prefix_statistics: Vec<Vec<u8>>,
 }
 
 impl CacheConfig {
@@ -27,7 +44,16 @@ impl CacheConfig {
         Self {
             mode: CacheMode::All,
             size: NonZeroUsize::new(10_000).unwrap(),
+            max_memory: None,
             default_client_ttl: Duration::from_secs(60 * 30),
+            nil_ttl: None,
+            stale_while_revalidate: None,
+            broadcast_prefixes: None,
+            storage: None,
+            registered_commands: HashMap::new(),
+            invalidation_sender: None,
+            command_statistics: false,
+            prefix_statistics: Vec::new(),
         }
     }
 
@@ -38,11 +64,43 @@ impl CacheConfig {
     }
 
     /// Sets maximum key count for the cache.
+    /// This is ignored if a custom storage is set with [`CacheConfig::set_storage`].
     pub fn set_size(mut self, size: NonZeroUsize) -> Self {
         self.size = size;
         self
     }
 
+    /// Sets maximum memory usage of the cache in bytes, by default only key count is limited.
+    /// Memory usage is estimated from the size of cached keys, commands and response values,
+    /// least recently used keys are evicted when the limit is exceeded.
+    /// Limit is divided between shards of the cache, a response which doesn't fit into a shard won't be cached.
+    ///
+    /// Key count limit of [`CacheConfig::set_size`] is still applied.
+    /// This is ignored if a custom storage is set with [`CacheConfig::set_storage`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::num::NonZeroUsize;
+    /// # use redis::caching::CacheConfig;
+    /// // Limit the cache to 64 MiB.
+    /// let cache_config = CacheConfig::new().set_max_memory(NonZeroUsize::new(64 * 1024 * 1024).unwrap());
+    /// ```
+    pub fn set_max_memory(mut self, max_memory: NonZeroUsize) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
+
+    /// Sets the storage backend which keeps cached values, default is a sharded LRU with size of [`CacheConfig::set_size`].
+    ///
+    /// Storage will be shared between all connections created with this configuration,
+    /// it can also be shared with other clients by passing an [`Arc`] of the storage.
+    /// Clients only share the values of the same server and database, see [`CacheStorage`] for details.
+    pub fn set_storage(mut self, storage: impl CacheStorage + 'static) -> Self {
+        self.storage = Some(Arc::new(storage));
+        self
+    }
+
     ///  Sets the default client side time to live (TTL) for cached values, this value will be used when a TTL isn't explicitly passed with [crate::Cmd::set_cache_config]/[crate::Pipeline::set_cache_config].
     ///  Client side caching mechanism will compare client side TTL with server side TTL and pick minimum to retain in the cache.
     ///  Client side TTL is the maximum time for a key to stay in cache.
@@ -72,6 +130,212 @@ impl CacheConfig {
         self.default_client_ttl = ttl;
         self
     }
+
+    /// Sets the maximum time to live (TTL) for `Nil` replies (negative caching), e.g. `GET` of a missing key.
+    /// By default `Nil` replies are cached like any other reply, `Duration::ZERO` disables caching them.
+    pub fn set_nil_ttl(mut self, ttl: Duration) -> Self {
+        self.nil_ttl = Some(ttl);
+        self
+    }
+
+    /// Allows serving a value up to `duration` after its time to live has passed, while the value is refreshed in the background
+    /// (stale-while-revalidate). Only a single refresh request is sent per command at a time.
+    ///
+    /// This is only supported for single key commands sent by async connections without a pipeline,
+    /// sync connections, pipelines and multi key commands (e.g. `MGET`) don't use expired values.
+    /// Values invalidated by the server are never served.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use redis::caching::CacheConfig;
+    /// let cache_config = CacheConfig::new()
+    ///     .set_default_client_ttl(Duration::from_secs(60))
+    ///     // Values expired less than 5 seconds ago are served while they're being refreshed.
+    ///     .set_stale_while_revalidate(Duration::from_secs(5));
+    /// ```
+    pub fn set_stale_while_revalidate(mut self, duration: Duration) -> Self {
+        self.stale_while_revalidate = Some(duration);
+        self
+    }
+
+    /// Enables broadcasting mode (`CLIENT TRACKING ON BCAST PREFIX ...`) for the given key prefixes.
+    /// In broadcasting mode the server doesn't remember which keys the client has read, instead it sends
+    /// invalidation messages for every modified key that matches one of the prefixes.
+    /// Only keys matching one of the prefixes will be cached, passing no prefixes will cache every key.
+    ///
+    /// [`CacheMode`] still decides which commands are cached, but `CLIENT CACHING YES` won't be sent
+    /// with [`CacheMode::OptIn`] since broadcasting mode doesn't support it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use redis::caching::CacheConfig;
+    /// # use redis::AsyncConnectionConfig;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let client = redis::Client::open("redis://127.0.0.1/?protocol=resp3").unwrap();
+    /// let cache_config = CacheConfig::new().set_broadcast_prefixes(["cfg:", "feature:"]);
+    /// let async_config = AsyncConnectionConfig::new().set_cache_config(cache_config);
+    /// let mut connection = client.get_multiplexed_async_connection_with_config(&async_config).await.unwrap();
+    /// // Cached, since key matches `cfg:` prefix.
+    /// let timeout: Option<String> = redis::cmd("GET").arg("cfg:timeout").query_async(&mut connection).await.unwrap();
+    /// // Not cached, key doesn't match any prefix.
+    /// let name: Option<String> = redis::cmd("GET").arg("user:name").query_async(&mut connection).await.unwrap();
+    /// # }
+    /// ```
+    pub fn set_broadcast_prefixes<I, P>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Vec<u8>>,
+    {
+        self.broadcast_prefixes = Some(prefixes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Registers a read-only command as cacheable, e.g. a command of a Redis module.
+    /// Built-in cacheable commands can be overridden with this too.
+    ///
+    /// Command name is matched exactly as it's sent to the server, so it should be uppercase
+    /// like the commands created by this crate.
+    /// Reply of the command must only depend on the keys described by [`CommandKeys`],
+    /// since cached replies are only invalidated when these keys are modified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use redis::caching::{CacheConfig, CommandKeys};
+    /// let cache_config = CacheConfig::new()
+    ///     // MYMODULE.GET key field
+    ///     .register_command("MYMODULE.GET", CommandKeys::Single { key_index: 1 })
+    ///     // MYMODULE.MGET key [key ...] field, which returns what MYMODULE.GET returns for each key
+    ///     .register_command(
+    ///         "MYMODULE.MGET",
+    ///         CommandKeys::Multiple {
+    ///             single_command: "MYMODULE.GET".to_string(),
+    ///             trailing_args: 1,
+    ///         },
+    ///     );
+    /// ```
+    pub fn register_command(mut self, command_name: &str, keys: CommandKeys) -> Self {
+        self.registered_commands
+            .insert(command_name.as_bytes().to_vec(), keys);
+        self
+    }
+
+    /// Sets a sender which is notified about invalidations received from the server,
+    /// after related values are removed from the cache.
+    /// Sender will be shared between all connections created with this configuration.
+    ///
+    /// This can be used to keep application structures derived from cached values in sync with the cache.
+    /// Values cached by a connection are also discarded when it reconnects, this isn't reported to the sender.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use redis::caching::{CacheConfig, InvalidationEvent};
+    /// # use redis::AsyncConnectionConfig;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let client = redis::Client::open("redis://127.0.0.1/?protocol=resp3").unwrap();
+    /// let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    /// let cache_config = CacheConfig::new().set_invalidation_sender(tx);
+    /// let async_config = AsyncConnectionConfig::new().set_cache_config(cache_config);
+    /// let mut connection = client.get_multiplexed_async_connection_with_config(&async_config).await.unwrap();
+    /// while let Some(event) = rx.recv().await {
+    ///     match event {
+    ///         InvalidationEvent::Keys(keys) => println!("Invalidated keys: {keys:?}"),
+    ///         InvalidationEvent::Flush => println!("Cache is flushed"),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn set_invalidation_sender(mut self, sender: impl InvalidationSender) -> Self {
+        self.invalidation_sender = Some(Arc::new(sender));
+        self
+    }
+
+    /// Enables collection of hits and misses for each command name, which are reported in
+    /// [`crate::caching::CacheStatistics::commands`]. Disabled by default, since it adds a lock to every lookup.
+    pub fn set_command_statistics(mut self, enabled: bool) -> Self {
+        self.command_statistics = enabled;
+        self
+    }
+
+    /// Sets key prefixes whose hits and misses are reported in [`crate::caching::CacheStatistics::prefixes`].
+    /// A key matching multiple prefixes is counted for each of them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use redis::caching::CacheConfig;
+    /// # use redis::AsyncConnectionConfig;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let client = redis::Client::open("redis://127.0.0.1/?protocol=resp3").unwrap();
+    /// let cache_config = CacheConfig::new()
+    ///     .set_command_statistics(true)
+    ///     .set_prefix_statistics(["user:", "session:"]);
+    /// let async_config = AsyncConnectionConfig::new().set_cache_config(cache_config);
+    /// let connection = client.get_multiplexed_async_connection_with_config(&async_config).await.unwrap();
+    /// let statistics = connection.get_cache_statistics().unwrap();
+    /// if let Some(users) = statistics.prefixes.get("user:") {
+    ///     println!("Hit ratio of users: {}", users.hit_ratio());
+    /// }
+    /// # }
+    /// ```
+    pub fn set_prefix_statistics<I, P>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Vec<u8>>,
+    {
+        self.prefix_statistics = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Returns how keys of the command are placed if the command is cacheable.
+    pub(crate) fn key_spec(&self, command_name: &[u8]) -> Option<KeySpec<'_>> {
+        match self.registered_commands.get(command_name) {
+            Some(keys) => Some(keys.as_key_spec()),
+            None => builtin_key_spec(command_name),
+        }
+    }
+
+    /// Returns true if `CLIENT CACHING YES` must be sent before each cached command.
+    pub(crate) fn requires_caching_yes(&self) -> bool {
+        self.mode == CacheMode::OptIn && self.broadcast_prefixes.is_none()
+    }
+
+    /// Returns true if the key can be cached with current tracking mode.
+    pub(crate) fn is_key_tracked(&self, redis_key: &[u8]) -> bool {
+        match &self.broadcast_prefixes {
+            Some(prefixes) if !prefixes.is_empty() => {
+                prefixes.iter().any(|prefix| redis_key.starts_with(prefix))
+            }
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Debug for CacheConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheConfig")
+            .field("mode", &self.mode)
+            .field("size", &self.size)
+            .field("max_memory", &self.max_memory)
+            .field("default_client_ttl", &self.default_client_ttl)
+            .field("nil_ttl", &self.nil_ttl)
+            .field("stale_while_revalidate", &self.stale_while_revalidate)
+            .field("broadcast_prefixes", &self.broadcast_prefixes)
+            .field("custom_storage", &self.storage.is_some())
+            .field("registered_commands", &self.registered_commands)
+            .field("invalidation_sender", &self.invalidation_sender.is_some())
+            .field("command_statistics", &self.command_statistics)
+            .field("prefix_statistics", &self.prefix_statistics)
+            .finish()
+    }
 }
 
 impl Default for CacheConfig {
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+/// Invalidation of client side cache, which is reported to [`InvalidationSender`] after cached values are removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidationEvent {
+    /// Server reported that these redis keys are modified, expired or evicted.
+    Keys(Vec<Vec<u8>>),
+    /// Server reported that the whole database is flushed (`FLUSHALL`/`FLUSHDB`), every cached value is removed.
+    Flush,
+}
+
+/// A trait for receivers of client side cache invalidations, check [`crate::caching::CacheConfig::set_invalidation_sender`].
+pub trait InvalidationSender: Send + Sync + 'static {
+    /// The sender must send without blocking, otherwise it will block the connection which received the invalidation.
+    fn send(&self, event: InvalidationEvent);
+}
+
+impl<Func: Fn(InvalidationEvent) + Send + Sync + 'static> InvalidationSender for Func {
+    fn send(&self, event: InvalidationEvent) {
+        self(event)
+    }
+}
+
+impl InvalidationSender for std::sync::mpsc::Sender<InvalidationEvent> {
+    fn send(&self, event: InvalidationEvent) {
+        let _ = std::sync::mpsc::Sender::send(self, event);
+    }
+}
+
+#[cfg(feature = "aio")]
+impl InvalidationSender for ::tokio::sync::mpsc::UnboundedSender<InvalidationEvent> {
+    fn send(&self, event: InvalidationEvent) {
+        let _ = ::tokio::sync::mpsc::UnboundedSender::send(self, event);
+    }
+}
+
+#[cfg(feature = "aio")]
+impl InvalidationSender for ::tokio::sync::broadcast::Sender<InvalidationEvent> {
+    fn send(&self, event: InvalidationEvent) {
+        let _ = ::tokio::sync::broadcast::Sender::send(self, event);
+    }
+}
+
+impl<T> InvalidationSender for Arc<T>
+where
+    T: InvalidationSender,
+{
+    fn send(&self, event: InvalidationEvent) {
+        self.as_ref().send(event)
+    }
+}
@@ -8,17 +8,48 @@
 //! Commands are cached in memory by their redis key and command pairs, when a redis key is removed all related command pairs are also removed.
 //! TTL is for key and not per command, when TTL of redis key gets updated, all command pairs of that redis key will use the new one.
 //!
+//! Only read-only commands whose reply depends solely on the content of their keys are cached (e.g. `GET`, `HGETALL`, `SMEMBERS`, `LRANGE`, `JSON.GET`),
+//! other commands like module commands can be registered with [CacheConfig::register_command].
+//!
+//! Cache size is limited by key count, it can also be limited by estimated memory usage with [CacheConfig::set_max_memory].
+//!
+//! By default the server tracks every key read by the connection, broadcasting mode can be used instead
+//! to track only keys under given prefixes, check [CacheConfig::set_broadcast_prefixes] for more details.
+//!
 //! For more information please read <https://redis.io/docs/manual/client-side-caching/>
 //!
+//! ## Managing the cache
+//!
+//! Connections which use client side caching can also manage their cache manually,
+//! these methods do nothing if caching is not enabled:
+//! - `invalidate_cache_keys` and `invalidate_cache_pattern` remove given keys, or keys matching a glob-style pattern
+//!   (e.g. `user:*`, same as `KEYS` command), from the cache.
+//! - `flush_cache` removes every value from the cache.
+//! - `prefetch_cache_keys` reads given keys with `GET`, and `prefetch_cache_commands` sends given commands,
+//!   so their replies are cached and following requests are served from the cache, e.g. to warm up the cache at startup.
+//!   Commands which aren't cacheable are sent, but their replies aren't cached.
+//!
+//! The cache of `ConnectionManager` is kept between reconnections, and the cache of cluster connections
+//! is shared by all node connections, so these methods apply to all of them.
+//!
+//! Sync [crate::Connection] and [crate::cluster::ClusterConnection] handle invalidation messages only while reading a response from the server,
+//! so a stale value might be served from the cache until the next request which isn't served from the cache.
+//!
 //! **Note:** ClusterConnection usage with Redis instances using 6.x version might give stale duration when resharding happens,
 //! therefore it's recommended to use version 7.x and above.
 
 mod cache_manager;
 pub(crate) mod cmd;
+mod commands;
 mod config;
+mod invalidation;
 pub(crate) mod sharded_lru;
 mod statistics;
+mod storage;
 
 pub(crate) use cache_manager::{CacheManager, PrepareCacheResult};
+pub use commands::CommandKeys;
 pub use config::{CacheConfig, CacheMode};
-pub use statistics::CacheStatistics;
+pub use invalidation::{InvalidationEvent, InvalidationSender};
+pub use statistics::{CacheHitStatistics, CacheStatistics};
+pub use storage::{CacheEntry, CacheEpoch, CacheStorage};
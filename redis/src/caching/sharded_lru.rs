@@ -1,10 +1,10 @@
 use crate::caching::statistics::Statistics;
+use crate::caching::{CacheEntry, CacheEpoch, CacheStatistics, CacheStorage};
 use crate::Value;
 use lru::LruCache;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hasher;
 use std::num::NonZeroUsize;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -16,25 +16,91 @@ pub(crate) struct CacheCmdEntry {
     value: Value,
 }
 
+impl CacheCmdEntry {
+    fn memory_usage(cmd: &[u8], value: &Value) -> usize {
+        std::mem::size_of::<CacheCmdEntry>() + cmd.len() + value_memory_usage(value)
+    }
+}
+
 /// CacheItem keeps information about a key's expiry time and cached response for each key, command pair.
 pub(crate) struct CacheItem {
     expire_time: Instant,
-    epoch: usize,
+    stale_until: Instant,
+    epoch: CacheEpoch,
     value_list: Vec<CacheCmdEntry>,
+    memory_usage: usize,
+}
+
+/// LRUCacheShard keeps cache items of a shard, with their estimated total memory usage.
+pub(crate) struct LRUCacheShard {
+    lru: LruCache<RedisKey, CacheItem>,
+    memory_usage: usize,
 }
 
-type LRUCacheShard = LruCache<RedisKey, CacheItem>;
+impl LRUCacheShard {
+    fn push(&mut self, redis_key: RedisKey, cache_item: CacheItem, statistics: &Statistics) {
+        self.memory_usage += cache_item.memory_usage;
+        statistics.increase_memory_usage(cache_item.memory_usage);
+        statistics.increase_entry_count(cache_item.value_list.len());
+        // Replaced item with same key or evicted LRU item.
+        if let Some((removed_key, removed_item)) = self.lru.push(redis_key, cache_item) {
+            let evicted = !self.lru.contains(&removed_key);
+            self.remove_item(&removed_item, evicted, statistics);
+        }
+    }
+
+    fn pop(&mut self, redis_key: &[u8], statistics: &Statistics) {
+        if let Some(cache_item) = self.lru.pop(redis_key) {
+            self.remove_item(&cache_item, false, statistics);
+        }
+    }
+
+    /// Evicts least recently used items until shard's memory usage fits into `max_memory`.
+    fn evict_to_fit(&mut self, max_memory: usize, statistics: &Statistics) {
+        while self.memory_usage > max_memory {
+            match self.lru.pop_lru() {
+                Some((_, cache_item)) => self.remove_item(&cache_item, true, statistics),
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self, statistics: &Statistics) {
+        let invalidated = self
+            .lru
+            .iter()
+            .map(|(_, cache_item)| cache_item.value_list.len())
+            .sum();
+        statistics.increase_invalidate(invalidated);
+        statistics.decrease_entry_count(invalidated);
+        statistics.decrease_memory_usage(self.memory_usage);
+        self.memory_usage = 0;
+        self.lru.clear();
+    }
+
+    /// Updates statistics for a removed item, `evicted` is true if it's removed to make room for other items.
+    fn remove_item(&mut self, cache_item: &CacheItem, evicted: bool, statistics: &Statistics) {
+        self.memory_usage -= cache_item.memory_usage;
+        statistics.decrease_memory_usage(cache_item.memory_usage);
+        statistics.decrease_entry_count(cache_item.value_list.len());
+        if evicted {
+            statistics.increase_eviction(cache_item.value_list.len());
+        } else {
+            statistics.increase_invalidate(cache_item.value_list.len());
+        }
+    }
+}
 
 pub(crate) struct ShardedLRU {
     shards: Vec<std::sync::Mutex<LRUCacheShard>>,
-    pub(crate) statistics: Arc<Statistics>,
-    last_epoch: AtomicUsize,
+    max_shard_memory: Option<usize>,
+    statistics: Arc<Statistics>,
 }
 
 impl ShardedLRU {
     const MAX_SHARD_COUNT: usize = 32;
 
-    pub(crate) fn new(total_key_size: NonZeroUsize) -> Self {
+    pub(crate) fn new(total_key_size: NonZeroUsize, max_memory: Option<NonZeroUsize>) -> Self {
         // If total cache size is smaller than max shard size then it won't use sharding.
         let (shard_count, shard_size) = if total_key_size.get() >= Self::MAX_SHARD_COUNT {
             (
@@ -47,14 +113,19 @@ impl ShardedLRU {
 
         let mut shards = Vec::with_capacity(shard_count);
         for _ in 0..shard_count {
-            let shard = LruCache::new(NonZeroUsize::new(shard_size).unwrap());
+            let shard = LRUCacheShard {
+                lru: LruCache::new(NonZeroUsize::new(shard_size).unwrap()),
+                memory_usage: 0,
+            };
             shards.push(std::sync::Mutex::new(shard));
         }
+        // Memory budget is divided equally between shards, like the key count.
+        let max_shard_memory = max_memory.map(|max_memory| max_memory.get() / shard_count);
         let statistics = Arc::new(Statistics::default());
         ShardedLRU {
             shards,
+            max_shard_memory,
             statistics,
-            last_epoch: AtomicUsize::new(0),
         }
     }
 
@@ -65,32 +136,41 @@ impl ShardedLRU {
         let lock = &self.shards[s.finish() as usize % self.shards.len()];
         lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
+}
 
-    pub(crate) fn get<'a>(
+impl CacheStorage for ShardedLRU {
+    fn get(
         &self,
-        redis_key: &'a [u8],
-        redis_cmd: &'a [u8],
-        epoch: usize,
-    ) -> Option<Value> {
+        redis_key: &[u8],
+        redis_cmd: &[u8],
+        min_expire_time: Instant,
+    ) -> Option<CacheEntry> {
         let mut lru_cache = self.get_shard(redis_key);
-        if let Some(cache_item) = lru_cache.get_mut(redis_key) {
+        if let Some(cache_item) = lru_cache.lru.get_mut(redis_key) {
             // If one of following conditions are true, cache item is invalid and can't be trusted to use:
-            // Epoch of client is not same, it means cache item is created by another redis connection.
-            // Expire time of key has been passed, value could be stale.
+            // Connection which created the cache item is gone, so its invalidations aren't received anymore.
+            // Stale-while-revalidate duration of key has been passed, value can't be used even if it's stale.
             let cache_item_is_invalid =
-                cache_item.epoch != epoch || Instant::now() > cache_item.expire_time;
+                !cache_item.epoch.is_alive() || Instant::now() > cache_item.stale_until;
             if cache_item_is_invalid {
-                self.statistics
-                    .increase_invalidate(cache_item.value_list.len());
                 self.statistics.increase_miss(1);
-                lru_cache.pop(redis_key);
+                lru_cache.pop(redis_key, &self.statistics);
                 return None;
             };
+            // Cache item expires too soon for this lookup, but it's kept for lookups which allow stale values.
+            if min_expire_time > cache_item.expire_time {
+                self.statistics.increase_miss(1);
+                return None;
+            }
             // Found redis key in cache, but KEY,CMD combination also must be in the cache otherwise, it will be fetched from server.
             for entry in &cache_item.value_list {
                 if entry.cmd == redis_cmd {
                     self.statistics.increase_hit(1);
-                    return Some(entry.value.clone());
+                    return Some(CacheEntry {
+                        value: entry.value.clone(),
+                        expire_time: cache_item.expire_time,
+                        stale_until: cache_item.stale_until,
+                    });
                 }
             }
         }
@@ -98,56 +178,141 @@ impl ShardedLRU {
         None
     }
 
-    pub(crate) fn insert(
+    fn insert(
         &self,
         redis_key: &[u8],
         cmd_key: &[u8],
         value: Value,
         expire_time: Instant,
-        epoch: usize,
+        stale_until: Instant,
+        epoch: CacheEpoch,
     ) {
         let mut lru_cache = self.get_shard(redis_key);
-        if let Some(ch) = lru_cache.peek_mut(redis_key) {
+        let entry_memory_usage = CacheCmdEntry::memory_usage(cmd_key, &value);
+        if let Some(max_shard_memory) = self.max_shard_memory {
+            // A value which can't fit into the shard is not cached, previous values of the key are removed
+            // since they might be stale, and other keys are kept instead of being evicted for nothing.
+            if entry_memory_usage + std::mem::size_of::<CacheItem>() + redis_key.len()
+                > max_shard_memory
+            {
+                lru_cache.pop(redis_key, &self.statistics);
+                return;
+            }
+        }
+        let shard = &mut *lru_cache;
+        if let Some(ch) = shard.lru.peek_mut(redis_key) {
             if ch.epoch == epoch {
-                for entry in &mut ch.value_list {
-                    if entry.cmd == cmd_key {
+                let previous_memory_usage = ch.memory_usage;
+                match ch.value_list.iter_mut().find(|entry| entry.cmd == cmd_key) {
+                    Some(entry) => {
+                        ch.memory_usage -= CacheCmdEntry::memory_usage(cmd_key, &entry.value);
                         entry.value = value;
-                        ch.expire_time = expire_time;
-                        return;
+                    }
+                    None => {
+                        ch.value_list.push(CacheCmdEntry {
+                            cmd: cmd_key.to_vec(),
+                            value,
+                        });
+                        self.statistics.increase_entry_count(1);
                     }
                 }
-                ch.value_list.push(CacheCmdEntry {
-                    cmd: cmd_key.to_vec(),
-                    value,
-                });
+                ch.memory_usage += entry_memory_usage;
                 ch.expire_time = expire_time;
+                ch.stale_until = stale_until;
+                shard.memory_usage = shard.memory_usage - previous_memory_usage + ch.memory_usage;
+                self.statistics.decrease_memory_usage(previous_memory_usage);
+                self.statistics.increase_memory_usage(ch.memory_usage);
+                // Updated key is the most recently used one, so it won't be evicted.
+                shard.lru.promote(redis_key);
+                if let Some(max_shard_memory) = self.max_shard_memory {
+                    shard.evict_to_fit(max_shard_memory, &self.statistics);
+                }
                 return;
             }
         }
-        let _ = lru_cache.push(
+        shard.push(
             redis_key.to_vec(),
             CacheItem {
                 expire_time,
+                stale_until,
                 value_list: vec![CacheCmdEntry {
                     cmd: cmd_key.to_vec(),
                     value,
                 }],
                 epoch,
+                memory_usage: std::mem::size_of::<CacheItem>()
+                    + redis_key.len()
+                    + entry_memory_usage,
             },
+            &self.statistics,
         );
+        if let Some(max_shard_memory) = self.max_shard_memory {
+            shard.evict_to_fit(max_shard_memory, &self.statistics);
+        }
+    }
+
+    fn invalidate(&self, cache_key: &[u8]) {
+        self.get_shard(cache_key).pop(cache_key, &self.statistics);
+    }
+
+    fn invalidate_matching(&self, matches: &dyn Fn(&[u8]) -> bool) {
+        for shard in &self.shards {
+            let mut lru_cache = shard
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let redis_keys: Vec<RedisKey> = lru_cache
+                .lru
+                .iter()
+                .filter(|(redis_key, _)| matches(redis_key))
+                .map(|(redis_key, _)| redis_key.clone())
+                .collect();
+            for redis_key in redis_keys {
+                lru_cache.pop(&redis_key, &self.statistics);
+            }
+        }
     }
 
-    pub(crate) fn invalidate(&self, cache_key: &Vec<u8>) {
-        if let Some(cache_holder) = self.get_shard(cache_key).pop(cache_key) {
-            self.statistics
-                .increase_invalidate(cache_holder.value_list.len());
+    /// Removes every cache item from all shards.
+    fn flush(&self) {
+        for shard in &self.shards {
+            shard
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clear(&self.statistics);
         }
     }
 
-    pub(crate) fn increase_epoch(&self) -> usize {
-        self.last_epoch.fetch_add(1, Ordering::Relaxed)
+    fn statistics(&self) -> CacheStatistics {
+        self.statistics.clone().into()
     }
 }
+
+/// Estimates heap and inline memory used by the value.
+fn value_memory_usage(value: &Value) -> usize {
+    let inner = match value {
+        Value::BulkString(bytes) => bytes.len(),
+        Value::SimpleString(text) | Value::VerbatimString { text, .. } => text.len(),
+        Value::Array(values) | Value::Set(values) | Value::Push { data: values, .. } => {
+            values.iter().map(value_memory_usage).sum()
+        }
+        Value::Map(pairs) => pairs
+            .iter()
+            .map(|(key, value)| value_memory_usage(key) + value_memory_usage(value))
+            .sum(),
+        Value::Attribute { data, attributes } => {
+            value_memory_usage(data)
+                + attributes
+                    .iter()
+                    .map(|(key, value)| value_memory_usage(key) + value_memory_usage(value))
+                    .sum::<usize>()
+        }
+        Value::BigNumber(number) => number.bits().div_ceil(8) as usize,
+        Value::ServerError(error) => error.details().map_or(0, str::len),
+        Value::Nil | Value::Int(_) | Value::Okay | Value::Double(_) | Value::Boolean(_) => 0,
+    };
+    std::mem::size_of::<Value>() + inner
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,42 +322,64 @@ mod tests {
     static CMD_KEY_2: &[u8] = b"test_cmd_key_2".as_slice();
     static REDIS_KEY: &[u8] = b"test_redis_key".as_slice();
 
+    /// Returns an epoch which is alive as long as the returned connection isn't dropped.
+    fn connection_epoch() -> (Arc<()>, CacheEpoch) {
+        let connection = Arc::new(());
+        let epoch = CacheEpoch::new(&connection);
+        (connection, epoch)
+    }
+
     #[test]
     fn test_expire() {
-        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap());
+        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap(), None);
+        let (_connection, epoch) = connection_epoch();
+
+        let expire_time = Instant::now().add(Duration::from_secs(10));
 
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY,
             Value::Boolean(true),
-            Instant::now().add(Duration::from_secs(10)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             Some(Value::Boolean(true))
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY_2, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Using different cmd key must result in cache miss"
         );
 
+        let expire_time = Instant::now().add(Duration::from_millis(5));
+
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY,
             Value::Boolean(false),
-            Instant::now().add(Duration::from_millis(5)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             Some(Value::Boolean(false)),
             "Old value must be overwritten"
         );
         std::thread::sleep(Duration::from_millis(6));
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Cache must be expired"
         );
@@ -200,38 +387,55 @@ mod tests {
 
     #[test]
     fn test_different_cmd_keys() {
-        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap());
+        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap(), None);
+        let (_connection, epoch) = connection_epoch();
+
+        let expire_time = Instant::now().add(Duration::from_secs(10));
 
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY,
             Value::Int(1),
-            Instant::now().add(Duration::from_secs(10)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
         // Second insert must override expire of the redis key.
+        let expire_time = Instant::now().add(Duration::from_millis(5));
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY_2,
             Value::Int(2),
-            Instant::now().add(Duration::from_millis(5)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
 
-        assert_eq!(sharded_lru.get(REDIS_KEY, CMD_KEY, 0), Some(Value::Int(1)));
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY_2, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
+            Some(Value::Int(1))
+        );
+        assert_eq!(
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
             Some(Value::Int(2))
         );
 
         std::thread::sleep(Duration::from_millis(6));
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Cache must be expired"
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY_2, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Cache must be expired"
         );
@@ -239,28 +443,38 @@ mod tests {
 
     #[test]
     fn test_invalidate() {
-        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap());
+        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap(), None);
+        let (_connection, epoch) = connection_epoch();
+
+        let expire_time = Instant::now().add(Duration::from_secs(10));
 
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY,
             Value::Boolean(true),
-            Instant::now().add(Duration::from_secs(10)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             Some(Value::Boolean(true))
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY_2, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Using different cmd key must result in cache miss"
         );
 
-        sharded_lru.invalidate(&REDIS_KEY.to_vec());
+        sharded_lru.invalidate(REDIS_KEY);
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             None,
             "Cache must be invalidated"
         );
@@ -268,43 +482,245 @@ mod tests {
 
     #[test]
     fn test_epoch_change() {
-        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap());
+        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(64).unwrap(), None);
+        let (connection, epoch) = connection_epoch();
+        let (_other_connection, other_epoch) = connection_epoch();
 
         let another_key = "foobar";
 
+        let expire_time = Instant::now().add(Duration::from_secs(10));
+
         sharded_lru.insert(
             REDIS_KEY,
             CMD_KEY,
             Value::Boolean(true),
-            Instant::now().add(Duration::from_secs(10)),
-            0,
+            expire_time,
+            expire_time,
+            epoch.clone(),
         );
+        let expire_time = Instant::now().add(Duration::from_secs(10));
         sharded_lru.insert(
             another_key.as_bytes(),
             CMD_KEY,
             Value::Boolean(true),
-            Instant::now().add(Duration::from_secs(10)),
-            0,
+            expire_time,
+            expire_time,
+            epoch,
+        );
+        let expire_time = Instant::now().add(Duration::from_secs(10));
+        sharded_lru.insert(
+            REDIS_KEY,
+            CMD_KEY_2,
+            Value::Boolean(false),
+            expire_time,
+            expire_time,
+            other_epoch,
         );
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 0),
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
+            Some(Value::Boolean(false)),
+            "Insert with another epoch must replace the values of the key"
+        );
+        assert_eq!(
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
+            None,
+            "Insert with another epoch must replace the values of the key"
+        );
+        assert_eq!(
+            sharded_lru
+                .get(another_key.as_bytes(), CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             Some(Value::Boolean(true))
         );
+
+        drop(connection);
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY_2, 0),
+            sharded_lru.get(another_key.as_bytes(), CMD_KEY, Instant::now()),
             None,
-            "Using different cmd key must result in cache miss"
+            "Cache must be invalidated once the connection is gone"
+        );
+        assert_eq!(
+            sharded_lru
+                .get(REDIS_KEY, CMD_KEY_2, Instant::now())
+                .map(|entry| entry.value),
+            Some(Value::Boolean(false)),
+            "Values of other connections must be kept"
+        );
+    }
+
+    #[test]
+    fn test_max_memory() {
+        let redis_keys = [
+            b"test_redis_key_1",
+            b"test_redis_key_2",
+            b"test_redis_key_3",
+        ];
+        let value = Value::BulkString(vec![0; 1000]);
+        let item_memory_usage = std::mem::size_of::<CacheItem>()
+            + redis_keys[0].len()
+            + CacheCmdEntry::memory_usage(CMD_KEY, &value);
+        // Budget allows only two items of same size.
+        let sharded_lru = ShardedLRU::new(
+            NonZeroUsize::new(16).unwrap(),
+            NonZeroUsize::new(item_memory_usage * 2 + item_memory_usage / 2),
         );
+        let expire_time = Instant::now().add(Duration::from_secs(10));
+        let (_connection, epoch) = connection_epoch();
 
+        sharded_lru.insert(
+            redis_keys[0],
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        sharded_lru.insert(
+            redis_keys[1],
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        assert_eq!(sharded_lru.statistics().memory_usage, item_memory_usage * 2);
+
+        sharded_lru.insert(
+            redis_keys[2],
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        assert_eq!(sharded_lru.statistics().memory_usage, item_memory_usage * 2);
+        assert_eq!(sharded_lru.statistics().eviction, 1);
+        assert_eq!(sharded_lru.statistics().entry_count, 2);
         assert_eq!(
-            sharded_lru.get(REDIS_KEY, CMD_KEY, 1),
+            sharded_lru
+                .get(redis_keys[0], CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             None,
-            "Cache must be invalidated"
+            "Least recently used key must be evicted"
         );
         assert_eq!(
-            sharded_lru.get(another_key.as_bytes(), CMD_KEY, 1),
+            sharded_lru
+                .get(redis_keys[1], CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
+            Some(value.clone())
+        );
+        assert_eq!(
+            sharded_lru
+                .get(redis_keys[2], CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
+            Some(value.clone())
+        );
+
+        sharded_lru.insert(
+            redis_keys[2],
+            CMD_KEY,
+            Value::BulkString(vec![0; item_memory_usage * 3]),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        assert_eq!(
+            sharded_lru
+                .get(redis_keys[2], CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
             None,
-            "Cache must be invalidated"
+            "Value larger than the budget must not be cached"
+        );
+        assert_eq!(
+            sharded_lru
+                .get(redis_keys[1], CMD_KEY, Instant::now())
+                .map(|entry| entry.value),
+            Some(value.clone()),
+            "Other keys must be kept"
+        );
+        assert_eq!(sharded_lru.statistics().memory_usage, item_memory_usage);
+
+        sharded_lru.flush();
+        assert_eq!(sharded_lru.statistics().memory_usage, 0);
+        assert_eq!(sharded_lru.statistics().entry_count, 0);
+    }
+
+    #[test]
+    fn test_eviction_and_entry_count() {
+        let sharded_lru = ShardedLRU::new(NonZeroUsize::new(2).unwrap(), None);
+        let (_connection, epoch) = connection_epoch();
+        let expire_time = Instant::now().add(Duration::from_secs(10));
+        let value = Value::Int(1);
+
+        sharded_lru.insert(
+            b"key_1",
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        sharded_lru.insert(
+            b"key_1",
+            CMD_KEY_2,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        sharded_lru.insert(
+            b"key_2",
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        assert_eq!(sharded_lru.statistics().entry_count, 3);
+
+        // Replacing the value of same key and command doesn't change anything.
+        sharded_lru.insert(
+            b"key_2",
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        assert_eq!(sharded_lru.statistics().entry_count, 3);
+
+        // Key count limit is reached, so both commands of key_1 are evicted.
+        sharded_lru.insert(
+            b"key_3",
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            epoch.clone(),
+        );
+        let statistics = sharded_lru.statistics();
+        assert_eq!(statistics.eviction, 2);
+        assert_eq!(statistics.invalidate, 0);
+        assert_eq!(statistics.entry_count, 2);
+
+        // Epoch change replaces the item, which is an invalidation.
+        let (_other_connection, other_epoch) = connection_epoch();
+        sharded_lru.insert(
+            b"key_3",
+            CMD_KEY,
+            value.clone(),
+            expire_time,
+            expire_time,
+            other_epoch,
         );
+        sharded_lru.invalidate(b"key_2");
+        let statistics = sharded_lru.statistics();
+        assert_eq!(statistics.eviction, 2);
+        assert_eq!(statistics.invalidate, 2);
+        assert_eq!(statistics.entry_count, 1);
     }
 }
@@ -1,16 +1,62 @@
+use super::commands::builtin_command_names;
+use super::CacheConfig;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// CacheStatistics holds statistics generated by Client Side Caching.
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct CacheStatistics {
     /// Count of how many cache hits occurred.
     pub hit: usize,
     /// Count of how many cache misses occurred.
     pub miss: usize,
-    /// Count of how many cache item is invalidated via [`crate::types::PushKind::Invalidate`] or LRU.
+    /// Count of how many cache item is invalidated via [`crate::types::PushKind::Invalidate`], expiry or manual invalidation.
     /// If there is multiple cache item per key it will be increased multiple times instead of 1.
     pub invalidate: usize,
+    /// Count of how many cache item is evicted by LRU to stay within the size or memory limit.
+    /// If there is multiple cache item per key it will be increased multiple times instead of 1.
+    pub eviction: usize,
+    /// Count of cache items currently in the cache, each key and command pair is counted separately.
+    pub entry_count: usize,
+    /// Estimated memory usage of cached keys, commands and values in bytes.
+    pub memory_usage: usize,
+    /// Hits and misses of each command, only collected if enabled with
+    /// [`crate::caching::CacheConfig::set_command_statistics`].
+    pub commands: HashMap<String, CacheHitStatistics>,
+    /// Hits and misses of keys matching each prefix, only collected for prefixes set with
+    /// [`crate::caching::CacheConfig::set_prefix_statistics`].
+    pub prefixes: HashMap<String, CacheHitStatistics>,
+}
+
+impl CacheStatistics {
+    /// Returns ratio of hits to all lookups, 0 if there wasn't any lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        hit_ratio(self.hit, self.miss)
+    }
+}
+
+/// Hits and misses of a subset of cache lookups, see [`CacheStatistics::commands`] and [`CacheStatistics::prefixes`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheHitStatistics {
+    /// Count of how many cache hits occurred.
+    pub hit: usize,
+    /// Count of how many cache misses occurred.
+    pub miss: usize,
+}
+
+impl CacheHitStatistics {
+    /// Returns ratio of hits to all lookups, 0 if there wasn't any lookup.
+    pub fn hit_ratio(&self) -> f64 {
+        hit_ratio(self.hit, self.miss)
+    }
+}
+
+fn hit_ratio(hit: usize, miss: usize) -> f64 {
+    match hit + miss {
+        0 => 0.0,
+        total => hit as f64 / total as f64,
+    }
 }
 
 #[derive(Default)]
@@ -18,6 +64,9 @@ pub(crate) struct Statistics {
     hit: AtomicUsize,
     miss: AtomicUsize,
     invalidate: AtomicUsize,
+    eviction: AtomicUsize,
+    entry_count: AtomicUsize,
+    memory_usage: AtomicUsize,
 }
 
 impl Statistics {
@@ -32,6 +81,26 @@ impl Statistics {
     pub(crate) fn increase_invalidate(&self, val: usize) {
         self.invalidate.fetch_add(val, Ordering::Relaxed);
     }
+
+    pub(crate) fn increase_eviction(&self, val: usize) {
+        self.eviction.fetch_add(val, Ordering::Relaxed);
+    }
+
+    pub(crate) fn increase_entry_count(&self, val: usize) {
+        self.entry_count.fetch_add(val, Ordering::Relaxed);
+    }
+
+    pub(crate) fn decrease_entry_count(&self, val: usize) {
+        self.entry_count.fetch_sub(val, Ordering::Relaxed);
+    }
+
+    pub(crate) fn increase_memory_usage(&self, val: usize) {
+        self.memory_usage.fetch_add(val, Ordering::Relaxed);
+    }
+
+    pub(crate) fn decrease_memory_usage(&self, val: usize) {
+        self.memory_usage.fetch_sub(val, Ordering::Relaxed);
+    }
 }
 
 impl From<Arc<Statistics>> for CacheStatistics {
@@ -40,6 +109,94 @@ impl From<Arc<Statistics>> for CacheStatistics {
             hit: value.hit.load(Ordering::Relaxed),
             miss: value.miss.load(Ordering::Relaxed),
             invalidate: value.invalidate.load(Ordering::Relaxed),
+            eviction: value.eviction.load(Ordering::Relaxed),
+            entry_count: value.entry_count.load(Ordering::Relaxed),
+            memory_usage: value.memory_usage.load(Ordering::Relaxed),
+            ..Default::default()
+        }
+    }
+}
+
+/// Hits and misses of a subset of cache lookups, counted with atomics.
+#[derive(Default)]
+struct HitCounters {
+    hit: AtomicUsize,
+    miss: AtomicUsize,
+}
+
+impl HitCounters {
+    fn record(&self, hit: bool) {
+        match hit {
+            true => self.hit.fetch_add(1, Ordering::Relaxed),
+            false => self.miss.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+
+    fn load(&self) -> CacheHitStatistics {
+        CacheHitStatistics {
+            hit: self.hit.load(Ordering::Relaxed),
+            miss: self.miss.load(Ordering::Relaxed),
         }
     }
 }
+
+/// Collects hits and misses by command name and key prefix, as configured in [`crate::caching::CacheConfig`].
+///
+/// Counters of each cacheable command are created upfront, so lookups only update atomics.
+pub(crate) struct DetailedStatistics {
+    commands: HashMap<Vec<u8>, HitCounters>,
+    prefixes: Vec<(Vec<u8>, HitCounters)>,
+}
+
+impl DetailedStatistics {
+    pub(crate) fn new(cache_config: &CacheConfig) -> Self {
+        let commands = match cache_config.command_statistics {
+            true => builtin_command_names()
+                .map(<[u8]>::to_vec)
+                .chain(cache_config.registered_commands.keys().cloned())
+                .map(|name| (name, HitCounters::default()))
+                .collect(),
+            false => HashMap::new(),
+        };
+        DetailedStatistics {
+            commands,
+            prefixes: cache_config
+                .prefix_statistics
+                .iter()
+                .map(|prefix| (prefix.clone(), HitCounters::default()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn record(&self, command_name: &[u8], redis_key: &[u8], hit: bool) {
+        if let Some(counters) = self.commands.get(command_name) {
+            counters.record(hit);
+        }
+        for (prefix, counters) in &self.prefixes {
+            if redis_key.starts_with(prefix) {
+                counters.record(hit);
+            }
+        }
+    }
+
+    /// Adds collected breakdowns into statistics of the storage, commands without lookups are left out.
+    pub(crate) fn fill(&self, statistics: &mut CacheStatistics) {
+        statistics.commands = self
+            .commands
+            .iter()
+            .map(|(name, counters)| (name, counters.load()))
+            .filter(|(_, hits)| hits.hit + hits.miss > 0)
+            .map(|(name, hits)| (String::from_utf8_lossy(name).into_owned(), hits))
+            .collect();
+        statistics.prefixes = self
+            .prefixes
+            .iter()
+            .map(|(prefix, counters)| {
+                (
+                    String::from_utf8_lossy(prefix).into_owned(),
+                    counters.load(),
+                )
+            })
+            .collect();
+    }
+}
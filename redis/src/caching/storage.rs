@@ -0,0 +1,132 @@
+use super::CacheStatistics;
+use crate::Value;
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+/// Cached value returned by [`CacheStorage::get`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheEntry {
+    /// Cached response of the command.
+    pub value: Value,
+    /// Time when the value expires, which was given when the value was inserted.
+    pub expire_time: Instant,
+    /// Time when the value is removed from the storage, which was given when the value was inserted.
+    ///
+    /// It's later than [`CacheEntry::expire_time`] if the client which inserted the value uses
+    /// [stale-while-revalidate](super::CacheConfig::set_stale_while_revalidate), each client decides whether
+    /// it uses an expired value with its own stale-while-revalidate duration.
+    pub stale_until: Instant,
+}
+
+/// Identifies the connection which fetched a cached value.
+///
+/// The server only sends invalidations of a key to the connections which have read it, so a value can be trusted
+/// while the connection which fetched it is alive. Once that connection is dropped or recreated, its values might be
+/// stale and must be treated as missing, even if they're read by another connection or client.
+#[derive(Clone, Debug)]
+pub struct CacheEpoch(Weak<()>);
+
+impl CacheEpoch {
+    pub(crate) fn new(connection: &Arc<()>) -> Self {
+        CacheEpoch(Arc::downgrade(connection))
+    }
+
+    /// Returns true while the connection which fetched the value is alive.
+    pub fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
+impl PartialEq for CacheEpoch {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CacheEpoch {}
+
+/// Storage backend used by client side caching to keep cached responses.
+///
+/// Responses are stored per redis key and command pair, a redis key can have multiple commands cached
+/// (e.g. `GET key` and `STRLEN key`), and all of them must be removed when the redis key is invalidated.
+///
+/// Each value is inserted with a [`CacheEpoch`] which identifies the connection that fetched it.
+/// Values whose epoch isn't [alive](CacheEpoch::is_alive) anymore can't be trusted and must be treated as missing.
+///
+/// Implementations are shared between connections, so they must be safe to use from multiple threads.
+/// The same storage can be used by multiple clients by passing it to each [`super::CacheConfig`] in an [`Arc`].
+/// Values cached by a client are then served to the other clients of the same server and database, the redis keys
+/// of a shared storage are prefixed with the server address and database number to keep the values of other
+/// servers and databases apart, and invalidations and flushes of a server only remove its own values.
+pub trait CacheStorage: Send + Sync {
+    /// Returns the cached value of the redis key and command pair, if it exists, its epoch is alive,
+    /// `stale_until` hasn't passed and it expires after `min_expire_time`.
+    ///
+    /// Values which expire before `min_expire_time` must be counted as misses, but they must be kept until
+    /// `stale_until` passes, since they're still used by lookups which allow stale values.
+    fn get(&self, redis_key: &[u8], cmd_key: &[u8], min_expire_time: Instant)
+        -> Option<CacheEntry>;
+
+    /// Inserts the value of the redis key and command pair, which expires at `expire_time`.
+    /// The redis key must be removed once `stale_until` passes.
+    fn insert(
+        &self,
+        redis_key: &[u8],
+        cmd_key: &[u8],
+        value: Value,
+        expire_time: Instant,
+        stale_until: Instant,
+        epoch: CacheEpoch,
+    );
+
+    /// Removes all cached commands of the redis key.
+    fn invalidate(&self, redis_key: &[u8]);
+
+    /// Removes all cached commands of redis keys for which `matches` returns true.
+    fn invalidate_matching(&self, matches: &dyn Fn(&[u8]) -> bool);
+
+    /// Removes every cached value.
+    fn flush(&self);
+
+    /// Returns statistics about the usage of the storage.
+    fn statistics(&self) -> CacheStatistics;
+}
+
+impl<T: CacheStorage + ?Sized> CacheStorage for Arc<T> {
+    fn get(
+        &self,
+        redis_key: &[u8],
+        cmd_key: &[u8],
+        min_expire_time: Instant,
+    ) -> Option<CacheEntry> {
+        (**self).get(redis_key, cmd_key, min_expire_time)
+    }
+
+    fn insert(
+        &self,
+        redis_key: &[u8],
+        cmd_key: &[u8],
+        value: Value,
+        expire_time: Instant,
+        stale_until: Instant,
+        epoch: CacheEpoch,
+    ) {
+        (**self).insert(redis_key, cmd_key, value, expire_time, stale_until, epoch)
+    }
+
+    fn invalidate(&self, redis_key: &[u8]) {
+        (**self).invalidate(redis_key)
+    }
+
+    fn invalidate_matching(&self, matches: &dyn Fn(&[u8]) -> bool) {
+        (**self).invalidate_matching(matches)
+    }
+
+    fn flush(&self) {
+        (**self).flush()
+    }
+
+    fn statistics(&self) -> CacheStatistics {
+        (**self).statistics()
+    }
+}
@@ -5,7 +5,10 @@ use crate::aio::{AsyncPushSender, DefaultAsyncDNSResolver};
 #[cfg(feature = "aio")]
 use crate::io::{tcp::TcpSettings, AsyncDNSResolver};
 use crate::{
-    connection::{connect, Connection, ConnectionInfo, ConnectionLike, IntoConnectionInfo},
+    connection::{
+        connect, connect_with_config, Connection, ConnectionInfo, ConnectionLike,
+        IntoConnectionInfo,
+    },
     types::{RedisResult, Value},
 };
 #[cfg(feature = "aio")]
@@ -14,12 +17,9 @@ use std::pin::Pin;
 #[cfg(feature = "tls-rustls")]
 use crate::tls::{inner_build_with_tls, TlsCertificates};
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use crate::caching::CacheConfig;
-#[cfg(all(
-    feature = "cache-aio",
-    any(feature = "connection-manager", feature = "cluster-async")
-))]
+#[cfg(all(feature = "cache", any(feature = "aio", feature = "cluster")))]
 use crate::caching::CacheManager;
 
 /// The client type.
@@ -72,6 +72,25 @@ impl Client {
         connect(&self.connection_info, Some(timeout))
     }
 
+    /// Instructs the client to actually connect to redis with the given
+    /// configuration and returns a connection object.  The connection object
+    /// can be used to send commands to the server.  This can fail with
+    /// a variety of errors (like unreachable host) so it's important
+    /// that you handle those errors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use redis::ConnectionConfig;
+    /// let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+    /// let config = ConnectionConfig::new().set_connection_timeout(Duration::from_secs(1));
+    /// let con = client.get_connection_with_config(&config).unwrap();
+    /// ```
+    pub fn get_connection_with_config(&self, config: &ConnectionConfig) -> RedisResult<Connection> {
+        connect_with_config(&self.connection_info, config)
+    }
+
     /// Returns a reference of client connection info object.
     pub fn get_connection_info(&self) -> &ConnectionInfo {
         &self.connection_info
@@ -166,11 +185,50 @@ impl Client {
     }
 }
 
-#[cfg(feature = "cache-aio")]
+/// Options for creation of connection
+#[derive(Clone, Default)]
+pub struct ConnectionConfig {
+    /// Maximum time to wait for a connection to be established
+    pub(crate) connection_timeout: Option<Duration>,
+    #[cfg(feature = "cache")]
+    pub(crate) cache: Option<Cache>,
+}
+
+impl ConnectionConfig {
+    /// Creates a new instance of the options with nothing set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the connection timeout
+    pub fn set_connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.connection_timeout = Some(connection_timeout);
+        self
+    }
+
+    /// Sets cache config for Connection, check CacheConfig for more details.
+    ///
+    /// Since [`Connection`] reads from the socket only while a request is sent to the server,
+    /// invalidation messages are handled when the next request which isn't served from
+    /// the cache is sent. Setting a short client side TTL limits how long a stale value can be served.
+    #[cfg(feature = "cache")]
+    pub fn set_cache_config(mut self, cache_config: CacheConfig) -> Self {
+        self.cache = Some(Cache::Config(cache_config));
+        self
+    }
+
+    #[cfg(all(feature = "cache", feature = "cluster"))]
+    pub(crate) fn set_cache_manager(mut self, cache_manager: CacheManager) -> Self {
+        self.cache = Some(Cache::Manager(cache_manager));
+        self
+    }
+}
+
+#[cfg(feature = "cache")]
 #[derive(Clone)]
 pub(crate) enum Cache {
     Config(CacheConfig),
-    #[cfg(any(feature = "connection-manager", feature = "cluster-async"))]
+    #[cfg(any(feature = "aio", feature = "cluster"))]
     Manager(CacheManager),
 }
 
@@ -183,7 +241,7 @@ pub struct AsyncConnectionConfig {
     /// Maximum time to wait for a connection to be established
     pub(crate) connection_timeout: Option<std::time::Duration>,
     pub(crate) push_sender: Option<std::sync::Arc<dyn AsyncPushSender>>,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     pub(crate) cache: Option<Cache>,
     pub(crate) tcp_settings: TcpSettings,
     pub(crate) dns_resolver: Option<std::sync::Arc<dyn AsyncDNSResolver>>,
@@ -246,16 +304,13 @@ impl AsyncConnectionConfig {
     }
 
     /// Sets cache config for MultiplexedConnection, check CacheConfig for more details.
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     pub fn set_cache_config(mut self, cache_config: CacheConfig) -> Self {
         self.cache = Some(Cache::Config(cache_config));
         self
     }
 
-    #[cfg(all(
-        feature = "cache-aio",
-        any(feature = "connection-manager", feature = "cluster-async")
-    ))]
+    #[cfg(feature = "cache")]
     pub(crate) fn set_cache_manager(mut self, cache_manager: CacheManager) -> Self {
         self.cache = Some(Cache::Manager(cache_manager));
         self
@@ -790,12 +845,20 @@ impl Client {
         let con = self
             .get_simple_async_connection::<T>(resolver, &config.tcp_settings)
             .await?;
-        crate::aio::MultiplexedConnection::new_with_config(
-            &self.connection_info.redis,
-            con,
-            config.clone(),
-        )
-        .await
+        #[cfg(not(feature = "cache"))]
+        let config = config.clone();
+        #[cfg(feature = "cache")]
+        let config = match &config.cache {
+            Some(Cache::Config(cache_config)) => config.clone().set_cache_manager(
+                CacheManager::new(cache_config.clone()).with_scope(
+                    &self.connection_info.addr.to_string(),
+                    self.connection_info.redis.db,
+                ),
+            ),
+            _ => config.clone(),
+        };
+        crate::aio::MultiplexedConnection::new_with_config(&self.connection_info.redis, con, config)
+            .await
     }
 
     async fn get_simple_async_connection_dynamically(
@@ -62,33 +62,50 @@
 //! });
 //! let _: redis::Value = connection.route_command(&redis::cmd("PING"), routing_info).unwrap();
 //! ```
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::cluster_node_health::NodeHealthTracker;
 use crate::cluster_pipeline::UNROUTABLE_ERROR;
+use crate::cluster_read_strategy::record_request;
 use crate::cluster_routing::{
-    MultipleNodeRoutingInfo, ResponsePolicy, Routable, SingleNodeRoutingInfo, SlotAddr,
+    check_script_keys, is_blocking_read, route_for_atomic_commands, MultipleNodeRoutingInfo,
+    ResponsePolicy, Routable, SingleNodeRoutingInfo, SlotAddr,
 };
-use crate::cluster_topology::parse_slots;
+use crate::cluster_topology::{parse_shards, parse_slots};
 use crate::cmd::{cmd, Cmd};
 use crate::connection::{
-    connect, Connection, ConnectionAddr, ConnectionInfo, ConnectionLike, RedisConnectionInfo,
+    connect, connect_with_config, Connection, ConnectionAddr, ConnectionInfo, ConnectionLike,
+    RedisConnectionInfo,
 };
 use crate::parser::parse_redis_value;
-use crate::types::{ErrorKind, HashMap, RedisError, RedisResult, Value};
-use crate::IntoConnectionInfo;
+use crate::pipeline::Pipeline;
+use crate::types::{
+    ErrorKind, FromRedisValue, HashMap, InfoDict, RedisError, RedisResult, ToRedisArgs, Value,
+};
 pub use crate::TlsMode; // Pub for backwards compatibility
 use crate::{
     cluster_client::ClusterParams,
     cluster_routing::{Redirect, Route, RoutingInfo, SlotMap, SLOT_SIZE},
 };
+use crate::{ConnectionConfig, IntoConnectionInfo};
+// `Iter` is deprecated unless `safe_iterators` is enabled, cluster scans return it like other scans.
+#[allow(deprecated)]
+use crate::{Iter, ScanOptions};
 use rand::{rng, seq::IteratorRandom, Rng};
 
 pub use crate::cluster_client::{ClusterClient, ClusterClientBuilder};
-pub use crate::cluster_pipeline::{cluster_pipe, ClusterPipeline};
+pub use crate::cluster_node_health::{CircuitBreakerConfig, NodeHealthStats};
+pub use crate::cluster_pipeline::{cluster_pipe, ClusterCommandError, ClusterPipeline};
+pub use crate::cluster_read_strategy::{
+    AzAffinityReplica, LowestLatencyReplica, RandomReplica, ReadFromReplicaStrategy,
+    RoundRobinReplica,
+};
+pub use crate::cluster_topology::{ClusterTopology, SlotRange};
 
 use crate::connection::TlsConnParams;
 
@@ -174,6 +191,16 @@ pub trait Connect: Sized {
     where
         T: IntoConnectionInfo;
 
+    /// Connect to a node with the given configuration, returning handle for command execution.
+    ///
+    /// The default implementation only uses the connection timeout from the configuration.
+    fn connect_with_config<T>(info: T, config: ConnectionConfig) -> RedisResult<Self>
+    where
+        T: IntoConnectionInfo,
+    {
+        Self::connect(info, config.connection_timeout)
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// does not read a response.  This is useful for commands like
     /// `MONITOR` which yield multiple items.  This needs to be used with
@@ -207,6 +234,13 @@ impl Connect for Connection {
         connect(&info.into_connection_info()?, timeout)
     }
 
+    fn connect_with_config<T>(info: T, config: ConnectionConfig) -> RedisResult<Self>
+    where
+        T: IntoConnectionInfo,
+    {
+        connect_with_config(&info.into_connection_info()?, &config)
+    }
+
     fn send_packed_command(&mut self, cmd: &[u8]) -> RedisResult<()> {
         Self::send_packed_command(self, cmd)
     }
@@ -301,9 +335,12 @@ pub struct ClusterConnection<C = Connection> {
     initial_nodes: Vec<ConnectionInfo>,
     connections: RefCell<HashMap<String, C>>,
     slots: RefCell<SlotMap>,
+    node_health: Arc<NodeHealthTracker>,
     auto_reconnect: RefCell<bool>,
     read_timeout: RefCell<Option<Duration>>,
     write_timeout: RefCell<Option<Duration>>,
+    // Set once a node rejects `CLUSTER SHARDS`, so the topology is discovered with `CLUSTER SLOTS` from then on.
+    shards_unsupported: Cell<bool>,
     cluster_params: ClusterParams,
 }
 
@@ -315,12 +352,20 @@ where
         cluster_params: ClusterParams,
         initial_nodes: Vec<ConnectionInfo>,
     ) -> RedisResult<Self> {
+        let node_health = Arc::new(NodeHealthTracker::new(
+            cluster_params.circuit_breaker.clone(),
+        ));
         let connection = Self {
             connections: RefCell::new(HashMap::new()),
-            slots: RefCell::new(SlotMap::new(cluster_params.read_from_replicas)),
+            slots: RefCell::new(SlotMap::new(
+                cluster_params.read_from_replicas.clone(),
+                Some(node_health.clone()),
+            )),
+            node_health,
             auto_reconnect: RefCell::new(true),
             read_timeout: RefCell::new(cluster_params.response_timeout),
             write_timeout: RefCell::new(None),
+            shards_unsupported: Cell::new(false),
             initial_nodes: initial_nodes.to_vec(),
             cluster_params,
         };
@@ -382,6 +427,66 @@ where
         Ok(())
     }
 
+    /// Gets [`crate::caching::CacheStatistics`] for current connection if caching is enabled.
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn get_cache_statistics(&self) -> Option<crate::caching::CacheStatistics> {
+        self.cluster_params
+            .cache_manager
+            .as_ref()
+            .map(|cm| cm.statistics())
+    }
+
+    /// Removes given keys from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_keys<K: crate::ToRedisArgs>(&self, keys: K) {
+        if let Some(cache_manager) = &self.cluster_params.cache_manager {
+            cache_manager.invalidate_keys(&keys.to_redis_args());
+        }
+    }
+
+    /// Removes keys matching the glob-style pattern from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_pattern(&self, pattern: impl AsRef<[u8]>) {
+        if let Some(cache_manager) = &self.cluster_params.cache_manager {
+            cache_manager.invalidate_pattern(pattern.as_ref());
+        }
+    }
+
+    /// Removes every value from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn flush_cache(&self) {
+        if let Some(cache_manager) = &self.cluster_params.cache_manager {
+            cache_manager.flush();
+        }
+    }
+
+    /// Reads given keys with `GET` and stores their values in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn prefetch_cache_keys<K: crate::ToRedisArgs>(&mut self, keys: K) -> RedisResult<()> {
+        let commands = crate::caching::cmd::prefetch_key_commands(keys.to_redis_args());
+        self.prefetch_cache_commands(&commands)
+    }
+
+    /// Sends given commands and stores their replies in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn prefetch_cache_commands(&mut self, commands: &[Cmd]) -> RedisResult<()> {
+        if self.cluster_params.cache_manager.is_none() || commands.is_empty() {
+            return Ok(());
+        }
+        let pipeline = crate::caching::cmd::prefetch_pipeline(commands);
+        // Pipelines are sent to a single node, so commands are sent one by one.
+        for cmd in pipeline.cmd_iter() {
+            let _: Value = cmd.query(self)?;
+        }
+        Ok(())
+    }
+
     /// Check that all connections it has are available (`PING` internally).
     #[doc(hidden)]
     pub fn check_connection(&mut self) -> bool {
@@ -392,6 +497,92 @@ where
         self.send_recv_and_retry_cmds(pipe.commands())
     }
 
+    pub(crate) fn execute_pipeline_per_command(
+        &mut self,
+        pipe: &ClusterPipeline,
+    ) -> Vec<Result<Value, ClusterCommandError>> {
+        let cmds = pipe.commands();
+        let mut results: Vec<Option<Result<Value, ClusterCommandError>>> =
+            (0..cmds.len()).map(|_| None).collect();
+        let mut cmd_map: HashMap<String, NodeCmd> = HashMap::new();
+
+        for (idx, cmd) in cmds.iter().enumerate() {
+            match self.get_addr_for_cmd(cmd) {
+                Ok(addr) => {
+                    let nc = cmd_map
+                        .entry(addr.clone())
+                        .or_insert_with(|| NodeCmd::new(addr));
+                    nc.indexes.push(idx);
+                    cmd.write_packed_command(&mut nc.pipe);
+                }
+                Err(err) => results[idx] = Some(Err(ClusterCommandError::new(None, err))),
+            }
+        }
+
+        let mut redirected = false;
+        {
+            let mut connections = self.connections.borrow_mut();
+            let mut sent = Vec::with_capacity(cmd_map.len());
+            for (_, nc) in cmd_map.drain() {
+                match self
+                    .get_connection_by_addr(&mut connections, &nc.addr)
+                    .and_then(|conn| conn.send_packed_command(&nc.pipe))
+                {
+                    Ok(()) => sent.push(nc),
+                    Err(err) => {
+                        connections.remove(&nc.addr);
+                        for idx in &nc.indexes {
+                            let err = err.clone_mostly("Failed to send the pipeline");
+                            results[*idx] =
+                                Some(Err(ClusterCommandError::new(Some(nc.addr.clone()), err)));
+                        }
+                    }
+                }
+            }
+
+            for nc in sent {
+                let mut broken_connection: Option<RedisError> = None;
+                for idx in &nc.indexes {
+                    let result = match &broken_connection {
+                        Some(err) => Err(err.clone_mostly("Failed to receive the reply")),
+                        None => match connections.get_mut(&nc.addr) {
+                            // Server errors are returned as values, but they fail the command.
+                            Some(conn) => conn.recv_response().and_then(Value::extract_error),
+                            None => Err(RedisError::from((
+                                ErrorKind::IoError,
+                                "Connection was dropped",
+                            ))),
+                        },
+                    };
+                    results[*idx] = Some(result.map_err(|err| {
+                        redirected |= err.is_cluster_error();
+                        // A broken connection can't be read from anymore, so the remaining
+                        // commands of the node fail with the same error.
+                        if err.is_io_error() && broken_connection.is_none() {
+                            broken_connection =
+                                Some(err.clone_mostly("Failed to receive the reply"));
+                        }
+                        ClusterCommandError::new(Some(nc.addr.clone()), err)
+                    }));
+                }
+                if broken_connection.is_some() {
+                    connections.remove(&nc.addr);
+                }
+            }
+        }
+
+        if redirected {
+            // The failed commands aren't retried, but the caller should find the new owners of
+            // their slots when sending them again. A failed refresh is retried by the next request.
+            let _ = self.refresh_slots();
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every command has a result"))
+            .collect()
+    }
+
     /// Returns the connection status.
     ///
     /// The connection is open until any `read_response` call received an
@@ -429,6 +620,11 @@ where
     fn refresh_slots(&self) -> RedisResult<()> {
         let mut slots = self.slots.borrow_mut();
         *slots = self.create_new_slots()?;
+        let addresses = slots.addresses_for_all_nodes();
+        self.node_health.retain_nodes(&addresses);
+        if let Some(strategy) = &self.cluster_params.read_from_replicas {
+            strategy.retain_nodes(&addresses);
+        }
 
         let mut nodes = slots.values().flatten().collect::<Vec<_>>();
         nodes.sort_unstable();
@@ -463,15 +659,32 @@ where
         let mut new_slots = None;
 
         for (addr, conn) in connections.iter_mut() {
-            let value = conn.req_command(&slot_cmd())?;
-            if let Ok(slots_data) = parse_slots(
-                value,
-                self.cluster_params.tls,
-                addr.rsplit_once(':').unwrap().0,
-            ) {
+            let host = addr.rsplit_once(':').unwrap().0;
+            // Servers older than Redis 7 don't support `CLUSTER SHARDS`.
+            let shards = if self.shards_unsupported.get() {
+                None
+            } else {
+                let shards = conn
+                    .req_command(&shards_cmd())
+                    .and_then(|value| value.extract_error())
+                    .and_then(|value| parse_shards(value, self.cluster_params.tls, host));
+                if shards.as_ref().is_err_and(is_shards_unsupported) {
+                    self.shards_unsupported.set(true);
+                }
+                shards.ok()
+            };
+            let slots_data = match shards {
+                Some(slots_data) => Ok(slots_data),
+                None => {
+                    let value = conn.req_command(&slot_cmd())?;
+                    parse_slots(value, self.cluster_params.tls, host)
+                }
+            };
+            if let Ok(slots_data) = slots_data {
                 new_slots = Some(SlotMap::from_slots(
                     slots_data,
-                    self.cluster_params.read_from_replicas,
+                    self.cluster_params.read_from_replicas.clone(),
+                    Some(self.node_health.clone()),
                 ));
                 break;
             }
@@ -491,10 +704,25 @@ where
         let params = self.cluster_params.clone();
         let info = get_connection_info(node, params)?;
 
-        let mut conn = C::connect(info, Some(self.cluster_params.connection_timeout))?;
-        if self.cluster_params.read_from_replicas {
+        #[allow(unused_mut)]
+        let mut config =
+            ConnectionConfig::new().set_connection_timeout(self.cluster_params.connection_timeout);
+        #[cfg(feature = "cache")]
+        if let Some(cache_manager) = &self.cluster_params.cache_manager {
+            config = config.set_cache_manager(cache_manager.clone_and_increase_epoch());
+        }
+
+        let mut conn = C::connect_with_config(info, config)?;
+        if let Some(strategy) = &self.cluster_params.read_from_replicas {
             // If READONLY is sent to primary nodes, it will have no effect
             cmd("READONLY").exec(&mut conn)?;
+            if strategy.uses_availability_zone() {
+                let availability_zone = availability_zone_cmd()
+                    .query(&mut conn)
+                    .ok()
+                    .and_then(parse_availability_zone);
+                strategy.node_connected(node, availability_zone.as_deref());
+            }
         }
         conn.set_read_timeout(*self.read_timeout.borrow())?;
         conn.set_write_timeout(*self.write_timeout.borrow())?;
@@ -619,29 +847,23 @@ where
         self.execute_on_all(input, slots.addresses_for_all_primaries())
     }
 
-    fn execute_multi_slot<'a, 'b>(
-        &'a self,
+    fn execute_multi_slot(
+        &self,
         input: Input,
-        slots: &'a mut SlotMap,
-        connections: &'a mut HashMap<String, C>,
-        routes: &'b [(Route, Vec<usize>)],
-    ) -> Vec<RedisResult<(&'a str, Value)>>
-    where
-        'b: 'a,
-    {
-        slots
-            .addresses_for_multi_slot(routes)
-            .enumerate()
-            .map(|(index, addr)| {
-                let addr = addr.ok_or(RedisError::from((
-                    ErrorKind::IoError,
-                    "Couldn't find connection",
-                )))?;
-                let connection = self.get_connection_by_addr(connections, addr)?;
-                let (_, indices) = routes.get(index).unwrap();
+        routes: &[(Route, Vec<usize>)],
+    ) -> Vec<RedisResult<(String, Value)>> {
+        routes
+            .iter()
+            .map(|(route, indices)| {
                 let cmd =
                     crate::cluster_routing::command_for_multi_slot_indices(&input, indices.iter());
-                connection.req_command(&cmd).map(|res| (addr, res))
+                // Each sub-command is routed like a command with a single slot, so it's retried and
+                // redirected on its own, like the sub-commands of the async cluster connection.
+                self.request_single_node(
+                    Input::Cmd(&cmd),
+                    SingleNodeRoutingInfo::SpecificNode(*route),
+                )
+                .map(|(addr, res)| (addr, res.into()))
             })
             .collect()
     }
@@ -652,20 +874,19 @@ where
         routing: MultipleNodeRoutingInfo,
         response_policy: Option<ResponsePolicy>,
     ) -> RedisResult<Value> {
-        let mut connections = self.connections.borrow_mut();
-        let mut slots = self.slots.borrow_mut();
-
+        let owned_addresses = |results: Vec<RedisResult<(&str, Value)>>| {
+            results
+                .into_iter()
+                .map(|result| result.map(|(addr, value)| (addr.to_string(), value)))
+                .collect::<Vec<_>>()
+        };
         let results = match &routing {
-            MultipleNodeRoutingInfo::MultiSlot(routes) => {
-                self.execute_multi_slot(input, &mut slots, &mut connections, routes)
-            }
+            MultipleNodeRoutingInfo::MultiSlot(routes) => self.execute_multi_slot(input, routes),
             MultipleNodeRoutingInfo::AllMasters => {
-                drop(connections);
-                self.execute_on_all_primaries(input, &mut slots)
+                owned_addresses(self.execute_on_all_primaries(input, &mut self.slots.borrow_mut()))
             }
             MultipleNodeRoutingInfo::AllNodes => {
-                drop(connections);
-                self.execute_on_all_nodes(input, &mut slots)
+                owned_addresses(self.execute_on_all_nodes(input, &mut self.slots.borrow_mut()))
             }
         };
 
@@ -766,13 +987,23 @@ where
             }
             None => fail!(UNROUTABLE_ERROR),
         };
+        self.request_single_node(input, single_node_routing)
+            .map(|(_addr, output)| output)
+    }
 
+    /// Sends `input` to the node of `single_node_routing`, following redirects and retrying on
+    /// errors, and returns the result along with the address of the node which answered.
+    fn request_single_node(
+        &self,
+        input: Input,
+        single_node_routing: SingleNodeRoutingInfo,
+    ) -> RedisResult<(String, Output)> {
         let mut retries = 0;
         let mut redirected = None::<Redirect>;
 
         loop {
             // Get target address and response.
-            let (addr, rv) = {
+            let (addr, rv, circuit_opened) = {
                 let mut connections = self.connections.borrow_mut();
                 let (addr, conn) = if let Some(redirected) = redirected.take() {
                     let (addr, is_asking) = match redirected {
@@ -806,11 +1037,38 @@ where
                         }
                     }
                 };
-                (addr, conn.and_then(|conn| input.send(conn)))
+                let latency_strategy = self
+                    .cluster_params
+                    .read_from_replicas
+                    .as_ref()
+                    .filter(|strategy| strategy.uses_latency() && !is_blocking_read(&input));
+                let start = Instant::now();
+                let rv = conn.and_then(|conn| input.send(conn));
+                let latency = start.elapsed();
+                // Only replicas are chosen by the strategy, so reads served by primaries aren't reported.
+                if let Some(strategy) = latency_strategy {
+                    if self.slots.borrow().is_replica(&addr) {
+                        record_request(strategy.as_ref(), &addr, &rv, latency);
+                    }
+                }
+                let circuit_opened = self.node_health.record(&addr, &rv, latency);
+                (addr, rv, circuit_opened)
             };
 
+            if circuit_opened
+                && self
+                    .slots
+                    .borrow()
+                    .addresses_for_all_primaries()
+                    .contains(addr.as_str())
+            {
+                // The primary might have failed over. If the refresh fails, the request's own error
+                // handling decides how to continue.
+                let _ = self.refresh_slots();
+            }
+
             match rv {
-                Ok(rv) => return Ok(rv),
+                Ok(rv) => return Ok((addr, rv)),
                 Err(err) => {
                     if err.kind() == ErrorKind::ClusterConnectionNotFound
                         && *self.auto_reconnect.borrow()
@@ -957,6 +1215,64 @@ where
         self.request(Input::Cmd(cmd), Some(routing))
             .map(|res| res.into())
     }
+
+    /// Returns a snapshot of the slot map which is used to route commands.
+    pub fn topology(&self) -> ClusterTopology {
+        ClusterTopology::new(self.slots.borrow().slots())
+    }
+
+    /// Returns the health of the nodes this connection sent requests to, by their address.
+    ///
+    /// The health is always tracked, but circuits only open if a [`CircuitBreakerConfig`] is set.
+    pub fn node_health(&self) -> std::collections::HashMap<String, NodeHealthStats> {
+        self.node_health.snapshot()
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster.
+    ///
+    /// Each primary is scanned with its own cursor, and primaries which are replaced by a failover
+    /// or receive slots during the scan are scanned again, so a key might be returned multiple times.
+    #[allow(deprecated)]
+    pub fn scan<RV: FromRedisValue>(&mut self) -> RedisResult<Iter<'_, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0);
+        Ok(self.cluster_scan(c))
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster with options.
+    #[allow(deprecated)]
+    pub fn scan_options<RV: FromRedisValue>(
+        &mut self,
+        opts: ScanOptions,
+    ) -> RedisResult<Iter<'_, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0).arg(opts);
+        Ok(self.cluster_scan(c))
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster for keys matching a pattern.
+    #[allow(deprecated)]
+    pub fn scan_match<P: ToRedisArgs, RV: FromRedisValue>(
+        &mut self,
+        pattern: P,
+    ) -> RedisResult<Iter<'_, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0).arg("MATCH").arg(pattern);
+        Ok(self.cluster_scan(c))
+    }
+
+    #[allow(deprecated)]
+    fn cluster_scan<RV: FromRedisValue>(&mut self, cmd: Cmd) -> Iter<'_, RV> {
+        let primaries = self
+            .topology()
+            .primaries()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        Iter::cluster_scan(cmd, primaries, move |cmd, routing| {
+            self.route_command(cmd, routing)
+        })
+    }
 }
 
 const MULTI: &[u8] = "*1\r\n$5\r\nMULTI\r\n".as_bytes();
@@ -966,6 +1282,7 @@ impl<C: Connect + ConnectionLike> ConnectionLike for ClusterConnection<C> {
     }
 
     fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        check_script_keys(cmd)?;
         let routing = RoutingInfo::for_routable(cmd);
         self.request(Input::Cmd(cmd), routing).map(|res| res.into())
     }
@@ -977,6 +1294,7 @@ impl<C: Connect + ConnectionLike> ConnectionLike for ClusterConnection<C> {
             cmd
         };
         let value = parse_redis_value(actual_cmd)?;
+        check_script_keys(&value)?;
         let routing = RoutingInfo::for_routable(&value);
         self.request(
             Input::Slice {
@@ -1014,6 +1332,33 @@ impl<C: Connect + ConnectionLike> ConnectionLike for ClusterConnection<C> {
         .map(|res| res.into())
     }
 
+    fn req_pipeline(
+        &mut self,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let cmd = pipeline.get_packed_pipeline();
+        if !pipeline.is_transaction() {
+            return self.req_packed_commands(&cmd, offset, count);
+        }
+        // Transactions are sent as a unit to the primary which serves all of their keys, and are
+        // retried as a unit after redirections.
+        let route = route_for_atomic_commands(pipeline.cmd_iter())?.map_or(
+            SingleNodeRoutingInfo::Random,
+            SingleNodeRoutingInfo::SpecificNode,
+        );
+        self.request(
+            Input::Commands {
+                cmd: &cmd,
+                offset,
+                count,
+            },
+            Some(RoutingInfo::SingleNode(route)),
+        )
+        .map(|res| res.into())
+    }
+
     fn get_db(&self) -> i64 {
         0
     }
@@ -1101,7 +1446,7 @@ pub(crate) fn get_connection_info(
     })
 }
 
-fn split_node_address(node: &str) -> RedisResult<(String, u16)> {
+pub(crate) fn split_node_address(node: &str) -> RedisResult<(String, u16)> {
     let invalid_error =
         || RedisError::from((ErrorKind::InvalidClientConfig, "Invalid node string"));
     node.rsplit_once(':')
@@ -1137,16 +1482,85 @@ pub(crate) fn get_connection_addr(
     }
 }
 
+/// Command which reports the availability zone of a node, which is read by [`parse_availability_zone`].
+pub(crate) fn availability_zone_cmd() -> Cmd {
+    let mut cmd = Cmd::new();
+    cmd.arg("INFO").arg("SERVER");
+    cmd
+}
+
+/// Returns the availability zone from the response of [`availability_zone_cmd`], if the node reported one.
+pub(crate) fn parse_availability_zone(info: InfoDict) -> Option<String> {
+    info.get::<String>("availability_zone")
+        .filter(|availability_zone| !availability_zone.is_empty())
+}
+
 pub(crate) fn slot_cmd() -> Cmd {
     let mut cmd = Cmd::new();
     cmd.arg("CLUSTER").arg("SLOTS");
     cmd
 }
 
+/// Returns true if `CLUSTER SHARDS` failed because the node doesn't know the subcommand, so it never will support it.
+/// Other errors, like `LOADING`, `CLUSTERDOWN` or a reply which can't be parsed, might not happen on the next refresh.
+pub(crate) fn is_shards_unsupported(err: &RedisError) -> bool {
+    err.kind() == ErrorKind::ResponseError
+        && err
+            .detail()
+            .is_some_and(|detail| detail.to_ascii_lowercase().contains("unknown subcommand"))
+}
+
+pub(crate) fn shards_cmd() -> Cmd {
+    let mut cmd = Cmd::new();
+    cmd.arg("CLUSTER").arg("SHARDS");
+    cmd
+}
+
+/// Command which reports the current epoch of a node, which is read by [`parse_current_epoch`].
+#[cfg(feature = "cluster-async")]
+pub(crate) fn cluster_info_cmd() -> Cmd {
+    let mut cmd = Cmd::new();
+    cmd.arg("CLUSTER").arg("INFO");
+    cmd
+}
+
+/// Returns the current epoch from the response of [`cluster_info_cmd`], which is the highest
+/// config epoch the node is aware of.
+#[cfg(feature = "cluster-async")]
+pub(crate) fn parse_current_epoch(info: InfoDict) -> Option<u64> {
+    info.get("cluster_current_epoch")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_shards_unsupported() {
+        let error = |reply: &str| {
+            crate::parse_redis_value(reply.as_bytes())
+                .and_then(|value| value.extract_error())
+                .unwrap_err()
+        };
+        assert!(is_shards_unsupported(&error(
+            "-ERR unknown subcommand 'SHARDS'. Try CLUSTER HELP.\r\n"
+        )));
+        assert!(is_shards_unsupported(&error(
+            "-ERR Unknown subcommand or wrong number of arguments for 'SHARDS'. Try CLUSTER HELP\r\n"
+        )));
+        assert!(!is_shards_unsupported(&error(
+            "-LOADING Redis is loading the dataset in memory\r\n"
+        )));
+        assert!(!is_shards_unsupported(&error(
+            "-CLUSTERDOWN The cluster is down\r\n"
+        )));
+        assert!(!is_shards_unsupported(&error("-TRYAGAIN\r\n")));
+        assert!(!is_shards_unsupported(&RedisError::from((
+            ErrorKind::TypeError,
+            "Invalid shards"
+        ))));
+    }
+
     #[test]
     fn parse_cluster_node_host_port() {
         let cases = vec![
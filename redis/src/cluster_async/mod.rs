@@ -96,30 +96,45 @@ use std::{
     future::Future,
     io, mem,
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
     task::{self, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 mod request;
 mod routing;
 use crate::{
     aio::{check_resp3, ConnectionLike, HandleContainer, MultiplexedConnection, Runtime},
-    cluster::{get_connection_info, slot_cmd},
+    cluster::NodeHealthStats,
+    cluster::{
+        availability_zone_cmd, cluster_info_cmd, get_connection_info, is_shards_unsupported,
+        parse_availability_zone, parse_current_epoch, shards_cmd, slot_cmd,
+    },
     cluster_client::ClusterParams,
+    cluster_node_health::NodeHealthTracker,
+    cluster_read_strategy::record_request,
     cluster_routing::{
-        MultipleNodeRoutingInfo, Redirect, ResponsePolicy, RoutingInfo, SingleNodeRoutingInfo,
-        Slot, SlotMap,
+        check_script_keys, is_blocking_read, route_for_atomic_commands, MultipleNodeRoutingInfo,
+        Redirect, ResponsePolicy, RoutingInfo, SingleNodeRoutingInfo, Slot, SlotMap,
+    },
+    cluster_topology::{
+        group_views, parse_shards, parse_slots, same_topology, select_view, ClusterTopology,
+        SlotRange,
     },
-    cluster_topology::parse_slots,
     cmd,
     subscription_tracker::SubscriptionTracker,
     types::closed_connection_error,
-    AsyncConnectionConfig, Cmd, ConnectionInfo, ErrorKind, IntoConnectionInfo, RedisError,
-    RedisFuture, RedisResult, ToRedisArgs, Value,
+    AsyncConnectionConfig, Cmd, ConnectionInfo, ErrorKind, FromRedisValue, IntoConnectionInfo,
+    RedisError, RedisFuture, RedisResult, ScanOptions, ToRedisArgs, Value,
 };
+// `AsyncIter` is deprecated unless `safe_iterators` is enabled, cluster scans return it like other scans.
+#[allow(deprecated)]
+use crate::AsyncIter;
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use crate::caching::{CacheManager, CacheStatistics};
 use crate::ProtocolVersion;
 use futures_sink::Sink;
@@ -134,12 +149,27 @@ use request::{CmdArg, PendingRequest, Request, RequestState, Retry};
 use routing::{route_for_pipeline, InternalRoutingInfo, InternalSingleNodeRouting};
 use tokio::sync::{mpsc, oneshot, RwLock};
 
+/// Receives the slot map of an async cluster connection whenever it changes.
+///
+/// Listeners are called while the topology is being refreshed, so they must not block.
+pub trait SlotMapChangeListener: Send + Sync + 'static {
+    /// Called with the slot ranges of the new slot map, ordered by their first slot.
+    fn slot_map_changed(&self, slot_ranges: &[SlotRange]);
+}
+
+impl<Func: Fn(&[SlotRange]) + Send + Sync + 'static> SlotMapChangeListener for Func {
+    fn slot_map_changed(&self, slot_ranges: &[SlotRange]) {
+        self(slot_ranges)
+    }
+}
+
 struct ClientSideState {
     protocol: ProtocolVersion,
     _task_handle: HandleContainer,
+    _topology_checks_handle: Option<HandleContainer>,
     response_timeout: Option<Duration>,
     runtime: Runtime,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     cache_manager: Option<CacheManager>,
 }
 
@@ -151,6 +181,7 @@ struct ClientSideState {
 pub struct ClusterConnection<C = MultiplexedConnection> {
     state: Arc<ClientSideState>,
     sender: mpsc::Sender<Message<C>>,
+    core: Core<C>,
 }
 
 impl<C> ClusterConnection<C>
@@ -163,12 +194,20 @@ where
     ) -> RedisResult<ClusterConnection<C>> {
         let protocol = cluster_params.protocol.unwrap_or_default();
         let response_timeout = cluster_params.response_timeout;
-        #[cfg(feature = "cache-aio")]
+        let topology_checks_interval = cluster_params.topology_checks_interval;
+        #[cfg(feature = "cache")]
         let cache_manager = cluster_params.cache_manager.clone();
         let runtime = Runtime::locate();
         ClusterConnInner::new(initial_nodes, cluster_params)
             .await
             .map(|inner| {
+                let core = inner.inner.clone();
+                let _topology_checks_handle = topology_checks_interval.map(|interval| {
+                    HandleContainer::new(runtime.spawn(ClusterConnInner::periodic_topology_checks(
+                        Arc::downgrade(&inner.inner),
+                        interval,
+                    )))
+                });
                 let (sender, mut receiver) = mpsc::channel::<Message<_>>(100);
                 let stream = async move {
                     let _ = stream::poll_fn(move |cx| receiver.poll_recv(cx))
@@ -180,18 +219,26 @@ where
 
                 ClusterConnection {
                     sender,
+                    core,
                     state: Arc::new(ClientSideState {
                         protocol,
                         _task_handle,
+                        _topology_checks_handle,
                         response_timeout,
                         runtime,
-                        #[cfg(feature = "cache-aio")]
+                        #[cfg(feature = "cache")]
                         cache_manager,
                     }),
                 }
             })
     }
 
+    /// Returns true if the task driving the connection has stopped, so the connection can't be used anymore.
+    #[cfg(feature = "deadpool")]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
     /// Send a command to the given `routing`, and aggregate the response according to `response_policy`.
     pub async fn route_command(&mut self, cmd: &Cmd, routing: RoutingInfo) -> RedisResult<Value> {
         trace!("send_packed_command");
@@ -233,6 +280,71 @@ where
         }
     }
 
+    /// Returns a snapshot of the slot map which is used to route commands.
+    pub async fn topology(&self) -> ClusterTopology {
+        ClusterTopology::new(self.core.conn_lock.read().await.1.slots())
+    }
+
+    /// Returns the health of the nodes this connection sent requests to, by their address.
+    ///
+    /// The health is always tracked, but circuits only open if a
+    /// [`CircuitBreakerConfig`](crate::cluster::CircuitBreakerConfig) is set.
+    pub fn node_health(&self) -> HashMap<String, NodeHealthStats> {
+        self.core.node_health.snapshot()
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster.
+    ///
+    /// Each primary is scanned with its own cursor, and primaries which are replaced by a failover
+    /// or receive slots during the scan are scanned again, so a key might be returned multiple times.
+    #[allow(deprecated)]
+    pub async fn scan<'a, RV: FromRedisValue + 'a>(&'a mut self) -> RedisResult<AsyncIter<'a, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0);
+        Ok(self.cluster_scan(c).await)
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster with options.
+    #[allow(deprecated)]
+    pub async fn scan_options<'a, RV: FromRedisValue + 'a>(
+        &'a mut self,
+        opts: ScanOptions,
+    ) -> RedisResult<AsyncIter<'a, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0).arg(opts);
+        Ok(self.cluster_scan(c).await)
+    }
+
+    /// Incrementally iterate the keys space of every primary in the cluster for keys matching a pattern.
+    #[allow(deprecated)]
+    pub async fn scan_match<'a, P: ToRedisArgs, RV: FromRedisValue + 'a>(
+        &'a mut self,
+        pattern: P,
+    ) -> RedisResult<AsyncIter<'a, RV>> {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(0).arg("MATCH").arg(pattern);
+        Ok(self.cluster_scan(c).await)
+    }
+
+    #[allow(deprecated)]
+    async fn cluster_scan<'a, RV: FromRedisValue + 'a>(
+        &'a mut self,
+        cmd: Cmd,
+    ) -> AsyncIter<'a, RV> {
+        let primaries = self
+            .topology()
+            .await
+            .primaries()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let connection = self.clone();
+        AsyncIter::cluster_scan(cmd, primaries, move |cmd, routing| {
+            let mut connection = connection.clone();
+            Box::pin(async move { connection.route_command(&cmd, routing).await })
+        })
+    }
+
     /// Send commands in `pipeline` to the given `route`. If `route` is [None], it will be sent to a random node.
     pub async fn route_pipeline<'a>(
         &'a mut self,
@@ -352,11 +464,77 @@ where
         Ok(())
     }
     /// Gets [`CacheStatistics`] for cluster connection if caching is enabled.
-    #[cfg(feature = "cache-aio")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
     pub fn get_cache_statistics(&self) -> Option<CacheStatistics> {
         self.state.cache_manager.as_ref().map(|cm| cm.statistics())
     }
+
+    /// Removes given keys from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_keys<K: ToRedisArgs>(&self, keys: K) {
+        if let Some(cache_manager) = &self.state.cache_manager {
+            cache_manager.invalidate_keys(&keys.to_redis_args());
+        }
+    }
+
+    /// Removes keys matching the glob-style pattern from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_pattern(&self, pattern: impl AsRef<[u8]>) {
+        if let Some(cache_manager) = &self.state.cache_manager {
+            cache_manager.invalidate_pattern(pattern.as_ref());
+        }
+    }
+
+    /// Removes every value from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn flush_cache(&self) {
+        if let Some(cache_manager) = &self.state.cache_manager {
+            cache_manager.flush();
+        }
+    }
+
+    /// Reads given keys with `GET` and stores their values in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_keys<K: ToRedisArgs>(&mut self, keys: K) -> RedisResult<()> {
+        let commands = crate::caching::cmd::prefetch_key_commands(keys.to_redis_args());
+        self.prefetch_cache_commands(&commands).await
+    }
+
+    /// Sends given commands and stores their replies in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub async fn prefetch_cache_commands(&mut self, commands: &[Cmd]) -> RedisResult<()> {
+        if self.state.cache_manager.is_none() || commands.is_empty() {
+            return Ok(());
+        }
+        // A pipeline can only contain keys of a single slot, so commands are grouped by their slot
+        // and every group is sent in its own pipeline.
+        let mut slots: HashMap<Option<u16>, Vec<&Cmd>> = HashMap::new();
+        for cmd in commands {
+            let slot = match RoutingInfo::for_routable(cmd) {
+                Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(route))) => {
+                    Some(route.slot())
+                }
+                _ => None,
+            };
+            slots.entry(slot).or_default().push(cmd);
+        }
+        future::try_join_all(slots.into_values().map(|commands| {
+            let pipeline = crate::caching::cmd::prefetch_pipeline(commands);
+            let mut connection = self.clone();
+            async move {
+                let _: Vec<Value> = pipeline.query_async(&mut connection).await?;
+                Ok::<_, RedisError>(())
+            }
+        }))
+        .await?;
+        Ok(())
+    }
 }
 
 type ConnectionMap<C> = HashMap<String, C>;
@@ -370,6 +548,11 @@ struct InnerCore<C> {
     pending_requests: Mutex<Vec<PendingRequest<C>>>,
     initial_nodes: Vec<ConnectionInfo>,
     subscription_tracker: Option<Mutex<SubscriptionTracker>>,
+    node_health: Arc<NodeHealthTracker>,
+    // Set when the circuit of a primary opens, so that the slots are refreshed by the next poll.
+    slot_refresh_requested: AtomicBool,
+    // Set once a node rejects `CLUSTER SHARDS`, so the topology is discovered with `CLUSTER SLOTS` from then on.
+    shards_unsupported: AtomicBool,
 }
 
 type Core<C> = Arc<InnerCore<C>>;
@@ -450,12 +633,24 @@ where
         } else {
             None
         };
+        let node_health = Arc::new(NodeHealthTracker::new(
+            cluster_params.circuit_breaker.clone(),
+        ));
         let inner = Arc::new(InnerCore {
-            conn_lock: RwLock::new((connections, SlotMap::new(cluster_params.read_from_replicas))),
+            conn_lock: RwLock::new((
+                connections,
+                SlotMap::new(
+                    cluster_params.read_from_replicas.clone(),
+                    Some(node_health.clone()),
+                ),
+            )),
             cluster_params,
             pending_requests: Mutex::new(Vec::new()),
             initial_nodes: initial_nodes.to_vec(),
             subscription_tracker,
+            node_health,
+            slot_refresh_requested: AtomicBool::new(false),
+            shards_unsupported: AtomicBool::new(false),
         });
         let connection = ClusterConnInner {
             inner,
@@ -565,7 +760,10 @@ where
             let mut write_lock = inner.conn_lock.write().await;
             *write_lock = (
                 connection_map,
-                SlotMap::new(inner.cluster_params.read_from_replicas),
+                SlotMap::new(
+                    inner.cluster_params.read_from_replicas.clone(),
+                    Some(inner.node_health.clone()),
+                ),
             );
             drop(write_lock);
             if let Err(err) = Self::refresh_slots(inner.clone()).await {
@@ -587,36 +785,208 @@ where
     async fn refresh_slots(inner: Core<C>) -> RedisResult<()> {
         let mut write_guard = inner.conn_lock.write().await;
         let (connections, slots) = &mut *write_guard;
-
-        let mut result = Ok(());
-        for (addr, conn) in &mut *connections {
-            result = async {
-                let value = conn
-                    .req_packed_command(&slot_cmd())
-                    .await
-                    .and_then(|value| value.extract_error())?;
-                let v: Vec<Slot> = parse_slots(
-                    value,
-                    inner.cluster_params.tls,
-                    addr.rsplit_once(':').unwrap().0,
-                )?;
-                Self::build_slot_map(slots, v)
-            }
-            .await;
-            if result.is_ok() {
-                break;
-            }
+        let previous_slots = slots.slots();
+
+        let candidates: Vec<(String, C)> = connections
+            .iter()
+            .map(|(addr, conn)| (addr.clone(), conn.clone()))
+            .collect();
+        if let Some(view) = Self::consensus_topology(&inner, candidates).await? {
+            Self::build_slot_map(slots, view)?;
+        }
+        let addresses = slots.addresses_for_all_nodes();
+        inner.node_health.retain_nodes(&addresses);
+        if let Some(strategy) = &inner.cluster_params.read_from_replicas {
+            strategy.retain_nodes(&addresses);
         }
-        result?;
 
+        let new_slots = slots.slots();
         let mut nodes = slots.values().flatten().cloned().collect::<Vec<_>>();
         nodes.sort_unstable();
         nodes.dedup();
         Self::refresh_connections_locked(&inner, connections, nodes).await;
+        drop(write_guard);
+
+        if let Some(listener) = &inner.cluster_params.slot_map_change_listener {
+            if !same_topology(&previous_slots, &new_slots) {
+                let slot_ranges: Vec<SlotRange> =
+                    new_slots.into_iter().map(SlotRange::from).collect();
+                listener.slot_map_changed(&slot_ranges);
+            }
+        }
 
         Ok(())
     }
 
+    // Queries the views of the topology of `topology_consensus_nodes` nodes, and selects the view which most of
+    // them agree on. Nodes which fail to answer are replaced by the next candidates. Returns None if there are
+    // no candidates, and the last error if none of them answered.
+    async fn consensus_topology(
+        inner: &Core<C>,
+        candidates: Vec<(String, C)>,
+    ) -> RedisResult<Option<Vec<Slot>>> {
+        let consensus_nodes = inner.cluster_params.topology_consensus_nodes.max(1);
+        let mut candidates = candidates.into_iter();
+        let mut reported = Vec::new();
+        let mut last_err = None;
+        while reported.len() < consensus_nodes {
+            let batch: Vec<_> = candidates
+                .by_ref()
+                .take(consensus_nodes - reported.len())
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+            let views = future::join_all(batch.into_iter().map(|(addr, mut conn)| async move {
+                let view = Self::query_topology(inner, &mut conn, &addr).await;
+                (addr, conn, view)
+            }))
+            .await;
+            for (addr, conn, view) in views {
+                match view {
+                    Ok(view) => reported.push((addr, conn, view)),
+                    Err(err) => last_err = Some(err),
+                }
+            }
+        }
+        if reported.is_empty() {
+            return last_err.map_or(Ok(None), Err);
+        }
+
+        let mut connections: HashMap<String, C> = HashMap::new();
+        let views = group_views(
+            reported
+                .into_iter()
+                .map(|(addr, conn, view)| {
+                    connections.insert(addr.clone(), conn);
+                    (addr, view)
+                })
+                .collect(),
+        );
+        let mut epochs = HashMap::new();
+        if views.len() > 1 {
+            warn!(
+                "Nodes disagree on the cluster topology: {}",
+                views
+                    .iter()
+                    .map(|view| format!("{:?} reported {:?}", view.nodes, view.slots))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let majority = views[0].nodes.len();
+            if views
+                .get(1)
+                .is_some_and(|view| view.nodes.len() == majority)
+            {
+                let tied_nodes = views
+                    .iter()
+                    .take_while(|view| view.nodes.len() == majority)
+                    .flat_map(|view| view.nodes.iter());
+                epochs = future::join_all(tied_nodes.map(|addr| {
+                    let mut conn = connections[addr].clone();
+                    async move {
+                        let epoch = cluster_info_cmd()
+                            .query_async(&mut conn)
+                            .await
+                            .ok()
+                            .and_then(parse_current_epoch);
+                        (addr.clone(), epoch)
+                    }
+                }))
+                .await
+                .into_iter()
+                .filter_map(|(addr, epoch)| Some((addr, epoch?)))
+                .collect();
+            }
+        }
+        let selected = select_view(views, &epochs);
+        if let Some(selected) = &selected {
+            debug!(
+                "Using the cluster topology reported by {:?}",
+                selected.nodes
+            );
+        }
+        Ok(selected.map(|view| view.slots))
+    }
+
+    // Periodically compares the slot map with the views of a few nodes, and refreshes it if they differ.
+    // Stops once the connection is dropped.
+    async fn periodic_topology_checks(inner: Weak<InnerCore<C>>, interval: Duration) {
+        loop {
+            boxed_sleep(interval).await;
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+            if Self::topology_changed(&inner).await {
+                debug!("Topology change detected by periodic check");
+                if let Err(err) = Self::refresh_slots(inner).await {
+                    warn!("Failed to refresh slots after topology change: `{err}`");
+                }
+            }
+        }
+    }
+
+    async fn topology_changed(inner: &Core<C>) -> bool {
+        const CHECKED_NODES: usize = 3;
+
+        let (current_slots, connections) = {
+            let read_guard = inner.conn_lock.read().await;
+            let connections: Vec<(String, C)> = read_guard
+                .0
+                .iter()
+                .map(|(addr, conn)| (addr.clone(), conn.clone()))
+                .choose_multiple(&mut rng(), CHECKED_NODES);
+            (read_guard.1.slots(), connections)
+        };
+
+        let views = future::join_all(connections.into_iter().map(|(addr, mut conn)| async move {
+            Self::query_topology(inner, &mut conn, &addr).await
+        }))
+        .await;
+
+        views.into_iter().any(|view| match view {
+            Ok(view) => !same_topology(&current_slots, &view),
+            Err(err) => {
+                debug!("Failed to check topology: {err}");
+                false
+            }
+        })
+    }
+
+    // Discovers the topology with `CLUSTER SHARDS`, falling back to `CLUSTER SLOTS` for servers
+    // older than Redis 7, which don't support it.
+    async fn query_topology(inner: &Core<C>, conn: &mut C, addr: &str) -> RedisResult<Vec<Slot>> {
+        let Some((host, _)) = addr.rsplit_once(':') else {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Invalid node address",
+                addr.to_string(),
+            )));
+        };
+        let tls = inner.cluster_params.tls;
+        if !inner.shards_unsupported.load(Ordering::Relaxed) {
+            let shards = conn
+                .req_packed_command(&shards_cmd())
+                .await
+                .and_then(|value| value.extract_error())
+                .and_then(|value| parse_shards(value, tls, host));
+            match shards {
+                Ok(slots) => return Ok(slots),
+                Err(err) => {
+                    debug!("Falling back to CLUSTER SLOTS: {err}");
+                    if is_shards_unsupported(&err) {
+                        inner.shards_unsupported.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+        let value = conn
+            .req_packed_command(&slot_cmd())
+            .await
+            .and_then(|value| value.extract_error())?;
+        parse_slots(value, tls, host)
+    }
+
     async fn refresh_connections_locked(
         inner: &Core<C>,
         connections: &mut ConnectionMap<C>,
@@ -854,13 +1224,30 @@ where
             }
         };
 
-        match Self::get_connection(route, core).await {
-            Ok((addr, mut conn)) => conn
-                .req_packed_command(&cmd)
-                .await
-                .and_then(|value| value.extract_error())
-                .map(Response::Single)
-                .map_err(|err| (addr.into(), err)),
+        let latency_strategy = core
+            .cluster_params
+            .read_from_replicas
+            .as_ref()
+            .filter(|strategy| strategy.uses_latency() && !is_blocking_read(cmd.as_ref()));
+        match Self::get_connection(route, core.clone()).await {
+            Ok((addr, mut conn)) => {
+                let start = Instant::now();
+                let result = conn
+                    .req_packed_command(&cmd)
+                    .await
+                    .and_then(|value| value.extract_error());
+                let latency = start.elapsed();
+                // Only replicas are chosen by the strategy, so reads served by primaries aren't reported.
+                if let Some(strategy) = latency_strategy {
+                    if core.conn_lock.read().await.1.is_replica(&addr) {
+                        record_request(strategy.as_ref(), &addr, &result, latency);
+                    }
+                }
+                Self::record_node_health(&core, &addr, &result, latency).await;
+                result
+                    .map(Response::Single)
+                    .map_err(|err| (addr.into(), err))
+            }
             Err(err) => Err((OperationTarget::NotFound, err)),
         }
     }
@@ -870,18 +1257,43 @@ where
         offset: usize,
         count: usize,
         conn: impl Future<Output = RedisResult<(String, C)>>,
+        core: Core<C>,
     ) -> OperationResult {
         match conn.await {
-            Ok((addr, mut conn)) => conn
-                .req_packed_commands(&pipeline, offset, count)
-                .await
-                .and_then(Value::extract_error_vec)
-                .map(Response::Multiple)
-                .map_err(|err| (OperationTarget::Node { address: addr }, err)),
+            Ok((addr, mut conn)) => {
+                let start = Instant::now();
+                let result = conn.req_packed_commands(&pipeline, offset, count).await;
+                Self::record_node_health(&core, &addr, &result, start.elapsed()).await;
+                result
+                    .and_then(Value::extract_error_vec)
+                    .map(Response::Multiple)
+                    .map_err(|err| (OperationTarget::Node { address: addr }, err))
+            }
             Err(err) => Err((OperationTarget::NotFound, err)),
         }
     }
 
+    // Requests a refresh of the slots if the request opened the circuit of a primary, since it might have failed over.
+    async fn record_node_health<T>(
+        core: &Core<C>,
+        addr: &str,
+        result: &RedisResult<T>,
+        latency: Duration,
+    ) {
+        if core.node_health.record(addr, result, latency)
+            && core
+                .conn_lock
+                .read()
+                .await
+                .1
+                .addresses_for_all_primaries()
+                .contains(addr)
+        {
+            debug!("Circuit of primary {addr} opened, refreshing slots");
+            core.slot_refresh_requested.store(true, Ordering::Relaxed);
+        }
+    }
+
     async fn try_request(cmd: CmdArg<C>, core: Core<C>) -> OperationResult {
         match cmd {
             CmdArg::Cmd { cmd, routing } => Self::try_cmd_request(cmd, routing, core).await,
@@ -895,7 +1307,8 @@ where
                     pipeline,
                     offset,
                     count,
-                    Self::get_connection(route, core),
+                    Self::get_connection(route, core.clone()),
+                    core,
                 )
                 .await
             }
@@ -1088,6 +1501,14 @@ where
             poll_flush_action = poll_flush_action.change_state(next);
         }
 
+        if self
+            .inner
+            .slot_refresh_requested
+            .swap(false, Ordering::Relaxed)
+        {
+            poll_flush_action = poll_flush_action.change_state(PollFlushAction::RebuildSlots);
+        }
+
         if !matches!(poll_flush_action, PollFlushAction::None) || self.in_flight_requests.is_empty()
         {
             Poll::Ready(poll_flush_action)
@@ -1261,9 +1682,13 @@ where
     C: ConnectionLike + Send + Clone + Unpin + Sync + Connect + 'static,
 {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
-        let routing = RoutingInfo::for_routable(cmd)
-            .unwrap_or(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random));
-        self.route_command(cmd, routing).boxed()
+        async move {
+            check_script_keys(cmd)?;
+            let routing = RoutingInfo::for_routable(cmd)
+                .unwrap_or(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random));
+            self.route_command(cmd, routing).await
+        }
+        .boxed()
     }
 
     fn req_packed_commands<'a>(
@@ -1273,7 +1698,12 @@ where
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
         async move {
-            let route = route_for_pipeline(pipeline)?;
+            // Transactions are sent to the primary which serves all of their keys.
+            let route = if pipeline.is_transaction() {
+                route_for_atomic_commands(pipeline.cmd_iter())?
+            } else {
+                route_for_pipeline(pipeline)?
+            };
             self.route_pipeline(pipeline, offset, count, route.into())
                 .await
         }
@@ -1327,13 +1757,13 @@ async fn connect_and_check<C>(node: &str, params: ClusterParams) -> RedisResult<
 where
     C: ConnectionLike + Connect + Send + 'static,
 {
-    let read_from_replicas = params.read_from_replicas;
+    let read_from_replicas = params.read_from_replicas.clone();
     let connection_timeout = params.connection_timeout;
     let response_timeout = params.response_timeout;
     let push_sender = params.async_push_sender.clone();
     let tcp_settings = params.tcp_settings.clone();
     let dns_resolver = params.async_dns_resolver.clone();
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     let cache_manager = params.cache_manager.clone();
     let info = get_connection_info(node, params)?;
     let mut config = AsyncConnectionConfig::default()
@@ -1348,7 +1778,7 @@ where
     if let Some(resolver) = dns_resolver {
         config = config.set_dns_resolver_internal(resolver.clone());
     }
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     if let Some(cache_manager) = cache_manager {
         config = config.set_cache_manager(cache_manager.clone_and_increase_epoch());
     }
@@ -1360,7 +1790,7 @@ where
         }
     };
 
-    let check = if read_from_replicas {
+    let check = if read_from_replicas.is_some() {
         // If READONLY is sent to primary nodes, it will have no effect
         cmd("READONLY")
     } else {
@@ -1368,6 +1798,15 @@ where
     };
 
     conn.req_packed_command(&check).await?;
+    if let Some(strategy) = read_from_replicas.filter(|strategy| strategy.uses_availability_zone())
+    {
+        let availability_zone = availability_zone_cmd()
+            .query_async(&mut conn)
+            .await
+            .ok()
+            .and_then(parse_availability_zone);
+        strategy.node_connected(node, availability_zone.as_deref());
+    }
     Ok(conn)
 }
 
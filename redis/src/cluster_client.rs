@@ -1,14 +1,15 @@
 #[cfg(feature = "cluster-async")]
 use crate::aio::AsyncPushSender;
-#[cfg(all(feature = "cache-aio", feature = "cluster-async"))]
+#[cfg(feature = "cache")]
 use crate::caching::{CacheConfig, CacheManager};
+use crate::cluster_node_health::CircuitBreakerConfig;
+use crate::cluster_read_strategy::{RandomReplica, ReadFromReplicaStrategy};
 use crate::connection::{ConnectionAddr, ConnectionInfo, IntoConnectionInfo};
 #[cfg(feature = "cluster-async")]
 use crate::io::{tcp::TcpSettings, AsyncDNSResolver};
 use crate::types::{ErrorKind, ProtocolVersion, RedisError, RedisResult};
 use crate::{cluster, cluster::TlsMode};
 use rand::Rng;
-#[cfg(feature = "cluster-async")]
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -27,7 +28,8 @@ use crate::tls::{retrieve_tls_certificates, TlsCertificates};
 struct BuilderParams {
     password: Option<String>,
     username: Option<String>,
-    read_from_replicas: bool,
+    read_from_replicas: Option<Arc<dyn ReadFromReplicaStrategy>>,
+    circuit_breaker: Option<CircuitBreakerConfig>,
     tls: Option<TlsMode>,
     #[cfg(feature = "tls-rustls")]
     certs: Option<TlsCertificates>,
@@ -43,7 +45,13 @@ struct BuilderParams {
     pub(crate) tcp_settings: TcpSettings,
     #[cfg(feature = "cluster-async")]
     async_dns_resolver: Option<Arc<dyn AsyncDNSResolver>>,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cluster-async")]
+    topology_checks_interval: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    topology_consensus_nodes: usize,
+    #[cfg(feature = "cluster-async")]
+    slot_map_change_listener: Option<Arc<dyn cluster_async::SlotMapChangeListener>>,
+    #[cfg(feature = "cache")]
     cache_config: Option<CacheConfig>,
 }
 
@@ -89,7 +97,11 @@ impl RetryParams {
 pub(crate) struct ClusterParams {
     pub(crate) password: Option<String>,
     pub(crate) username: Option<String>,
-    pub(crate) read_from_replicas: bool,
+    /// Strategy which chooses the replica serving reads. When None, reads are routed to primaries.
+    pub(crate) read_from_replicas: Option<Arc<dyn ReadFromReplicaStrategy>>,
+    /// Thresholds at which requests are routed away from unhealthy nodes. When None, the health of
+    /// nodes is tracked but doesn't affect routing.
+    pub(crate) circuit_breaker: Option<CircuitBreakerConfig>,
     /// tls indicates tls behavior of connections.
     /// When Some(TlsMode), connections use tls and verify certification depends on TlsMode.
     /// When None, connections do not use tls.
@@ -105,7 +117,17 @@ pub(crate) struct ClusterParams {
     pub(crate) tcp_settings: TcpSettings,
     #[cfg(feature = "cluster-async")]
     pub(crate) async_dns_resolver: Option<Arc<dyn AsyncDNSResolver>>,
-    #[cfg(all(feature = "cache-aio", feature = "cluster-async"))]
+    /// Interval between background checks of the topology. When None, the topology is only
+    /// refreshed after errors.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) topology_checks_interval: Option<Duration>,
+    /// Number of nodes whose views of the topology are compared when refreshing the slot map.
+    /// Values below 1 are treated as 1, which trusts the first node that answers.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) topology_consensus_nodes: usize,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) slot_map_change_listener: Option<Arc<dyn cluster_async::SlotMapChangeListener>>,
+    #[cfg(feature = "cache")]
     pub(crate) cache_manager: Option<CacheManager>,
 }
 
@@ -136,16 +158,17 @@ impl ClusterParams {
             tls_params
         };
 
-        #[cfg(all(feature = "cache-aio", feature = "cluster-async"))]
+        #[cfg(feature = "cache")]
         let cache_manager = value
             .cache_config
             .as_ref()
-            .map(|cache_config| CacheManager::new(*cache_config));
+            .map(|cache_config| CacheManager::new(cache_config.clone()));
 
         Ok(Self {
             password: value.password,
             username: value.username,
             read_from_replicas: value.read_from_replicas,
+            circuit_breaker: value.circuit_breaker,
             tls: value.tls,
             retry_params: value.retries_configuration,
             tls_params,
@@ -158,7 +181,13 @@ impl ClusterParams {
             tcp_settings: value.tcp_settings,
             #[cfg(feature = "cluster-async")]
             async_dns_resolver: value.async_dns_resolver,
-            #[cfg(all(feature = "cache-aio", feature = "cluster-async"))]
+            #[cfg(feature = "cluster-async")]
+            topology_checks_interval: value.topology_checks_interval,
+            #[cfg(feature = "cluster-async")]
+            topology_consensus_nodes: value.topology_consensus_nodes,
+            #[cfg(feature = "cluster-async")]
+            slot_map_change_listener: value.slot_map_change_listener,
+            #[cfg(feature = "cache")]
             cache_manager,
         })
     }
@@ -295,6 +324,17 @@ impl ClusterClientBuilder {
             }
         }
 
+        #[cfg(feature = "cache")]
+        if let Some(cache_manager) = cluster_params.cache_manager.take() {
+            // The cluster is identified by its initial nodes, and it only has a single database.
+            let mut nodes: Vec<String> = initial_nodes
+                .iter()
+                .map(|node| node.addr.to_string())
+                .collect();
+            nodes.sort_unstable();
+            cluster_params.cache_manager = Some(cache_manager.with_scope(&nodes.join(","), 0));
+        }
+
         Ok(ClusterClient {
             initial_nodes,
             cluster_params,
@@ -397,8 +437,32 @@ impl ClusterClientBuilder {
     ///
     /// If enabled, then read queries will go to the replica nodes & write queries will go to the
     /// primary nodes. If there are no replica nodes, then all queries will go to the primary nodes.
-    pub fn read_from_replicas(mut self) -> ClusterClientBuilder {
-        self.builder_params.read_from_replicas = true;
+    /// Reads are spread between replicas randomly, see [`ClusterClientBuilder::read_from_replicas_with`]
+    /// for other strategies.
+    pub fn read_from_replicas(self) -> ClusterClientBuilder {
+        self.read_from_replicas_with(RandomReplica)
+    }
+
+    /// Enables reading from replicas for all new connections, with `strategy` choosing the replica
+    /// which serves each read.
+    ///
+    /// See [`crate::cluster::RoundRobinReplica`], [`crate::cluster::AzAffinityReplica`] and
+    /// [`crate::cluster::LowestLatencyReplica`] for the built-in strategies.
+    pub fn read_from_replicas_with(
+        mut self,
+        strategy: impl ReadFromReplicaStrategy,
+    ) -> ClusterClientBuilder {
+        self.builder_params.read_from_replicas = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Enables the circuit breaker for all new connections (default is disabled).
+    ///
+    /// If enabled, reads stop being routed to replicas which are unhealthy according to `config`,
+    /// and the slot map is refreshed when a primary becomes unhealthy. The health of nodes is reported by
+    /// `node_health` of the connections either way.
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> ClusterClientBuilder {
+        self.builder_params.circuit_breaker = Some(config);
         self
     }
 
@@ -433,8 +497,12 @@ impl ClusterClientBuilder {
     /// Use `read_from_replicas()`.
     #[deprecated(since = "0.22.0", note = "Use read_from_replicas()")]
     pub fn readonly(mut self, read_from_replicas: bool) -> ClusterClientBuilder {
-        self.builder_params.read_from_replicas = read_from_replicas;
-        self
+        if read_from_replicas {
+            self.read_from_replicas()
+        } else {
+            self.builder_params.read_from_replicas = None;
+            self
+        }
     }
 
     #[cfg(feature = "cluster-async")]
@@ -488,8 +556,57 @@ impl ClusterClientBuilder {
         self
     }
 
-    /// Sets cache config for [`crate::cluster_async::ClusterConnection`], check CacheConfig for more details.
-    #[cfg(all(feature = "cache-aio", feature = "cluster-async"))]
+    /// Enables periodic checks of the topology of async connections (default is disabled).
+    ///
+    /// Every `interval`, the slot maps reported by a few nodes are compared with the slot map used for routing,
+    /// and the topology is refreshed if they differ. Without these checks, the topology is only refreshed after
+    /// redirections or connection errors, so replicas which are added to the cluster won't serve reads and
+    /// nodes which are removed from it stay connected.
+    #[cfg(feature = "cluster-async")]
+    pub fn periodic_topology_checks(mut self, interval: Duration) -> ClusterClientBuilder {
+        self.builder_params.topology_checks_interval = Some(interval);
+        self
+    }
+
+    /// Sets the number of nodes which are queried for their view of the topology when async connections
+    /// refresh their slot map (default is 1).
+    ///
+    /// By default the view of the first node that answers is trusted, which may be stale if that node is
+    /// partitioned from the rest of the cluster. With more nodes, the view reported by most of them is used,
+    /// and ties are broken in favor of the view reported by the nodes with the highest config epoch.
+    /// Disagreements between the nodes are logged.
+    #[cfg(feature = "cluster-async")]
+    pub fn topology_consensus_nodes(mut self, nodes: usize) -> ClusterClientBuilder {
+        self.builder_params.topology_consensus_nodes = nodes;
+        self
+    }
+
+    /// Sets a listener which is notified whenever the slot map of an async connection changes,
+    /// including when it's discovered as the connection is created.
+    ///
+    /// The listener can be an arbitrary function that handles a slice of [`crate::cluster::SlotRange`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use redis::cluster::{ClusterClientBuilder, SlotRange};
+    /// let config = ClusterClientBuilder::new(vec!["redis://127.0.0.1:6379/"])
+    ///     .periodic_topology_checks(std::time::Duration::from_secs(60))
+    ///     .slot_map_change_listener(|slot_ranges: &[SlotRange]| {
+    ///         println!("Cluster has {} slot ranges", slot_ranges.len());
+    ///     });
+    /// ```
+    #[cfg(feature = "cluster-async")]
+    pub fn slot_map_change_listener(
+        mut self,
+        listener: impl cluster_async::SlotMapChangeListener,
+    ) -> ClusterClientBuilder {
+        self.builder_params.slot_map_change_listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// Sets cache config for [`crate::cluster::ClusterConnection`] and [`crate::cluster_async::ClusterConnection`], check CacheConfig for more details.
+    #[cfg(feature = "cache")]
     pub fn cache_config(mut self, cache_config: CacheConfig) -> Self {
         self.builder_params.cache_config = Some(cache_config);
         self
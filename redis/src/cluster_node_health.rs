@@ -0,0 +1,376 @@
+//! Health of the nodes of a cluster, and the circuit breaker which stops routing requests to unhealthy nodes.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::types::RedisResult;
+
+// Weight of a new sample in the moving averages.
+const SAMPLE_WEIGHT: f64 = 0.2;
+
+/// Thresholds of the circuit breaker of a cluster connection.
+///
+/// The circuit of a node opens when any of the thresholds is crossed. While the circuit of a replica is open,
+/// reads are routed to the other replicas of its slots, or to their primary. When the circuit of a primary opens,
+/// the slot map is refreshed, so that a failover is noticed as soon as possible. After
+/// [`CircuitBreakerConfig::set_open_duration`] the node is tried again, and its circuit closes on the first
+/// request it serves successfully.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use redis::cluster::{CircuitBreakerConfig, ClusterClient};
+///
+/// let nodes = vec!["redis://127.0.0.1:6379/"];
+/// let client = ClusterClient::builder(nodes)
+///     .read_from_replicas()
+///     .circuit_breaker(CircuitBreakerConfig::new().set_latency_threshold(Duration::from_millis(50)))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    error_rate_threshold: f64,
+    min_requests: u32,
+    consecutive_timeouts: u32,
+    latency_threshold: Option<Duration>,
+    open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Creates a circuit breaker which opens after 3 consecutive timeouts, or when half of the requests sent to
+    /// a node fail, and tries the node again after 10 seconds.
+    pub fn new() -> Self {
+        Self {
+            error_rate_threshold: 0.5,
+            min_requests: 10,
+            consecutive_timeouts: 3,
+            latency_threshold: None,
+            open_duration: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets the rate of failed requests, between 0 and 1, at which the circuit opens. Requests fail when the
+    /// connection to the node fails or times out, while errors returned by the node count as successful requests.
+    pub fn set_error_rate_threshold(mut self, error_rate: f64) -> Self {
+        self.error_rate_threshold = error_rate;
+        self
+    }
+
+    /// Sets the number of requests a node must have served before its error rate and latency are considered.
+    pub fn set_min_requests(mut self, min_requests: u32) -> Self {
+        self.min_requests = min_requests;
+        self
+    }
+
+    /// Sets the number of consecutive timeouts after which the circuit opens.
+    pub fn set_consecutive_timeouts(mut self, consecutive_timeouts: u32) -> Self {
+        self.consecutive_timeouts = consecutive_timeouts;
+        self
+    }
+
+    /// Sets the average latency above which the circuit opens. By default latency doesn't open the circuit.
+    pub fn set_latency_threshold(mut self, latency: Duration) -> Self {
+        self.latency_threshold = Some(latency);
+        self
+    }
+
+    /// Sets how long the circuit stays open before the node is tried again.
+    pub fn set_open_duration(mut self, open_duration: Duration) -> Self {
+        self.open_duration = open_duration;
+        self
+    }
+}
+
+/// A snapshot of the health of a cluster node, as observed by a connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeHealthStats {
+    error_rate: f64,
+    consecutive_timeouts: u32,
+    latency: Option<Duration>,
+    circuit_open: bool,
+}
+
+impl NodeHealthStats {
+    /// The moving average of the rate of failed requests, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        self.error_rate
+    }
+
+    /// The number of requests which timed out since the last request that didn't.
+    pub fn consecutive_timeouts(&self) -> u32 {
+        self.consecutive_timeouts
+    }
+
+    /// The moving average of the round trip time of requests, if the node answered any.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Whether requests are currently routed away from the node.
+    pub fn is_circuit_open(&self) -> bool {
+        self.circuit_open
+    }
+}
+
+#[derive(Debug, Default)]
+struct NodeState {
+    requests: u32,
+    error_rate: f64,
+    consecutive_timeouts: u32,
+    latency: Option<Duration>,
+    opened_at: Option<Instant>,
+}
+
+impl NodeState {
+    fn is_open(&self, open_duration: Duration) -> bool {
+        self.opened_at
+            .is_some_and(|opened_at| opened_at.elapsed() < open_duration)
+    }
+
+    fn crossed_threshold(&self, config: &CircuitBreakerConfig) -> bool {
+        if config.consecutive_timeouts > 0
+            && self.consecutive_timeouts >= config.consecutive_timeouts
+        {
+            return true;
+        }
+        if self.requests < config.min_requests {
+            return false;
+        }
+        self.error_rate >= config.error_rate_threshold
+            || config
+                .latency_threshold
+                .zip(self.latency)
+                .is_some_and(|(threshold, latency)| latency > threshold)
+    }
+}
+
+/// Tracks the health of the nodes a connection sends requests to.
+#[derive(Debug)]
+pub(crate) struct NodeHealthTracker {
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    nodes: Mutex<HashMap<String, NodeState>>,
+}
+
+impl NodeHealthTracker {
+    pub(crate) fn new(circuit_breaker: Option<CircuitBreakerConfig>) -> Self {
+        Self {
+            circuit_breaker,
+            nodes: Default::default(),
+        }
+    }
+
+    /// Records the outcome of a request sent to `address`. Returns true if this opened the circuit of the node.
+    ///
+    /// Health is tracked even without a circuit breaker, but then circuits never open.
+    pub(crate) fn record<T>(
+        &self,
+        address: &str,
+        result: &RedisResult<T>,
+        latency: Duration,
+    ) -> bool {
+        let mut nodes = self.nodes.lock().unwrap();
+        let state = nodes.entry(address.to_string()).or_default();
+
+        let failed = match result {
+            Ok(_) => false,
+            Err(err) => err.is_io_error() || err.is_timeout(),
+        };
+        state.requests = state.requests.saturating_add(1);
+        state.error_rate =
+            state.error_rate * (1.0 - SAMPLE_WEIGHT) + if failed { SAMPLE_WEIGHT } else { 0.0 };
+        match result {
+            Err(err) if err.is_timeout() => state.consecutive_timeouts += 1,
+            _ => state.consecutive_timeouts = 0,
+        }
+        if !failed {
+            state.latency = Some(match state.latency {
+                Some(average) => {
+                    average.mul_f64(1.0 - SAMPLE_WEIGHT) + latency.mul_f64(SAMPLE_WEIGHT)
+                }
+                None => latency,
+            });
+        }
+
+        let Some(config) = &self.circuit_breaker else {
+            return false;
+        };
+        if state.is_open(config.open_duration) {
+            return false;
+        }
+        if state.opened_at.is_some() {
+            // The node was tried again after its circuit was open: a failure opens it again, while a success
+            // gives the node a clean slate.
+            if failed {
+                state.opened_at = Some(Instant::now());
+                return true;
+            }
+            *state = NodeState {
+                latency: state.latency,
+                ..Default::default()
+            };
+            return false;
+        }
+        if state.crossed_threshold(config) {
+            state.opened_at = Some(Instant::now());
+            return true;
+        }
+        false
+    }
+
+    /// Returns false while the circuit of the node at `address` is open.
+    pub(crate) fn is_available(&self, address: &str) -> bool {
+        let Some(config) = &self.circuit_breaker else {
+            return true;
+        };
+        self.nodes
+            .lock()
+            .unwrap()
+            .get(address)
+            .map_or(true, |state| !state.is_open(config.open_duration))
+    }
+
+    /// Forgets the nodes which aren't in `addresses`, like the nodes removed from the cluster.
+    pub(crate) fn retain_nodes(&self, addresses: &HashSet<&str>) {
+        self.nodes
+            .lock()
+            .unwrap()
+            .retain(|address, _| addresses.contains(address.as_str()));
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, NodeHealthStats> {
+        let open_duration = self
+            .circuit_breaker
+            .as_ref()
+            .map(|config| config.open_duration);
+        self.nodes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(address, state)| {
+                let health = NodeHealthStats {
+                    error_rate: state.error_rate,
+                    consecutive_timeouts: state.consecutive_timeouts,
+                    latency: state.latency,
+                    circuit_open: open_duration.is_some_and(|duration| state.is_open(duration)),
+                };
+                (address.clone(), health)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ErrorKind, RedisError, Value};
+
+    const NODE: &str = "node1:6379";
+
+    fn io_error() -> RedisResult<Value> {
+        Err(std::io::Error::from(std::io::ErrorKind::ConnectionReset).into())
+    }
+
+    fn timeout() -> RedisResult<Value> {
+        Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into())
+    }
+
+    fn server_error() -> RedisResult<Value> {
+        Err(RedisError::from((ErrorKind::ResponseError, "WRONGTYPE")))
+    }
+
+    #[test]
+    fn test_circuit_opens_on_consecutive_timeouts() {
+        let tracker = NodeHealthTracker::new(Some(CircuitBreakerConfig::new()));
+        let latency = Duration::from_millis(1);
+        assert!(!tracker.record(NODE, &timeout(), latency));
+        assert!(!tracker.record(NODE, &timeout(), latency));
+        assert!(tracker.is_available(NODE));
+        assert!(tracker.record(NODE, &timeout(), latency));
+        assert!(!tracker.is_available(NODE));
+
+        let health = &tracker.snapshot()[NODE];
+        assert_eq!(health.consecutive_timeouts(), 3);
+        assert!(health.is_circuit_open());
+    }
+
+    #[test]
+    fn test_circuit_opens_on_error_rate_after_min_requests() {
+        let config = CircuitBreakerConfig::new()
+            .set_min_requests(5)
+            .set_error_rate_threshold(0.3);
+        let tracker = NodeHealthTracker::new(Some(config));
+        let latency = Duration::from_millis(1);
+        // Errors returned by the node don't count as failures.
+        for _ in 0..5 {
+            assert!(!tracker.record(NODE, &server_error(), latency));
+        }
+        assert!(!tracker.record(NODE, &io_error(), latency));
+        assert!(tracker.record(NODE, &io_error(), latency));
+        assert!(!tracker.is_available(NODE));
+        assert!(tracker.is_available("node2:6379"));
+    }
+
+    #[test]
+    fn test_circuit_opens_on_latency() {
+        let config = CircuitBreakerConfig::new()
+            .set_min_requests(1)
+            .set_latency_threshold(Duration::from_millis(10));
+        let tracker = NodeHealthTracker::new(Some(config));
+        assert!(!tracker.record(NODE, &Ok(Value::Okay), Duration::from_millis(5)));
+        assert!(!tracker.record(NODE, &Ok(Value::Okay), Duration::from_millis(20)));
+        assert!(tracker.record(NODE, &Ok(Value::Okay), Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn test_circuit_closes_after_successful_retry() {
+        let config = CircuitBreakerConfig::new()
+            .set_consecutive_timeouts(1)
+            .set_open_duration(Duration::ZERO);
+        let tracker = NodeHealthTracker::new(Some(config));
+        let latency = Duration::from_millis(1);
+        assert!(tracker.record(NODE, &timeout(), latency));
+        // The open duration passed, so the node is tried again.
+        assert!(tracker.is_available(NODE));
+        assert!(tracker.record(NODE, &io_error(), latency));
+        assert!(!tracker.record(NODE, &Ok(Value::Okay), latency));
+
+        let health = &tracker.snapshot()[NODE];
+        assert_eq!(health.error_rate(), 0.0);
+        assert!(!health.is_circuit_open());
+    }
+
+    #[test]
+    fn test_health_is_tracked_without_circuit_breaker() {
+        let tracker = NodeHealthTracker::new(None);
+        for _ in 0..10 {
+            assert!(!tracker.record(NODE, &timeout(), Duration::from_millis(1)));
+        }
+        // Without a circuit breaker the node keeps being used.
+        assert!(tracker.is_available(NODE));
+
+        let health = &tracker.snapshot()[NODE];
+        assert_eq!(health.consecutive_timeouts(), 10);
+        assert!(health.error_rate() > 0.5);
+        assert!(!health.is_circuit_open());
+    }
+
+    #[test]
+    fn test_removed_nodes_are_forgotten() {
+        let tracker = NodeHealthTracker::new(Some(CircuitBreakerConfig::new()));
+        let latency = Duration::from_millis(1);
+        tracker.record(NODE, &Ok(Value::Okay), latency);
+        tracker.record("node2:6379", &Ok(Value::Okay), latency);
+
+        tracker.retain_nodes(&HashSet::from(["node2:6379"]));
+        let health = tracker.snapshot();
+        assert!(!health.contains_key(NODE));
+        assert!(health.contains_key("node2:6379"));
+    }
+}
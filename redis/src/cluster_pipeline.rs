@@ -1,8 +1,12 @@
-use crate::cluster::ClusterConnection;
+use crate::cluster::{ClusterConnection, Connect};
 use crate::cmd::{cmd, Cmd};
+use crate::connection::ConnectionLike;
+use crate::pipeline::Pipeline;
 use crate::types::{
-    from_owned_redis_value, ErrorKind, FromRedisValue, HashSet, RedisResult, ToRedisArgs, Value,
+    from_owned_redis_value, ErrorKind, FromRedisValue, HashSet, RedisError, RedisResult,
+    ToRedisArgs, Value,
 };
+use std::{error, fmt};
 
 pub(crate) const UNROUTABLE_ERROR: (ErrorKind, &str) = (
     ErrorKind::ClientError,
@@ -46,11 +50,12 @@ fn is_illegal_cmd(cmd: &str) -> bool {
 pub struct ClusterPipeline {
     commands: Vec<Cmd>,
     ignored_commands: HashSet<usize>,
+    transaction_mode: bool,
 }
 
 /// A cluster pipeline is almost identical to a normal [Pipeline](crate::pipeline::Pipeline), with two exceptions:
-/// * It does not support transactions
-/// * The following commands can not be used in a cluster pipeline:
+/// * In atomic mode, the keys of all commands must hash to the same slot
+/// * The following commands can not be used in a cluster pipeline which isn't atomic:
 /// ```text
 /// BGREWRITEAOF, BGSAVE, BITOP, BRPOPLPUSH
 /// CLIENT GETNAME, CLIENT KILL, CLIENT LIST, CLIENT SETNAME, CONFIG GET,
@@ -81,29 +86,35 @@ impl ClusterPipeline {
         ClusterPipeline {
             commands: Vec::with_capacity(capacity),
             ignored_commands: HashSet::new(),
+            transaction_mode: false,
         }
     }
 
-    pub(crate) fn commands(&self) -> &Vec<Cmd> {
-        &self.commands
-    }
-
-    /// Executes the pipeline and fetches the return values:
+    /// This enables atomic mode. In atomic mode the whole pipeline is enclosed in `MULTI`/`EXEC`
+    /// and sent to the primary which serves the slot of its keys. If the keys hash to different slots,
+    /// the pipeline fails with [`ErrorKind::CrossSlot`] before anything is sent.
     ///
     /// ```rust,no_run
     /// # let nodes = vec!["redis://127.0.0.1:6379/"];
     /// # let client = redis::cluster::ClusterClient::new(nodes).unwrap();
     /// # let mut con = client.get_connection().unwrap();
-    /// let mut pipe = redis::cluster::cluster_pipe();
-    /// let (k1, k2) : (i32, i32) = pipe
-    ///     .cmd("SET").arg("key_1").arg(42).ignore()
-    ///     .cmd("SET").arg("key_2").arg(43).ignore()
-    ///     .cmd("GET").arg("key_1")
-    ///     .cmd("GET").arg("key_2").query(&mut con).unwrap();
+    /// let (k1, k2) : (i32, i32) = redis::cluster::cluster_pipe()
+    ///     .atomic()
+    ///     .cmd("GET").arg("{user1}.followers")
+    ///     .cmd("GET").arg("{user1}.following").query(&mut con).unwrap();
     /// ```
     #[inline]
-    pub fn query<T: FromRedisValue>(&self, con: &mut ClusterConnection) -> RedisResult<T> {
-        for cmd in &self.commands {
+    pub fn atomic(&mut self) -> &mut ClusterPipeline {
+        self.transaction_mode = true;
+        self
+    }
+
+    pub(crate) fn commands(&self) -> &Vec<Cmd> {
+        &self.commands
+    }
+
+    fn check_routable_commands(&self) -> RedisResult<()> {
+        for cmd in self.commands.iter().filter(|_| !self.transaction_mode) {
             let cmd_name = std::str::from_utf8(cmd.arg_idx(0).unwrap_or(b""))
                 .unwrap_or("")
                 .trim()
@@ -117,14 +128,82 @@ impl ClusterPipeline {
                 ))
             }
         }
+        Ok(())
+    }
+
+    /// Executes the pipeline and fetches the return values:
+    ///
+    /// ```rust,no_run
+    /// # let nodes = vec!["redis://127.0.0.1:6379/"];
+    /// # let client = redis::cluster::ClusterClient::new(nodes).unwrap();
+    /// # let mut con = client.get_connection().unwrap();
+    /// let mut pipe = redis::cluster::cluster_pipe();
+    /// let (k1, k2) : (i32, i32) = pipe
+    ///     .cmd("SET").arg("key_1").arg(42).ignore()
+    ///     .cmd("SET").arg("key_2").arg(43).ignore()
+    ///     .cmd("GET").arg("key_1")
+    ///     .cmd("GET").arg("key_2").query(&mut con).unwrap();
+    /// ```
+    #[inline]
+    pub fn query<T: FromRedisValue>(
+        &self,
+        con: &mut ClusterConnection<impl ConnectionLike + Connect>,
+    ) -> RedisResult<T> {
+        self.check_routable_commands()?;
 
         from_owned_redis_value(if self.commands.is_empty() {
             Value::Array(vec![])
+        } else if self.transaction_mode {
+            // Transactions are sent as a unit to the primary which serves their keys.
+            Pipeline {
+                commands: self.commands.clone(),
+                transaction_mode: true,
+                ignored_commands: self.ignored_commands.clone(),
+            }
+            .execute_transaction(con)?
+            .extract_error()?
         } else {
             self.make_pipeline_results(con.execute_pipeline(self)?)?
         })
     }
 
+    /// Executes the pipeline and returns the result of every command in the order they were added,
+    /// so that a failure on one node doesn't discard the replies of the other nodes.
+    ///
+    /// Unlike [`query`](Self::query), failed commands are not retried. Every failure carries the node
+    /// it came from, so the caller can decide which commands to send again. Commands marked with
+    /// `ignore()` are part of the returned vector as well. The outer error is only returned if the
+    /// pipeline can't be executed at all, e.g. because it is atomic or contains a command which
+    /// can't be routed.
+    ///
+    /// ```rust,no_run
+    /// # let nodes = vec!["redis://127.0.0.1:6379/"];
+    /// # let client = redis::cluster::ClusterClient::new(nodes).unwrap();
+    /// # let mut con = client.get_connection().unwrap();
+    /// let mut pipe = redis::cluster::cluster_pipe();
+    /// pipe.set("key_1", 42).set("key_2", 43);
+    /// let results = pipe.query_per_command(&mut con).unwrap();
+    /// for (cmd, result) in pipe.cmd_iter().zip(results) {
+    ///     if let Err(err) = result {
+    ///         println!("{:?} failed on {:?}: {err}", cmd.arg_idx(1), err.node());
+    ///     }
+    /// }
+    /// ```
+    pub fn query_per_command(
+        &self,
+        con: &mut ClusterConnection<impl ConnectionLike + Connect>,
+    ) -> RedisResult<Vec<Result<Value, ClusterCommandError>>> {
+        if self.transaction_mode {
+            fail!((
+                ErrorKind::ClientError,
+                "Atomic pipelines can't return per-command results"
+            ))
+        }
+        self.check_routable_commands()?;
+
+        Ok(con.execute_pipeline_per_command(self))
+    }
+
     /// This is a shortcut to `query()` that does not return a value and
     /// will fail the task if the query of the pipeline fails.
     ///
@@ -139,7 +218,7 @@ impl ClusterPipeline {
     /// ```
     #[inline]
     #[deprecated(note = "Use Cmd::exec + unwrap, instead")]
-    pub fn execute(&self, con: &mut ClusterConnection) {
+    pub fn execute(&self, con: &mut ClusterConnection<impl ConnectionLike + Connect>) {
         self.exec(con).unwrap();
     }
 
@@ -148,7 +227,10 @@ impl ClusterPipeline {
     /// this is useful for "SET" commands for which the response's content is not important.
     /// It avoids the need to define generic bounds for ().
     #[inline]
-    pub fn exec(&self, con: &mut ClusterConnection) -> RedisResult<()> {
+    pub fn exec(
+        &self,
+        con: &mut ClusterConnection<impl ConnectionLike + Connect>,
+    ) -> RedisResult<()> {
         self.query::<()>(con)
     }
 }
@@ -159,3 +241,68 @@ pub fn cluster_pipe() -> ClusterPipeline {
 }
 
 implement_pipeline_commands!(ClusterPipeline);
+
+/// The failure of a single command of a cluster pipeline, see
+/// [`ClusterPipeline::query_per_command`].
+#[derive(Debug)]
+pub struct ClusterCommandError {
+    node: Option<String>,
+    error: RedisError,
+}
+
+impl ClusterCommandError {
+    pub(crate) fn new(node: Option<String>, error: RedisError) -> Self {
+        Self { node, error }
+    }
+
+    /// The address of the node the command was sent to, or `None` if the command couldn't be routed.
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    /// The error returned for the command.
+    pub fn error(&self) -> &RedisError {
+        &self.error
+    }
+
+    /// Converts this into the error returned for the command.
+    pub fn into_error(self) -> RedisError {
+        self.error
+    }
+
+    /// Returns true if the node redirected the command with a `MOVED` error.
+    pub fn is_moved(&self) -> bool {
+        self.error.kind() == ErrorKind::Moved
+    }
+
+    /// Returns true if the node redirected the command with an `ASK` error.
+    pub fn is_ask(&self) -> bool {
+        self.error.kind() == ErrorKind::Ask
+    }
+
+    /// Returns true if the command failed because the connection to the node failed.
+    pub fn is_connection_error(&self) -> bool {
+        self.error.is_io_error()
+    }
+}
+
+impl fmt::Display for ClusterCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.node {
+            Some(node) => write!(f, "{node}: {}", self.error),
+            None => self.error.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ClusterCommandError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<ClusterCommandError> for RedisError {
+    fn from(err: ClusterCommandError) -> RedisError {
+        err.error
+    }
+}
@@ -0,0 +1,319 @@
+//! Strategies which choose the replica that serves reads when reading from replicas is enabled.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use rand::{rng, Rng};
+
+use crate::types::RedisResult;
+
+/// Chooses the replica which serves a read from a slot.
+///
+/// A strategy is shared by all connections which are created by a [`crate::cluster::ClusterClient`],
+/// so observations reported to it from one connection affect the replicas which are chosen by all of them.
+///
+/// # Example
+/// ```rust,no_run
+/// use redis::cluster::{AzAffinityReplica, ClusterClient};
+///
+/// let nodes = vec!["redis://127.0.0.1:6379/"];
+/// let client = ClusterClient::builder(nodes)
+///     .read_from_replicas_with(AzAffinityReplica::new("us-east-1a"))
+///     .build()
+///     .unwrap();
+/// ```
+pub trait ReadFromReplicaStrategy: Send + Sync + 'static {
+    /// Returns the index of the replica in `replicas` which should serve the read.
+    /// `replicas` is never empty, and is ordered as reported by the cluster.
+    fn select_replica(&self, replicas: &[String]) -> usize;
+
+    /// Whether the availability zone of nodes should be queried when connecting to them,
+    /// and reported with [`ReadFromReplicaStrategy::node_connected`].
+    fn uses_availability_zone(&self) -> bool {
+        false
+    }
+
+    /// Called after a connection to a node is created, with the availability zone which the node reported, if any.
+    fn node_connected(&self, _address: &str, _availability_zone: Option<&str>) {}
+
+    /// Whether the latency of requests should be reported with [`ReadFromReplicaStrategy::record_latency`],
+    /// and their failures with [`ReadFromReplicaStrategy::record_failure`].
+    fn uses_latency(&self) -> bool {
+        false
+    }
+
+    /// Called with the round trip time of a request which was served by a node.
+    fn record_latency(&self, _address: &str, _latency: Duration) {}
+
+    /// Called when a request sent to a node failed, because the connection to it failed or timed out.
+    fn record_failure(&self, _address: &str) {}
+
+    /// Called after the topology is refreshed with the addresses of all nodes in the cluster,
+    /// anything kept about other nodes can be dropped.
+    fn retain_nodes(&self, _addresses: &HashSet<&str>) {}
+}
+
+/// Reports the outcome of a read served by the replica `address` to a strategy which
+/// [uses latency](ReadFromReplicaStrategy::uses_latency).
+/// Errors returned by the node count as served requests, like in the circuit breaker.
+pub(crate) fn record_request<T>(
+    strategy: &dyn ReadFromReplicaStrategy,
+    address: &str,
+    result: &RedisResult<T>,
+    latency: Duration,
+) {
+    match result {
+        Err(err) if err.is_io_error() || err.is_timeout() => strategy.record_failure(address),
+        _ => strategy.record_latency(address, latency),
+    }
+}
+
+/// Chooses a uniformly random replica. This is the strategy used by
+/// [`crate::cluster::ClusterClientBuilder::read_from_replicas`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomReplica;
+
+impl ReadFromReplicaStrategy for RandomReplica {
+    fn select_replica(&self, replicas: &[String]) -> usize {
+        rng().random_range(0..replicas.len())
+    }
+}
+
+/// Cycles through the replicas of a slot.
+#[derive(Debug, Default)]
+pub struct RoundRobinReplica {
+    counter: AtomicUsize,
+}
+
+impl RoundRobinReplica {
+    /// Creates a new round robin strategy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReadFromReplicaStrategy for RoundRobinReplica {
+    fn select_replica(&self, replicas: &[String]) -> usize {
+        self.counter.fetch_add(1, Ordering::Relaxed) % replicas.len()
+    }
+}
+
+/// Cycles through the replicas which are in the same availability zone as the client,
+/// and through all of the replicas of a slot if none of them is.
+///
+/// The availability zone of a node is read from the `availability_zone` field of `INFO SERVER`
+/// when connecting to it, which is reported by Valkey 8.0 and later. Nodes which don't report it are
+/// considered to be in a different availability zone.
+#[derive(Debug)]
+pub struct AzAffinityReplica {
+    client_az: String,
+    node_azs: RwLock<HashMap<String, String>>,
+    counter: AtomicUsize,
+}
+
+impl AzAffinityReplica {
+    /// Creates a strategy which prefers replicas in the availability zone `client_az`.
+    pub fn new(client_az: impl Into<String>) -> Self {
+        Self {
+            client_az: client_az.into(),
+            node_azs: Default::default(),
+            counter: Default::default(),
+        }
+    }
+}
+
+impl ReadFromReplicaStrategy for AzAffinityReplica {
+    fn select_replica(&self, replicas: &[String]) -> usize {
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let node_azs = self.node_azs.read().unwrap();
+        let local_replicas: Vec<usize> = replicas
+            .iter()
+            .enumerate()
+            .filter(|(_, replica)| node_azs.get(*replica) == Some(&self.client_az))
+            .map(|(index, _)| index)
+            .collect();
+        if local_replicas.is_empty() {
+            counter % replicas.len()
+        } else {
+            local_replicas[counter % local_replicas.len()]
+        }
+    }
+
+    fn uses_availability_zone(&self) -> bool {
+        true
+    }
+
+    fn node_connected(&self, address: &str, availability_zone: Option<&str>) {
+        let mut node_azs = self.node_azs.write().unwrap();
+        match availability_zone {
+            Some(availability_zone) => {
+                node_azs.insert(address.to_string(), availability_zone.to_string());
+            }
+            None => {
+                node_azs.remove(address);
+            }
+        }
+    }
+
+    fn retain_nodes(&self, addresses: &HashSet<&str>) {
+        self.node_azs
+            .write()
+            .unwrap()
+            .retain(|address, _| addresses.contains(address.as_str()));
+    }
+}
+
+/// Chooses the replica with the lowest observed latency.
+///
+/// Latency is a moving average of the round trip times of requests served by each node, a failed request
+/// counts as a request which took a second. Every tenth read cycles through all of the replicas instead,
+/// so replicas which didn't serve any request yet, or were slow in the past, are measured again.
+#[derive(Debug, Default)]
+pub struct LowestLatencyReplica {
+    latencies: RwLock<HashMap<String, Duration>>,
+    counter: AtomicUsize,
+}
+
+impl LowestLatencyReplica {
+    // Weight of a new sample in the moving average.
+    const SAMPLE_WEIGHT: f64 = 0.2;
+    // Latency which is recorded for a failed request.
+    const FAILURE_PENALTY: Duration = Duration::from_secs(1);
+    // One read in this many cycles through the replicas to measure them.
+    const MEASURE_INTERVAL: usize = 10;
+
+    /// Creates a new lowest latency strategy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ReadFromReplicaStrategy for LowestLatencyReplica {
+    fn select_replica(&self, replicas: &[String]) -> usize {
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let round_robin = counter / Self::MEASURE_INTERVAL % replicas.len();
+        if counter % Self::MEASURE_INTERVAL == 0 {
+            return round_robin;
+        }
+        let latencies = self.latencies.read().unwrap();
+        replicas
+            .iter()
+            .enumerate()
+            .filter_map(|(index, replica)| Some((index, latencies.get(replica)?)))
+            .min_by_key(|(_, latency)| **latency)
+            .map_or(round_robin, |(index, _)| index)
+    }
+
+    fn uses_latency(&self) -> bool {
+        true
+    }
+
+    fn record_latency(&self, address: &str, latency: Duration) {
+        let mut latencies = self.latencies.write().unwrap();
+        let average = latencies.entry(address.to_string()).or_insert(latency);
+        *average =
+            average.mul_f64(1.0 - Self::SAMPLE_WEIGHT) + latency.mul_f64(Self::SAMPLE_WEIGHT);
+    }
+
+    fn record_failure(&self, address: &str) {
+        self.record_latency(address, Self::FAILURE_PENALTY);
+    }
+
+    fn retain_nodes(&self, addresses: &HashSet<&str>) {
+        self.latencies
+            .write()
+            .unwrap()
+            .retain(|address, _| addresses.contains(address.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replicas() -> Vec<String> {
+        vec![
+            "replica1:6379".to_string(),
+            "replica2:6379".to_string(),
+            "replica3:6379".to_string(),
+        ]
+    }
+
+    #[test]
+    fn test_round_robin_cycles_through_replicas() {
+        let strategy = RoundRobinReplica::new();
+        let selected: Vec<usize> = (0..4)
+            .map(|_| strategy.select_replica(&replicas()))
+            .collect();
+        assert_eq!(selected, vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_az_affinity_prefers_local_replicas() {
+        let strategy = AzAffinityReplica::new("az-a");
+        strategy.node_connected("replica1:6379", Some("az-b"));
+        strategy.node_connected("replica2:6379", Some("az-a"));
+        strategy.node_connected("replica3:6379", Some("az-a"));
+        let selected: Vec<usize> = (0..4)
+            .map(|_| strategy.select_replica(&replicas()))
+            .collect();
+        assert_eq!(selected, vec![1, 2, 1, 2]);
+
+        // Without local replicas, all replicas are used.
+        strategy.node_connected("replica2:6379", None);
+        strategy.node_connected("replica3:6379", Some("az-c"));
+        let mut selected: Vec<usize> = (0..3)
+            .map(|_| strategy.select_replica(&replicas()))
+            .collect();
+        selected.sort();
+        assert_eq!(selected, vec![0, 1, 2]);
+
+        // Nodes which left the cluster are forgotten.
+        strategy.retain_nodes(&HashSet::from(["replica3:6379"]));
+        assert_eq!(
+            *strategy.node_azs.read().unwrap(),
+            HashMap::from([("replica3:6379".to_string(), "az-c".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_lowest_latency_prefers_fastest_replica() {
+        let strategy = LowestLatencyReplica::new();
+        let select = |count| -> Vec<usize> {
+            (0..count)
+                .map(|_| strategy.select_replica(&replicas()))
+                .collect()
+        };
+        // Without any measurement replicas are measured one by one.
+        assert_eq!(select(10)[1..], [0; 9]);
+
+        strategy.record_latency("replica1:6379", Duration::from_millis(5));
+        strategy.record_latency("replica2:6379", Duration::from_millis(1));
+        // Every tenth read measures the next replica, even if it's unmeasured or slower.
+        assert_eq!(select(10), vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(select(10), vec![2, 1, 1, 1, 1, 1, 1, 1, 1, 1]);
+
+        // Slow samples move the average.
+        for _ in 0..20 {
+            strategy.record_latency("replica2:6379", Duration::from_millis(20));
+        }
+        assert_eq!(select(10)[1..], [0; 9]);
+
+        // Failures are penalized, so replicas which fail don't keep being chosen.
+        strategy.record_latency("replica3:6379", Duration::from_millis(2));
+        assert_eq!(select(10)[1..], [2; 9]);
+        strategy.record_failure("replica3:6379");
+        assert_eq!(select(10)[1..], [0; 9]);
+
+        // Nodes which left the cluster are forgotten.
+        strategy.retain_nodes(&HashSet::from(["replica2:6379", "replica3:6379"]));
+        assert!(!strategy
+            .latencies
+            .read()
+            .unwrap()
+            .contains_key("replica1:6379"));
+        assert_eq!(select(10)[1..], [1; 9]);
+    }
+}
@@ -1,9 +1,10 @@
 use std::cmp::min;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
 
-use rand::prelude::IndexedRandom;
-use rand::rng;
-
+use crate::cluster_node_health::NodeHealthTracker;
+use crate::cluster_read_strategy::{RandomReplica, ReadFromReplicaStrategy};
 use crate::cmd::{Arg, Cmd};
 use crate::commands::is_readonly_cmd;
 use crate::types::Value;
@@ -265,6 +266,144 @@ fn get_route(is_readonly: bool, key: &[u8]) -> Route {
     }
 }
 
+/// Returns the keys of `routable` which determine the slots it's routed to.
+fn command_keys<R>(routable: &R) -> Vec<&[u8]>
+where
+    R: Routable + ?Sized,
+{
+    let args_from =
+        |first_key_index: usize| (first_key_index..).map_while(|index| routable.arg_idx(index));
+    // Returns the keys which follow the key count at `key_count_index`.
+    let counted_keys = |key_count_index: usize| -> Vec<&[u8]> {
+        let key_count = routable
+            .arg_idx(key_count_index)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or_default();
+        args_from(key_count_index + 1).take(key_count).collect()
+    };
+    let Some(cmd) = routable.command() else {
+        return Vec::new();
+    };
+    match cmd.as_slice() {
+        b"MGET" | b"DEL" | b"EXISTS" | b"UNLINK" | b"TOUCH" | b"WATCH" | b"PFCOUNT"
+        | b"PFMERGE" | b"SINTER" | b"SUNION" | b"SDIFF" | b"SINTERSTORE" | b"SUNIONSTORE"
+        | b"SDIFFSTORE" => args_from(1).collect(),
+        b"MSET" | b"MSETNX" => args_from(1).step_by(2).collect(),
+        b"RENAME" | b"RENAMENX" | b"SMOVE" | b"LMOVE" | b"BLMOVE" | b"RPOPLPUSH"
+        | b"BRPOPLPUSH" | b"COPY" | b"LCS" | b"GEOSEARCHSTORE" | b"ZRANGESTORE" => {
+            args_from(1).take(2).collect()
+        }
+        // The last argument is the timeout.
+        b"BLPOP" | b"BRPOP" | b"BZPOPMIN" | b"BZPOPMAX" => {
+            let mut keys: Vec<&[u8]> = args_from(1).collect();
+            keys.pop();
+            keys
+        }
+        b"BITOP" => args_from(2).collect(),
+        b"EVALSHA" | b"EVAL" | b"EVALSHA_RO" | b"EVAL_RO" | b"FCALL" | b"FCALL_RO" => {
+            counted_keys(2)
+        }
+        b"SINTERCARD" | b"ZUNION" | b"ZINTER" | b"ZDIFF" | b"ZINTERCARD" | b"LMPOP" | b"ZMPOP" => {
+            counted_keys(1)
+        }
+        b"BLMPOP" | b"BZMPOP" => counted_keys(2),
+        b"ZUNIONSTORE" | b"ZINTERSTORE" | b"ZDIFFSTORE" => {
+            let mut keys: Vec<&[u8]> = routable.arg_idx(1).into_iter().collect();
+            keys.extend(counted_keys(2));
+            keys
+        }
+        b"GEORADIUS" | b"GEORADIUSBYMEMBER" | b"SORT" => {
+            let mut keys: Vec<&[u8]> = routable.arg_idx(1).into_iter().collect();
+            for store in [&b"STORE"[..], b"STOREDIST"] {
+                // The first argument is the key, which may be named like the option.
+                if let Some(position) = routable.position(store).filter(|&position| position > 1) {
+                    keys.extend(routable.arg_idx(position + 1));
+                }
+            }
+            keys
+        }
+        b"XGROUP CREATE"
+        | b"XGROUP CREATECONSUMER"
+        | b"XGROUP DELCONSUMER"
+        | b"XGROUP DESTROY"
+        | b"XGROUP SETID"
+        | b"XINFO CONSUMERS"
+        | b"XINFO GROUPS"
+        | b"XINFO STREAM"
+        | b"PUBSUB SHARDNUMSUB"
+        | b"PUBSUB NUMSUB" => routable.arg_idx(2).into_iter().collect(),
+        b"XREAD" | b"XREADGROUP" => {
+            let Some(streams_position) = routable.position(b"STREAMS") else {
+                return Vec::new();
+            };
+            // The keys are followed by the same number of ids.
+            let streams: Vec<&[u8]> = args_from(streams_position + 1).collect();
+            let key_count = streams.len() / 2;
+            streams.into_iter().take(key_count).collect()
+        }
+        // These commands are routed by the slot which is passed to them.
+        b"CLUSTER COUNTKEYSINSLOT"
+        | b"CLUSTER GETKEYSINSLOT"
+        | b"CLUSTER SETSLOT"
+        | b"CLUSTER DELSLOTS"
+        | b"CLUSTER DELSLOTSRANGE" => Vec::new(),
+        _ => match RoutingInfo::for_routable(routable) {
+            Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(_))) => {
+                routable.arg_idx(1).into_iter().collect()
+            }
+            _ => Vec::new(),
+        },
+    }
+}
+
+/// Returns the route to the primary which serves the keys of `commands`, which are executed atomically
+/// by a single node, like the commands of a transaction. Returns `None` if the commands have no keys.
+///
+/// If the keys hash to different slots, the commands can't be executed by any node, so a
+/// [`ErrorKind::CrossSlot`] error which names the keys and their slots is returned.
+pub(crate) fn route_for_atomic_commands<'a, R>(
+    commands: impl IntoIterator<Item = &'a R>,
+) -> RedisResult<Option<Route>>
+where
+    R: Routable + ?Sized + 'a,
+{
+    let keys: Vec<(&[u8], u16)> = commands
+        .into_iter()
+        .flat_map(command_keys)
+        .map(|key| (key, get_slot(key)))
+        .collect();
+    let Some((_, slot)) = keys.first() else {
+        return Ok(None);
+    };
+    if keys.iter().any(|(_, key_slot)| key_slot != slot) {
+        let keys = keys
+            .iter()
+            .map(|(key, slot)| format!("`{}` (slot {slot})", String::from_utf8_lossy(key)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        fail!((
+            ErrorKind::CrossSlot,
+            "Keys which are executed atomically don't hash to the same slot",
+            keys
+        ));
+    }
+    Ok(Some(Route::new(*slot, SlotAddr::Master)))
+}
+
+/// Fails with a [`ErrorKind::CrossSlot`] error if `routable` is a script whose keys hash to different slots.
+pub(crate) fn check_script_keys<R>(routable: &R) -> RedisResult<()>
+where
+    R: Routable + ?Sized,
+{
+    match routable.command().as_deref() {
+        Some(b"EVALSHA" | b"EVAL" | b"EVALSHA_RO" | b"EVAL_RO" | b"FCALL" | b"FCALL_RO") => {
+            route_for_atomic_commands([routable]).map(|_| ())
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Takes the given `routable` and creates a multi-slot routing info.
 /// This is used for commands like MSET & MGET, where if the command's keys
 /// are hashed to multiple slots, the command should be split into sub-commands,
@@ -365,6 +504,25 @@ where
     ))
 }
 
+/// Returns true if the command is a read which might wait on the server until data is available, like
+/// `XREAD BLOCK`, so its round trip time doesn't show the latency of the node which served it.
+pub(crate) fn is_blocking_read<R>(routable: &R) -> bool
+where
+    R: Routable + ?Sized,
+{
+    match routable.command().as_deref() {
+        Some(b"XREAD") | Some(b"XREADGROUP") => {
+            // A stream key named "block" follows STREAMS, while the option precedes it.
+            match (routable.position(b"BLOCK"), routable.position(b"STREAMS")) {
+                (Some(block), Some(streams)) => block < streams,
+                (Some(_), None) => true,
+                (None, _) => false,
+            }
+        }
+        _ => false,
+    }
+}
+
 impl ResponsePolicy {
     /// Parse the command for the matching response policy.
     pub fn for_command(cmd: &[u8]) -> Option<ResponsePolicy> {
@@ -527,7 +685,6 @@ impl RoutingInfo {
             b"MGET" | b"DEL" | b"EXISTS" | b"UNLINK" | b"TOUCH" => multi_shard(r, cmd, 1, false),
             b"MSET" => multi_shard(r, cmd, 1, true),
             b"PFCOUNT" | b"PFMERGE" => multiple_keys_same_slot(r, cmd, 1, None, false),
-            // TODO - special handling - b"SCAN"
             b"SCAN" | b"SHUTDOWN" | b"SLAVEOF" | b"REPLICAOF" | b"MOVE" | b"BITOP" => None,
             b"EVALSHA" | b"EVAL" => {
                 let key_count = r
@@ -643,6 +800,8 @@ pub(crate) struct Slot {
     pub(crate) end: u16,
     pub(crate) master: String,
     pub(crate) replicas: Vec<String>,
+    /// Metadata of the master and replicas, available when the topology was discovered with `CLUSTER SHARDS`.
+    pub(crate) nodes: Vec<NodeInfo>,
 }
 
 impl Slot {
@@ -652,10 +811,29 @@ impl Slot {
             end: e,
             master: m,
             replicas: r,
+            nodes: Vec::new(),
         }
     }
 }
 
+/// Health of a node, as reported by `CLUSTER SHARDS`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeHealth {
+    Online,
+    Loading,
+    Failed,
+}
+
+/// Metadata of a node, as reported by `CLUSTER SHARDS`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct NodeInfo {
+    pub(crate) address: String,
+    pub(crate) id: String,
+    pub(crate) hostname: Option<String>,
+    pub(crate) health: NodeHealth,
+    pub(crate) replication_offset: Option<u64>,
+}
+
 /// What type of node should a request be routed to.
 #[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
 pub enum SlotAddr {
@@ -684,21 +862,43 @@ impl SlotAddrs {
         Self { primary, replicas }
     }
 
-    fn get_replica_node(&self) -> &str {
-        self.replicas.choose(&mut rng()).unwrap_or(&self.primary)
+    // Falls back to the primary if there are no replicas, or if the circuits of all of them are open.
+    fn get_replica_node(
+        &self,
+        strategy: Option<&dyn ReadFromReplicaStrategy>,
+        node_health: Option<&NodeHealthTracker>,
+    ) -> &str {
+        let strategy = strategy.unwrap_or(&RandomReplica);
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+        let selected = strategy.select_replica(&self.replicas) % self.replicas.len();
+        let Some(node_health) = node_health else {
+            return &self.replicas[selected];
+        };
+        // Skips to the next replica whose circuit isn't open.
+        (0..self.replicas.len())
+            .map(|offset| &self.replicas[(selected + offset) % self.replicas.len()])
+            .find(|replica| node_health.is_available(replica))
+            .unwrap_or(&self.primary)
     }
 
-    pub(crate) fn slot_addr(&self, slot_addr: &SlotAddr, read_from_replica: bool) -> &str {
+    pub(crate) fn slot_addr(
+        &self,
+        slot_addr: &SlotAddr,
+        read_from_replica: Option<&dyn ReadFromReplicaStrategy>,
+        node_health: Option<&NodeHealthTracker>,
+    ) -> &str {
         match slot_addr {
             SlotAddr::Master => &self.primary,
             SlotAddr::ReplicaOptional => {
-                if read_from_replica {
-                    self.get_replica_node()
+                if read_from_replica.is_some() {
+                    self.get_replica_node(read_from_replica, node_health)
                 } else {
                     &self.primary
                 }
             }
-            SlotAddr::ReplicaRequired => self.get_replica_node(),
+            SlotAddr::ReplicaRequired => self.get_replica_node(read_from_replica, node_health),
         }
     }
 
@@ -722,38 +922,59 @@ impl<'a> IntoIterator for &'a SlotAddrs {
 struct SlotMapValue {
     start: u16,
     addrs: SlotAddrs,
+    nodes: Vec<NodeInfo>,
 }
 
 impl SlotMapValue {
-    fn from_slot(slot: Slot) -> Self {
+    fn from_slot(mut slot: Slot) -> Self {
         Self {
             start: slot.start,
+            nodes: std::mem::take(&mut slot.nodes),
             addrs: SlotAddrs::from_slot(slot),
         }
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub(crate) struct SlotMap {
     slots: BTreeMap<u16, SlotMapValue>,
-    read_from_replica: bool,
+    read_from_replica: Option<Arc<dyn ReadFromReplicaStrategy>>,
+    node_health: Option<Arc<NodeHealthTracker>>,
+}
+
+impl fmt::Debug for SlotMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlotMap")
+            .field("slots", &self.slots)
+            .field("read_from_replica", &self.read_from_replica.is_some())
+            .finish()
+    }
 }
 
 impl SlotMap {
-    pub fn new(read_from_replica: bool) -> Self {
+    pub fn new(
+        read_from_replica: Option<Arc<dyn ReadFromReplicaStrategy>>,
+        node_health: Option<Arc<NodeHealthTracker>>,
+    ) -> Self {
         Self {
             slots: Default::default(),
             read_from_replica,
+            node_health,
         }
     }
 
-    pub fn from_slots(slots: Vec<Slot>, read_from_replica: bool) -> Self {
+    pub fn from_slots(
+        slots: Vec<Slot>,
+        read_from_replica: Option<Arc<dyn ReadFromReplicaStrategy>>,
+        node_health: Option<Arc<NodeHealthTracker>>,
+    ) -> Self {
         Self {
             slots: slots
                 .into_iter()
                 .map(|slot| (slot.end, SlotMapValue::from_slot(slot)))
                 .collect(),
             read_from_replica,
+            node_health,
         }
     }
 
@@ -771,11 +992,11 @@ impl SlotMap {
             .next()
             .and_then(|(end, slot_value)| {
                 if slot <= *end && slot_value.start <= slot {
-                    Some(
-                        slot_value
-                            .addrs
-                            .slot_addr(route.slot_addr(), self.read_from_replica),
-                    )
+                    Some(slot_value.addrs.slot_addr(
+                        route.slot_addr(),
+                        self.read_from_replica.as_deref(),
+                        self.node_health.as_deref(),
+                    ))
                 } else {
                     None
                 }
@@ -787,6 +1008,20 @@ impl SlotMap {
         self.slots.clear();
     }
 
+    /// Returns the slot ranges of the map, ordered by their first slot.
+    pub(crate) fn slots(&self) -> Vec<Slot> {
+        self.slots
+            .iter()
+            .map(|(end, slot_value)| Slot {
+                start: slot_value.start,
+                end: *end,
+                master: slot_value.addrs.primary.clone(),
+                replicas: slot_value.addrs.replicas.clone(),
+                nodes: slot_value.nodes.clone(),
+            })
+            .collect()
+    }
+
     pub fn values(&self) -> impl Iterator<Item = &SlotAddrs> {
         self.slots.values().map(|slot_value| &slot_value.addrs)
     }
@@ -795,9 +1030,8 @@ impl SlotMap {
         let mut addresses: HashSet<&str> = HashSet::new();
         if only_primaries {
             addresses.extend(
-                self.values().map(|slot_addrs| {
-                    slot_addrs.slot_addr(&SlotAddr::Master, self.read_from_replica)
-                }),
+                self.values()
+                    .map(|slot_addrs| slot_addrs.slot_addr(&SlotAddr::Master, None, None)),
             );
         } else {
             addresses.extend(
@@ -818,6 +1052,13 @@ impl SlotMap {
         self.all_unique_addresses(false)
     }
 
+    /// Returns true if the node serves any slot as a replica.
+    pub(crate) fn is_replica(&self, address: &str) -> bool {
+        self.values()
+            .any(|slot_addrs| slot_addrs.replicas.iter().any(|replica| replica == address))
+    }
+
+    #[cfg_attr(not(feature = "cluster-async"), allow(dead_code))]
     pub fn addresses_for_multi_slot<'a, 'b>(
         &'a self,
         routes: &'b [(Route, Vec<usize>)],
@@ -864,16 +1105,21 @@ fn get_hashtag(key: &[u8]) -> Option<&[u8]> {
 mod tests {
     use core::panic;
     use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::time::Duration;
 
     use super::{
-        command_for_multi_slot_indices, get_hashtag, slot, MultipleNodeRoutingInfo, Route,
-        RoutingInfo, SingleNodeRoutingInfo, Slot, SlotAddr, SlotMap,
+        check_script_keys, command_for_multi_slot_indices, get_hashtag, is_blocking_read,
+        route_for_atomic_commands, slot, MultipleNodeRoutingInfo, Routable, Route, RoutingInfo,
+        SingleNodeRoutingInfo, Slot, SlotAddr, SlotMap,
     };
     use crate::{
+        cluster_node_health::{CircuitBreakerConfig, NodeHealthTracker},
+        cluster_read_strategy::{RandomReplica, ReadFromReplicaStrategy, RoundRobinReplica},
         cluster_routing::{get_slot, AggregateOp, ResponsePolicy},
         cmd,
         parser::parse_redis_value,
-        Value,
+        ErrorKind, Value,
     };
 
     #[test]
@@ -1314,15 +1560,18 @@ mod tests {
                     end: 1000,
                     master: "node1:6379".to_owned(),
                     replicas: vec!["replica1:6379".to_owned()],
+                    nodes: Vec::new(),
                 },
                 Slot {
                     start: 1001,
                     end: 2000,
                     master: "node2:6379".to_owned(),
                     replicas: vec!["replica2:6379".to_owned()],
+                    nodes: Vec::new(),
                 },
             ],
-            true,
+            Some(Arc::new(RandomReplica)),
+            None,
         );
 
         assert_eq!(
@@ -1372,6 +1621,38 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn test_slot_map_skips_replicas_with_open_circuit() {
+        let node_health = Arc::new(NodeHealthTracker::new(Some(
+            CircuitBreakerConfig::new().set_consecutive_timeouts(1),
+        )));
+        let slot_map = SlotMap::from_slots(
+            vec![Slot::new(
+                1,
+                1000,
+                "node1:6379".to_owned(),
+                vec!["replica1:6379".to_owned(), "replica2:6379".to_owned()],
+            )],
+            Some(Arc::new(RoundRobinReplica::new())),
+            Some(node_health.clone()),
+        );
+        let timeout: crate::RedisResult<()> =
+            Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into());
+        let read = Route::new(1, SlotAddr::ReplicaOptional);
+
+        node_health.record("replica1:6379", &timeout, Duration::ZERO);
+        for _ in 0..3 {
+            assert_eq!(slot_map.slot_addr_for_route(&read), Some("replica2:6379"));
+        }
+
+        node_health.record("replica2:6379", &timeout, Duration::ZERO);
+        assert_eq!(slot_map.slot_addr_for_route(&read), Some("node1:6379"));
+        assert_eq!(
+            slot_map.slot_addr_for_route(&Route::new(1, SlotAddr::ReplicaRequired)),
+            Some("node1:6379")
+        );
+    }
+
     #[test]
     fn test_slot_map_when_read_from_replica_is_false() {
         let slot_map = SlotMap::from_slots(
@@ -1380,8 +1661,10 @@ mod tests {
                 end: 1000,
                 master: "node1:6379".to_owned(),
                 replicas: vec!["replica1:6379".to_owned()],
+                nodes: Vec::new(),
             }],
-            false,
+            None,
+            None,
         );
 
         assert_eq!(
@@ -1456,10 +1739,197 @@ mod tests {
                     vec!["replica2:6379".to_owned(), "replica3:6379".to_owned()],
                 ),
             ],
-            read_from_replica,
+            read_from_replica.then(|| Arc::new(RandomReplica) as Arc<dyn ReadFromReplicaStrategy>),
+            None,
         )
     }
 
+    #[test]
+    fn test_atomic_commands_are_routed_to_primary_of_their_slot() {
+        let mut pipeline = crate::pipe();
+        pipeline
+            .atomic()
+            .get("{foo}1") // readonly commands are routed to the primary too
+            .cmd("CONFIG").arg("GET").arg("timeout") // unkeyed command
+            .mset(&[("{foo}2", "a"), ("{foo}3", "b")])
+            .cmd("XREAD").arg("STREAMS").arg("{foo}4").arg("{foo}5").arg(0).arg(0);
+
+        assert_eq!(
+            route_for_atomic_commands(pipeline.cmd_iter()),
+            Ok(Some(Route::new(12182, SlotAddr::Master)))
+        );
+
+        let mut pipeline = crate::pipe();
+        pipeline.atomic().cmd("PING");
+        assert_eq!(route_for_atomic_commands(pipeline.cmd_iter()), Ok(None));
+    }
+
+    #[test]
+    fn test_atomic_commands_with_cross_slot_keys_name_the_keys() {
+        let mut pipeline = crate::pipe();
+        pipeline.atomic().set("foo", "bar").get("baz");
+
+        let err = route_for_atomic_commands(pipeline.cmd_iter()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        assert_eq!(err.detail(), Some("`foo` (slot 12182), `baz` (slot 4813)"));
+    }
+
+    #[test]
+    fn test_atomic_commands_check_all_keys_of_multi_key_commands() {
+        let cross_slot_commands = [
+            cmd("RENAME").arg("foo").arg("baz").clone(),
+            cmd("SMOVE").arg("foo").arg("baz").arg("member").clone(),
+            cmd("LMOVE")
+                .arg("foo")
+                .arg("baz")
+                .arg("LEFT")
+                .arg("RIGHT")
+                .clone(),
+            cmd("BLPOP").arg("{foo}a").arg("baz").arg(0).clone(),
+            cmd("SINTERSTORE")
+                .arg("{foo}a")
+                .arg("{foo}b")
+                .arg("baz")
+                .clone(),
+            cmd("ZUNIONSTORE")
+                .arg("foo")
+                .arg(2)
+                .arg("{foo}a")
+                .arg("baz")
+                .clone(),
+            cmd("ZINTER").arg(2).arg("foo").arg("baz").clone(),
+            cmd("BITOP").arg("AND").arg("foo").arg("baz").clone(),
+            cmd("SORT").arg("foo").arg("STORE").arg("baz").clone(),
+            cmd("FCALL")
+                .arg("func")
+                .arg(2)
+                .arg("foo")
+                .arg("baz")
+                .clone(),
+        ];
+        for command in cross_slot_commands {
+            let mut pipeline = crate::pipe();
+            pipeline.atomic().add_command(command.clone());
+            let err = route_for_atomic_commands(pipeline.cmd_iter()).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CrossSlot, "{:?}", command.command());
+        }
+
+        let same_slot_commands = [
+            // The timeout and the key count aren't keys.
+            cmd("BRPOP").arg("{foo}a").arg("{foo}b").arg(0).clone(),
+            cmd("ZUNIONSTORE")
+                .arg("{foo}a")
+                .arg(1)
+                .arg("{foo}b")
+                .arg("WEIGHTS")
+                .arg(1)
+                .clone(),
+            cmd("LMPOP").arg(1).arg("foo").arg("LEFT").clone(),
+            cmd("EVAL")
+                .arg("script")
+                .arg(1)
+                .arg("foo")
+                .arg("baz")
+                .clone(),
+        ];
+        for command in same_slot_commands {
+            let mut pipeline = crate::pipe();
+            pipeline.atomic().add_command(command.clone());
+            assert_eq!(
+                route_for_atomic_commands(pipeline.cmd_iter()),
+                Ok(Some(Route::new(12182, SlotAddr::Master))),
+                "{:?}",
+                command.command()
+            );
+        }
+    }
+
+    #[test]
+    fn test_script_keys_inside_transaction_must_hash_to_the_same_slot() {
+        let mut pipeline = crate::pipe();
+        pipeline
+            .atomic()
+            .set("foo", "bar")
+            .cmd("EVALSHA")
+            .arg("sha")
+            .arg(1)
+            .arg("baz");
+        let err = route_for_atomic_commands(pipeline.cmd_iter()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        assert_eq!(err.detail(), Some("`foo` (slot 12182), `baz` (slot 4813)"));
+    }
+
+    #[test]
+    fn test_script_keys_must_hash_to_the_same_slot() {
+        let mut script = cmd("EVALSHA");
+        script
+            .arg("sha")
+            .arg(2)
+            .arg("{foo}a")
+            .arg("{foo}b")
+            .arg("bar");
+        assert!(check_script_keys(&script).is_ok());
+
+        let mut script = cmd("EVAL");
+        script.arg("script").arg(2).arg("foo").arg("baz").arg("bar");
+        let err = check_script_keys(&script).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        assert_eq!(err.detail(), Some("`foo` (slot 12182), `baz` (slot 4813)"));
+
+        // Commands which aren't scripts aren't checked.
+        assert!(check_script_keys(&cmd("MGET").arg("foo").arg("baz").clone()).is_ok());
+    }
+
+    #[test]
+    fn test_is_blocking_read() {
+        assert!(is_blocking_read(
+            cmd("XREAD")
+                .arg("BLOCK")
+                .arg(0)
+                .arg("STREAMS")
+                .arg("foo")
+                .arg("$")
+        ));
+        assert!(is_blocking_read(
+            cmd("xreadgroup")
+                .arg("GROUP")
+                .arg("group")
+                .arg("consumer")
+                .arg("block")
+                .arg(100)
+                .arg("STREAMS")
+                .arg("foo")
+                .arg(">")
+        ));
+        assert!(!is_blocking_read(
+            cmd("XREAD").arg("STREAMS").arg("block").arg("0")
+        ));
+        assert!(!is_blocking_read(cmd("GET").arg("block")));
+    }
+
+    #[test]
+    fn test_slot_map_uses_read_from_replica_strategy() {
+        let mut slot_map = get_slot_map(false);
+        slot_map.read_from_replica = Some(Arc::new(RoundRobinReplica::new()));
+        let route = Route::new(2001, SlotAddr::ReplicaOptional);
+        let addresses: Vec<_> = (0..4)
+            .map(|_| slot_map.slot_addr_for_route(&route).unwrap())
+            .collect();
+        assert_eq!(
+            addresses,
+            vec![
+                "replica4:6379",
+                "replica5:6379",
+                "replica6:6379",
+                "replica4:6379"
+            ]
+        );
+        assert_eq!(
+            slot_map.slot_addr_for_route(&Route::new(2001, SlotAddr::Master)),
+            Some("node3:6379")
+        );
+    }
+
     #[test]
     fn test_slot_map_get_all_primaries() {
         let slot_map = get_slot_map(false);
@@ -0,0 +1,416 @@
+//! Cluster wide `SCAN`, which scans each primary with its own cursor.
+//!
+//! Slots are marked as scanned only after a primary's cursor is exhausted and only if the primary
+//! still owns them, so slots which are moved by a failover or resharding during the scan are scanned
+//! again on their new primary. Like `SCAN` itself, a key might be returned multiple times.
+//!
+//! Topology is requested from a known primary, so nodes without a hostname in the `CLUSTER SLOTS` reply
+//! are resolved to the host of the primary which answered.
+
+use crate::cluster::{slot_cmd, split_node_address};
+use crate::cluster_routing::{RoutingInfo, SingleNodeRoutingInfo, Slot, SLOT_SIZE};
+use crate::cluster_topology::parse_slots;
+use crate::cmd::Cmd;
+use crate::types::{from_owned_redis_value, ErrorKind, RedisError, RedisResult, Value};
+
+/// Primary which is being scanned.
+struct ScanNode {
+    address: String,
+    // Slot ranges owned by the primary when its scan started.
+    slot_ranges: Vec<(u16, u16)>,
+    cursor: u64,
+}
+
+enum Phase {
+    /// Topology is required to pick the primary which owns the first slot that isn't scanned yet.
+    PickNode,
+    Scan(ScanNode),
+    /// Cursor of the primary is exhausted, topology is required to see which of its slots it still owns.
+    FinishNode(ScanNode),
+    /// Scanning the primary failed, topology is required to see whether it's still a primary.
+    Recover(ScanNode, RedisError),
+    Done,
+}
+
+/// How many times a failed request is retried before the scan fails, e.g. scanning a primary which keeps its slots.
+const MAX_RETRIES_ON_SAME_PRIMARY: u32 = 3;
+
+pub(crate) struct ClusterScan {
+    cmd: Cmd,
+    scanned_slots: Vec<bool>,
+    phase: Phase,
+    // Primaries which topology is requested from, in the order they're tried.
+    primaries: Vec<String>,
+    retries: u32,
+}
+
+impl ClusterScan {
+    /// `cmd` must be a `SCAN` command created with [`Cmd::cursor_arg`], `primaries` are the addresses of the primaries
+    /// known by the connection. If none is known, topology is requested from a random node.
+    pub(crate) fn new(cmd: Cmd, primaries: Vec<String>) -> Self {
+        ClusterScan {
+            cmd,
+            scanned_slots: vec![false; SLOT_SIZE as usize],
+            phase: Phase::PickNode,
+            primaries,
+            retries: 0,
+        }
+    }
+
+    /// Returns the host and port of the node which topology is requested from.
+    fn topology_node(&self) -> Option<(String, u16)> {
+        let address = match &self.phase {
+            // The primary just answered, so it's likely to answer again.
+            Phase::FinishNode(node) => Some(&node.address),
+            Phase::Recover(node, _) => self
+                .primaries
+                .iter()
+                .find(|address| **address != node.address)
+                .or(self.primaries.first()),
+            _ => self.primaries.first(),
+        }?;
+        split_node_address(address).ok()
+    }
+
+    /// Returns the next command to send and where to send it, or `None` if the scan is finished.
+    pub(crate) fn next_request(&self) -> Option<(Cmd, RoutingInfo)> {
+        match &self.phase {
+            Phase::PickNode | Phase::FinishNode(_) | Phase::Recover(..) => {
+                let routing = match self.topology_node() {
+                    Some((host, port)) => SingleNodeRoutingInfo::ByAddress { host, port },
+                    None => SingleNodeRoutingInfo::Random,
+                };
+                Some((slot_cmd(), RoutingInfo::SingleNode(routing)))
+            }
+            Phase::Scan(node) => {
+                let (host, port) = split_node_address(&node.address).ok()?;
+                let mut cmd = self.cmd.clone();
+                cmd.cursor = Some(node.cursor);
+                Some((
+                    cmd,
+                    RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress { host, port }),
+                ))
+            }
+            Phase::Done => None,
+        }
+    }
+
+    /// Handles the response of the command returned by [`ClusterScan::next_request`], and returns scanned items.
+    /// Returned items might be empty even if the scan isn't finished.
+    pub(crate) fn handle_response(
+        &mut self,
+        response: RedisResult<Value>,
+    ) -> RedisResult<Vec<Value>> {
+        let topology_host = self
+            .topology_node()
+            .map(|(host, _)| host)
+            .unwrap_or_default();
+        match std::mem::replace(&mut self.phase, Phase::Done) {
+            Phase::Scan(mut node) => {
+                match response.and_then(from_owned_redis_value::<(u64, Vec<Value>)>) {
+                    Ok((cursor, items)) => {
+                        self.retries = 0;
+                        node.cursor = cursor;
+                        self.phase = if cursor == 0 {
+                            Phase::FinishNode(node)
+                        } else {
+                            Phase::Scan(node)
+                        };
+                        Ok(items)
+                    }
+                    Err(err) => {
+                        self.phase = Phase::Recover(node, err);
+                        Ok(vec![])
+                    }
+                }
+            }
+            Phase::Done => Ok(vec![]),
+            phase => {
+                let slots =
+                    match response.and_then(|value| parse_slots(value, None, &topology_host)) {
+                        Ok(slots) => slots,
+                        Err(err) => {
+                            // Next topology request is sent to another primary.
+                            if !self.primaries.is_empty() {
+                                self.primaries.rotate_left(1);
+                            }
+                            return self.retry(phase, err).map(|_| vec![]);
+                        }
+                    };
+                self.primaries.clear();
+                for slot in &slots {
+                    if !self.primaries.contains(&slot.master) {
+                        self.primaries.push(slot.master.clone());
+                    }
+                }
+                self.handle_topology(phase, &slots).map(|_| vec![])
+            }
+        }
+    }
+
+    fn handle_topology(&mut self, phase: Phase, slots: &[Slot]) -> RedisResult<()> {
+        match phase {
+            Phase::FinishNode(node) => {
+                for &(start, end) in &node.slot_ranges {
+                    for slot in start..=end {
+                        if primary_of(slots, slot) == Some(node.address.as_str()) {
+                            self.scanned_slots[slot as usize] = true;
+                        }
+                    }
+                }
+                self.pick_node(slots)
+            }
+            Phase::Recover(node, err) => {
+                if slots.iter().any(|slot| slot.master == node.address) {
+                    // The primary isn't replaced, so scanning it is retried with the same cursor.
+                    self.retry(Phase::Scan(node), err)
+                } else {
+                    self.pick_node(slots)
+                }
+            }
+            _ => self.pick_node(slots),
+        }
+    }
+
+    fn pick_node(&mut self, slots: &[Slot]) -> RedisResult<()> {
+        let Some(first_slot) = self.scanned_slots.iter().position(|scanned| !scanned) else {
+            self.phase = Phase::Done;
+            return Ok(());
+        };
+        let Some(address) = primary_of(slots, first_slot as u16) else {
+            return self.retry(
+                Phase::PickNode,
+                RedisError::from((ErrorKind::ClusterDown, "Missing slot coverage")),
+            );
+        };
+        self.phase = Phase::Scan(ScanNode {
+            address: address.to_string(),
+            slot_ranges: slots
+                .iter()
+                .filter(|slot| slot.master == address)
+                .map(|slot| (slot.start, slot.end))
+                .collect(),
+            cursor: 0,
+        });
+        Ok(())
+    }
+
+    /// Continues with `phase` after a transient error, the error is only returned and the scan is stopped
+    /// once the retries are used up. Retries are reset when a primary answers a scan.
+    fn retry(&mut self, phase: Phase, err: RedisError) -> RedisResult<()> {
+        self.retries += 1;
+        if self.retries > MAX_RETRIES_ON_SAME_PRIMARY {
+            self.phase = Phase::Done;
+            return Err(err);
+        }
+        self.phase = phase;
+        Ok(())
+    }
+}
+
+fn primary_of(slots: &[Slot], slot: u16) -> Option<&str> {
+    slots
+        .iter()
+        .find(|range| range.start <= slot && slot <= range.end)
+        .map(|range| range.master.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots_value(ranges: &[(u16, u16, u16)]) -> Value {
+        Value::Array(
+            ranges
+                .iter()
+                .map(|(start, end, port)| {
+                    Value::Array(vec![
+                        Value::Int(*start as i64),
+                        Value::Int(*end as i64),
+                        Value::Array(vec![
+                            Value::BulkString(b"node".to_vec()),
+                            Value::Int(*port as i64),
+                        ]),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    fn scan_reply(cursor: u64, keys: &[&str]) -> Value {
+        Value::Array(vec![
+            Value::BulkString(cursor.to_string().into_bytes()),
+            Value::Array(
+                keys.iter()
+                    .map(|key| Value::BulkString(key.as_bytes().to_vec()))
+                    .collect(),
+            ),
+        ])
+    }
+
+    fn scan_address(scan: &ClusterScan) -> (String, u16, Vec<u8>) {
+        match scan.next_request() {
+            Some((
+                cmd,
+                RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress { host, port }),
+            )) => (host, port, cmd.get_packed_command()),
+            _ => panic!("Expected SCAN request"),
+        }
+    }
+
+    fn topology_request_port(scan: &ClusterScan) -> Option<u16> {
+        match scan.next_request() {
+            Some((cmd, RoutingInfo::SingleNode(routing)))
+                if cmd.get_packed_command() == slot_cmd().get_packed_command() =>
+            {
+                match routing {
+                    SingleNodeRoutingInfo::ByAddress { port, .. } => Some(port),
+                    _ => None,
+                }
+            }
+            _ => panic!("Expected CLUSTER SLOTS request"),
+        }
+    }
+
+    #[test]
+    fn test_scan_each_primary() {
+        let topology = slots_value(&[(0, 8000, 6379), (8001, 16383, 6380)]);
+        let mut scan = ClusterScan::new(crate::cmd("SCAN").cursor_arg(0).clone(), vec![]);
+
+        assert_eq!(topology_request_port(&scan), None);
+        assert_eq!(scan.handle_response(Ok(topology.clone())), Ok(vec![]));
+        assert_eq!(scan_address(&scan).1, 6379);
+        let items = scan.handle_response(Ok(scan_reply(5, &["a"]))).unwrap();
+        assert_eq!(items, vec![Value::BulkString(b"a".to_vec())]);
+        let (_, port, packed) = scan_address(&scan);
+        assert_eq!(port, 6379);
+        assert_eq!(packed, crate::cmd("SCAN").arg(5).get_packed_command());
+        scan.handle_response(Ok(scan_reply(0, &["b"]))).unwrap();
+
+        // Topology is requested from the primary which was just scanned.
+        assert_eq!(topology_request_port(&scan), Some(6379));
+        scan.handle_response(Ok(topology.clone())).unwrap();
+        let (_, port, packed) = scan_address(&scan);
+        assert_eq!(port, 6380);
+        assert_eq!(packed, crate::cmd("SCAN").arg(0).get_packed_command());
+        scan.handle_response(Ok(scan_reply(0, &["c"]))).unwrap();
+
+        scan.handle_response(Ok(topology)).unwrap();
+        assert!(scan.next_request().is_none());
+    }
+
+    #[test]
+    fn test_scan_restarts_on_new_primary_after_failover() {
+        let mut scan = ClusterScan::new(crate::cmd("SCAN").cursor_arg(0).clone(), vec![]);
+        scan.handle_response(Ok(slots_value(&[(0, 8000, 6379), (8001, 16383, 6380)])))
+            .unwrap();
+        scan.handle_response(Ok(scan_reply(5, &["a"]))).unwrap();
+
+        // Primary fails, so its error is handled by checking the topology.
+        let error = RedisError::from((ErrorKind::IoError, "connection dropped"));
+        assert_eq!(scan.handle_response(Err(error)), Ok(vec![]));
+        assert_eq!(
+            topology_request_port(&scan),
+            Some(6380),
+            "Topology must be requested from another primary"
+        );
+        scan.handle_response(Ok(slots_value(&[(0, 8000, 6381), (8001, 16383, 6380)])))
+            .unwrap();
+
+        let (_, port, packed) = scan_address(&scan);
+        assert_eq!(port, 6381);
+        assert_eq!(packed, crate::cmd("SCAN").arg(0).get_packed_command());
+    }
+
+    #[test]
+    fn test_scan_stops_after_topology_requests_keep_failing() {
+        let mut scan = ClusterScan::new(crate::cmd("SCAN").cursor_arg(0).clone(), vec![]);
+        let error = || RedisError::from((ErrorKind::IoError, "connection dropped"));
+        for _ in 0..MAX_RETRIES_ON_SAME_PRIMARY {
+            assert_eq!(scan.handle_response(Err(error())), Ok(vec![]));
+            assert!(scan.next_request().is_some());
+        }
+        assert_eq!(scan.handle_response(Err(error())), Err(error()));
+        assert!(scan.next_request().is_none());
+    }
+
+    #[test]
+    fn test_scan_returns_error_if_primary_is_not_replaced() {
+        let topology = slots_value(&[(0, 16383, 6379)]);
+        let mut scan = ClusterScan::new(crate::cmd("SCAN").cursor_arg(0).clone(), vec![]);
+        scan.handle_response(Ok(topology.clone())).unwrap();
+        scan.handle_response(Ok(scan_reply(5, &[]))).unwrap();
+
+        let error = || RedisError::from((ErrorKind::ResponseError, "error"));
+        scan.handle_response(Err(error())).unwrap();
+        assert_eq!(
+            scan.handle_response(Ok(topology.clone())),
+            Ok(vec![]),
+            "Error must not be returned while the scan is retried"
+        );
+
+        // Scan continues with the same cursor.
+        let (_, port, packed) = scan_address(&scan);
+        assert_eq!(port, 6379);
+        assert_eq!(packed, crate::cmd("SCAN").arg(5).get_packed_command());
+
+        for _ in 1..MAX_RETRIES_ON_SAME_PRIMARY {
+            scan.handle_response(Err(error())).unwrap();
+            assert_eq!(scan.handle_response(Ok(topology.clone())), Ok(vec![]));
+            assert_eq!(scan_address(&scan).1, 6379);
+        }
+        scan.handle_response(Err(error())).unwrap();
+        assert_eq!(scan.handle_response(Ok(topology)), Err(error()));
+        assert!(
+            scan.next_request().is_none(),
+            "Scan must stop after retrying the same primary too many times"
+        );
+    }
+
+    #[test]
+    fn test_topology_is_requested_from_known_primary() {
+        let mut scan = ClusterScan::new(
+            crate::cmd("SCAN").cursor_arg(0).clone(),
+            vec!["node1:6379".to_string(), "node2:6380".to_string()],
+        );
+        assert_eq!(topology_request_port(&scan), Some(6379));
+        let error = RedisError::from((ErrorKind::IoError, "connection dropped"));
+        assert_eq!(scan.handle_response(Err(error)), Ok(vec![]));
+        assert_eq!(
+            topology_request_port(&scan),
+            Some(6380),
+            "Topology must be requested from the next primary after a failure"
+        );
+
+        // Node without a hostname is resolved to the host of the answering primary.
+        let topology = Value::Array(vec![Value::Array(vec![
+            Value::Int(0),
+            Value::Int(16383),
+            Value::Array(vec![Value::BulkString(vec![]), Value::Int(6381)]),
+        ])]);
+        scan.handle_response(Ok(topology)).unwrap();
+        assert_eq!(
+            scan_address(&scan),
+            (
+                "node2".to_string(),
+                6381,
+                crate::cmd("SCAN").arg(0).get_packed_command()
+            )
+        );
+    }
+
+    #[test]
+    fn test_moved_slots_are_scanned_on_new_primary() {
+        let mut scan = ClusterScan::new(crate::cmd("SCAN").cursor_arg(0).clone(), vec![]);
+        scan.handle_response(Ok(slots_value(&[(0, 8000, 6379), (8001, 16383, 6380)])))
+            .unwrap();
+        scan.handle_response(Ok(scan_reply(0, &[]))).unwrap();
+
+        // Slots 4001-8000 are moved to the other primary while the first one was scanned.
+        scan.handle_response(Ok(slots_value(&[(0, 4000, 6379), (4001, 16383, 6380)])))
+            .unwrap();
+        assert!(!scan.scanned_slots[4001]);
+        assert!(scan.scanned_slots[4000]);
+        assert_eq!(scan_address(&scan).1, 6380);
+    }
+}
@@ -1,9 +1,200 @@
 //! This module provides the functionality to refresh and calculate the cluster topology for Redis Cluster.
 
+#[cfg(feature = "cluster-async")]
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
 use crate::cluster::get_connection_addr;
-use crate::cluster_routing::Slot;
+use crate::cluster_routing::{get_slot, NodeHealth, NodeInfo, Slot};
+use crate::types::{from_owned_redis_value, ErrorKind};
 use crate::{cluster::TlsMode, RedisResult, Value};
 
+/// A range of slots, and the nodes which serve it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotRange {
+    start: u16,
+    end: u16,
+    primary: String,
+    replicas: Vec<String>,
+}
+
+impl SlotRange {
+    /// First slot of the range.
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    /// Last slot of the range, inclusive.
+    pub fn end(&self) -> u16 {
+        self.end
+    }
+
+    /// Address of the primary which serves the range.
+    pub fn primary(&self) -> &str {
+        &self.primary
+    }
+
+    /// Addresses of the replicas which serve the range.
+    pub fn replicas(&self) -> &[String] {
+        &self.replicas
+    }
+}
+
+impl From<Slot> for SlotRange {
+    fn from(slot: Slot) -> Self {
+        Self {
+            start: slot.start,
+            end: slot.end,
+            primary: slot.master,
+            replicas: slot.replicas,
+        }
+    }
+}
+
+/// A snapshot of the topology of a cluster, as known by a connection when it was taken.
+///
+/// Returned by [`crate::cluster::ClusterConnection::topology`] and
+/// [`crate::cluster_async::ClusterConnection::topology`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClusterTopology {
+    slot_ranges: Vec<SlotRange>,
+    node_ids: HashMap<String, String>,
+}
+
+impl ClusterTopology {
+    pub(crate) fn new(slots: Vec<Slot>) -> Self {
+        let mut node_ids = HashMap::new();
+        let mut slot_ranges: Vec<SlotRange> = slots
+            .into_iter()
+            .map(|mut slot| {
+                node_ids.extend(
+                    std::mem::take(&mut slot.nodes)
+                        .into_iter()
+                        .map(|node| (node.address, node.id)),
+                );
+                SlotRange::from(slot)
+            })
+            .collect();
+        slot_ranges.sort_by_key(|slot_range| slot_range.start);
+        Self {
+            slot_ranges,
+            node_ids,
+        }
+    }
+
+    /// Slot ranges of the cluster, ordered by their first slot.
+    pub fn slot_ranges(&self) -> &[SlotRange] {
+        &self.slot_ranges
+    }
+
+    /// Addresses of the primaries of the cluster, ordered by the first slot they serve.
+    pub fn primaries(&self) -> Vec<&str> {
+        let mut primaries: Vec<&str> = Vec::new();
+        for slot_range in &self.slot_ranges {
+            if !primaries.contains(&slot_range.primary()) {
+                primaries.push(slot_range.primary());
+            }
+        }
+        primaries
+    }
+
+    /// Returns the slot range which contains `slot`, if it's served by the cluster.
+    pub fn slot_range_for_slot(&self, slot: u16) -> Option<&SlotRange> {
+        let index = self
+            .slot_ranges
+            .partition_point(|slot_range| slot_range.end < slot);
+        self.slot_ranges
+            .get(index)
+            .filter(|slot_range| slot_range.start <= slot)
+    }
+
+    /// Returns the slot range which contains the slot of `key`, if it's served by the cluster.
+    pub fn slot_range_for_key(&self, key: &[u8]) -> Option<&SlotRange> {
+        self.slot_range_for_slot(get_slot(key))
+    }
+
+    /// Returns the id of the node at `address`.
+    ///
+    /// Ids are only known if the topology was discovered with `CLUSTER SHARDS`, which is supported
+    /// by Redis 7.0 and later.
+    pub fn node_id(&self, address: &str) -> Option<&str> {
+        self.node_ids.get(address).map(String::as_str)
+    }
+}
+
+/// Returns true if both views assign the same nodes to the same slots, regardless of the order
+/// in which nodes reported the ranges and replicas.
+#[cfg(feature = "cluster-async")]
+pub(crate) fn same_topology(view: &[Slot], other: &[Slot]) -> bool {
+    fn normalize(view: &[Slot]) -> Vec<(u16, u16, &str, Vec<&str>)> {
+        let mut normalized: Vec<_> = view
+            .iter()
+            .map(|slot| {
+                let mut replicas: Vec<&str> = slot.replicas.iter().map(String::as_str).collect();
+                replicas.sort_unstable();
+                (slot.start, slot.end, slot.master.as_str(), replicas)
+            })
+            .collect();
+        normalized.sort_unstable();
+        normalized
+    }
+    normalize(view) == normalize(other)
+}
+
+/// A view of the topology, and the nodes which reported it.
+#[cfg(feature = "cluster-async")]
+#[derive(Debug)]
+pub(crate) struct TopologyView {
+    pub(crate) nodes: Vec<String>,
+    pub(crate) slots: Vec<Slot>,
+}
+
+/// Groups the views reported by nodes which agree with each other, the view reported by most nodes first.
+#[cfg(feature = "cluster-async")]
+pub(crate) fn group_views(reported: Vec<(String, Vec<Slot>)>) -> Vec<TopologyView> {
+    let mut views: Vec<TopologyView> = Vec::new();
+    for (node, slots) in reported {
+        match views
+            .iter_mut()
+            .find(|view| same_topology(&view.slots, &slots))
+        {
+            Some(view) => view.nodes.push(node),
+            None => views.push(TopologyView {
+                nodes: vec![node],
+                slots,
+            }),
+        }
+    }
+    // The sort is stable, so tied views stay in the order in which they were reported.
+    views.sort_by_key(|view| Reverse(view.nodes.len()));
+    views
+}
+
+/// Selects the view reported by most nodes from views grouped by [`group_views`]. Ties are broken
+/// in favor of the view reported by the node with the highest epoch, and then by the order of the views.
+#[cfg(feature = "cluster-async")]
+pub(crate) fn select_view(
+    views: Vec<TopologyView>,
+    epochs: &HashMap<String, u64>,
+) -> Option<TopologyView> {
+    let majority = views.first()?.nodes.len();
+    let epoch = |view: &TopologyView| {
+        view.nodes
+            .iter()
+            .filter_map(|node| epochs.get(node))
+            .max()
+            .copied()
+    };
+    // `max_by_key` returns the last maximal element, so the views are reversed to prefer the first one.
+    views
+        .into_iter()
+        .take_while(|view| view.nodes.len() == majority)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .max_by_key(epoch)
+}
+
 // Parse slot data from raw redis value.
 pub(crate) fn parse_slots(
     raw_slot_resp: Value,
@@ -88,6 +279,164 @@ pub(crate) fn parse_slots(
     Ok(slots)
 }
 
+// Parse slot data from the response of `CLUSTER SHARDS`.
+// Replicas which aren't online are skipped, so that they don't serve reads.
+pub(crate) fn parse_shards(
+    raw_shards_resp: Value,
+    tls: Option<TlsMode>,
+    // The DNS address of the node from which `raw_shards_resp` was received.
+    addr_of_answering_node: &str,
+) -> RedisResult<Vec<Slot>> {
+    let Value::Array(shards) = raw_shards_resp else {
+        fail!(invalid_shards_response());
+    };
+
+    let mut slots = Vec::with_capacity(shards.len());
+    for shard in shards {
+        let mut slot_ranges = Vec::new();
+        let mut master = None;
+        let mut replicas = Vec::new();
+        for (field, value) in into_fields(shard).ok_or_else(invalid_shards_response)? {
+            match field.as_str() {
+                "slots" => slot_ranges = from_owned_redis_value::<Vec<u16>>(value)?,
+                "nodes" => {
+                    let Value::Array(nodes) = value else {
+                        fail!(invalid_shards_response());
+                    };
+                    for node in nodes {
+                        let fields = into_fields(node).ok_or_else(invalid_shards_response)?;
+                        let Some((is_master, node)) =
+                            parse_shard_node(fields, tls, addr_of_answering_node)?
+                        else {
+                            continue;
+                        };
+                        if is_master && master.is_none() {
+                            master = Some(node);
+                        } else if !is_master && node.health == NodeHealth::Online {
+                            replicas.push(node);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let Some(master) = master else {
+            continue;
+        };
+        for range in slot_ranges.chunks_exact(2) {
+            slots.push(Slot {
+                start: range[0],
+                end: range[1],
+                master: master.address.clone(),
+                replicas: replicas.iter().map(|node| node.address.clone()).collect(),
+                nodes: std::iter::once(&master)
+                    .chain(replicas.iter())
+                    .cloned()
+                    .collect(),
+            });
+        }
+    }
+
+    if slots.is_empty() {
+        fail!((
+            ErrorKind::ResponseError,
+            "CLUSTER SHARDS didn't report any slots"
+        ));
+    }
+    Ok(slots)
+}
+
+// Returns whether the node is a master, and its metadata, or None if its address is unknown.
+fn parse_shard_node(
+    fields: Vec<(String, Value)>,
+    tls: Option<TlsMode>,
+    addr_of_answering_node: &str,
+) -> RedisResult<Option<(bool, NodeInfo)>> {
+    let mut id = String::new();
+    let mut port = None;
+    let mut tls_port = None;
+    let mut ip = None;
+    let mut endpoint = None;
+    let mut hostname = None;
+    let mut is_master = false;
+    let mut replication_offset = None;
+    let mut health = NodeHealth::Online;
+    for (field, value) in fields {
+        match field.as_str() {
+            "id" => id = from_owned_redis_value(value)?,
+            "port" => port = Some(from_owned_redis_value::<u16>(value)?),
+            "tls-port" => tls_port = Some(from_owned_redis_value::<u16>(value)?),
+            "ip" => ip = Some(from_owned_redis_value::<String>(value)?),
+            "endpoint" => endpoint = Some(from_owned_redis_value::<String>(value)?),
+            "hostname" => {
+                hostname = Some(from_owned_redis_value::<String>(value)?)
+                    .filter(|hostname| !hostname.is_empty())
+            }
+            "role" => is_master = from_owned_redis_value::<String>(value)? == "master",
+            "replication-offset" => replication_offset = from_owned_redis_value(value)?,
+            "health" => {
+                health = match from_owned_redis_value::<String>(value)?.as_str() {
+                    "online" => NodeHealth::Online,
+                    "loading" => NodeHealth::Loading,
+                    _ => NodeHealth::Failed,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Same as in `CLUSTER SLOTS`, an empty endpoint is the answering node, and "?" is an unknown node.
+    let host = match endpoint.or(ip) {
+        Some(host) if host == "?" => return Ok(None),
+        Some(host) if !host.is_empty() => host,
+        _ => addr_of_answering_node.to_string(),
+    };
+    let port = if tls.is_some() {
+        tls_port.or(port)
+    } else {
+        port.or(tls_port)
+    };
+    let Some(port) = port.filter(|_| !host.is_empty()) else {
+        return Ok(None);
+    };
+
+    Ok(Some((
+        is_master,
+        NodeInfo {
+            address: get_connection_addr(host, port, tls, None).to_string(),
+            id,
+            hostname,
+            health,
+            replication_offset,
+        },
+    )))
+}
+
+// RESP2 returns the fields of a shard or a node as a flat array, RESP3 returns them as a map.
+fn into_fields(value: Value) -> Option<Vec<(String, Value)>> {
+    let pairs: Vec<(Value, Value)> = match value {
+        Value::Map(pairs) => pairs,
+        Value::Array(items) if items.len() % 2 == 0 => {
+            let mut items = items.into_iter();
+            std::iter::from_fn(|| Some((items.next()?, items.next()?))).collect()
+        }
+        _ => return None,
+    };
+    pairs
+        .into_iter()
+        .map(|(field, value)| Some((from_owned_redis_value(field).ok()?, value)))
+        .collect()
+}
+
+fn invalid_shards_response() -> crate::RedisError {
+    (
+        ErrorKind::TypeError,
+        "Response of CLUSTER SHARDS has an unexpected format",
+    )
+        .into()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,6 +460,238 @@ mod tests {
         slot_value_with_replicas(start, end, vec![(node, port)])
     }
 
+    #[cfg(feature = "cluster-async")]
+    #[test]
+    fn same_topology_ignores_order_of_ranges_and_replicas() {
+        let slot = |start, end, master: &str, replicas: &[&str]| {
+            Slot::new(
+                start,
+                end,
+                master.to_string(),
+                replicas.iter().map(|replica| replica.to_string()).collect(),
+            )
+        };
+        let view = vec![
+            slot(0, 8000, "node1:6379", &["node3:6379", "node4:6379"]),
+            slot(8001, 16383, "node2:6379", &[]),
+        ];
+        let reordered = vec![
+            slot(8001, 16383, "node2:6379", &[]),
+            slot(0, 8000, "node1:6379", &["node4:6379", "node3:6379"]),
+        ];
+        assert!(same_topology(&view, &reordered));
+
+        let with_new_replica = vec![
+            slot(0, 8000, "node1:6379", &["node3:6379", "node4:6379"]),
+            slot(8001, 16383, "node2:6379", &["node5:6379"]),
+        ];
+        assert!(!same_topology(&view, &with_new_replica));
+    }
+
+    #[cfg(feature = "cluster-async")]
+    #[test]
+    fn select_view_prefers_majority_and_then_highest_epoch() {
+        let view = |primary: &str| vec![Slot::new(0, 16383, primary.to_string(), Vec::new())];
+        let reported = |nodes: &[(&str, &str)]| {
+            nodes
+                .iter()
+                .map(|(node, primary)| (node.to_string(), view(primary)))
+                .collect::<Vec<_>>()
+        };
+
+        let views = group_views(reported(&[
+            ("node1:6379", "node1:6379"),
+            ("node2:6379", "node2:6379"),
+            ("node3:6379", "node2:6379"),
+        ]));
+        assert_eq!(views.len(), 2);
+        let epochs = HashMap::from([("node1:6379".to_string(), 10)]);
+        let selected = select_view(views, &epochs).unwrap();
+        assert_eq!(selected.nodes, vec!["node2:6379", "node3:6379"]);
+        assert!(same_topology(&selected.slots, &view("node2:6379")));
+
+        let tied = || {
+            group_views(reported(&[
+                ("node1:6379", "node1:6379"),
+                ("node2:6379", "node2:6379"),
+            ]))
+        };
+        let epochs = HashMap::from([("node1:6379".to_string(), 3), ("node2:6379".to_string(), 4)]);
+        assert_eq!(
+            select_view(tied(), &epochs).unwrap().nodes,
+            vec!["node2:6379"]
+        );
+        // Without epochs, the first reported view is used.
+        assert_eq!(
+            select_view(tied(), &HashMap::new()).unwrap().nodes,
+            vec!["node1:6379"]
+        );
+        assert!(select_view(Vec::new(), &HashMap::new()).is_none());
+    }
+
+    fn shard_node(fields: &[(&str, Value)]) -> Value {
+        Value::Array(
+            fields
+                .iter()
+                .flat_map(|(field, value)| {
+                    [Value::BulkString(field.as_bytes().to_vec()), value.clone()]
+                })
+                .collect(),
+        )
+    }
+
+    fn bulk(value: &str) -> Value {
+        Value::BulkString(value.as_bytes().to_vec())
+    }
+
+    fn node(id: &str, endpoint: &str, port: i64, role: &str, health: &str) -> Value {
+        shard_node(&[
+            ("id", bulk(id)),
+            ("port", Value::Int(port)),
+            ("tls-port", Value::Int(port + 1000)),
+            ("ip", bulk("10.0.0.1")),
+            ("endpoint", bulk(endpoint)),
+            ("hostname", bulk("")),
+            ("role", bulk(role)),
+            ("replication-offset", Value::Int(72156)),
+            ("health", bulk(health)),
+        ])
+    }
+
+    #[test]
+    fn parse_shards_skips_replicas_which_are_not_online() {
+        let view = Value::Array(vec![
+            shard_node(&[
+                (
+                    "slots",
+                    Value::Array(vec![
+                        Value::Int(0),
+                        Value::Int(100),
+                        Value::Int(200),
+                        Value::Int(300),
+                    ]),
+                ),
+                (
+                    "nodes",
+                    Value::Array(vec![
+                        node("id1", "node1", 6379, "replica", "online"),
+                        node("id2", "node2", 6379, "master", "online"),
+                        node("id3", "node3", 6379, "replica", "loading"),
+                        node("id4", "node4", 6379, "replica", "failed"),
+                    ]),
+                ),
+            ]),
+            // Shards without slots are ignored.
+            shard_node(&[
+                ("slots", Value::Array(vec![])),
+                (
+                    "nodes",
+                    Value::Array(vec![node("id5", "node5", 6379, "master", "online")]),
+                ),
+            ]),
+        ]);
+
+        let slots = parse_shards(view, None, "").unwrap();
+        assert_eq!(slots.len(), 2);
+        assert_eq!((slots[0].start, slots[0].end), (0, 100));
+        assert_eq!((slots[1].start, slots[1].end), (200, 300));
+        for slot in slots {
+            assert_eq!(slot.master, "node2:6379");
+            assert_eq!(slot.replicas, vec!["node1:6379"]);
+            assert_eq!(
+                slot.nodes
+                    .iter()
+                    .map(|node| node.id.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["id2", "id1"]
+            );
+            assert_eq!(slot.nodes[0].health, NodeHealth::Online);
+            assert_eq!(slot.nodes[0].replication_offset, Some(72156));
+            assert_eq!(slot.nodes[0].hostname, None);
+        }
+    }
+
+    #[test]
+    fn cluster_topology_finds_slot_ranges_and_node_ids() {
+        let shard = |slots: Vec<Value>, nodes: Vec<Value>| {
+            shard_node(&[
+                ("slots", Value::Array(slots)),
+                ("nodes", Value::Array(nodes)),
+            ])
+        };
+        let view = Value::Array(vec![
+            shard(
+                vec![Value::Int(8192), Value::Int(16383)],
+                vec![node("id2", "node2", 6379, "master", "online")],
+            ),
+            shard(
+                vec![Value::Int(0), Value::Int(8191)],
+                vec![
+                    node("id1", "node1", 6379, "master", "online"),
+                    node("id3", "node3", 6379, "replica", "online"),
+                ],
+            ),
+        ]);
+
+        let topology = ClusterTopology::new(parse_shards(view, None, "").unwrap());
+        let starts: Vec<u16> = topology
+            .slot_ranges()
+            .iter()
+            .map(SlotRange::start)
+            .collect();
+        assert_eq!(starts, vec![0, 8192]);
+        assert_eq!(topology.primaries(), vec!["node1:6379", "node2:6379"]);
+        assert_eq!(
+            topology.slot_range_for_slot(8191).unwrap().primary(),
+            "node1:6379"
+        );
+        assert_eq!(
+            topology.slot_range_for_slot(8192).unwrap().primary(),
+            "node2:6379"
+        );
+        // "foo" hashes to slot 12182.
+        assert_eq!(
+            topology.slot_range_for_key(b"foo").unwrap().primary(),
+            "node2:6379"
+        );
+        assert_eq!(topology.node_id("node3:6379"), Some("id3"));
+        assert_eq!(topology.node_id("node4:6379"), None);
+
+        let partial =
+            ClusterTopology::new(vec![Slot::new(0, 100, "node1:6379".to_string(), vec![])]);
+        assert!(partial.slot_range_for_slot(101).is_none());
+    }
+
+    #[test]
+    fn parse_shards_handles_resp3_maps_and_tls_ports() {
+        let view = Value::Array(vec![Value::Map(vec![
+            (
+                bulk("slots"),
+                Value::Array(vec![Value::Int(0), Value::Int(16383)]),
+            ),
+            (
+                bulk("nodes"),
+                Value::Array(vec![Value::Map(vec![
+                    (bulk("id"), bulk("id1")),
+                    (bulk("port"), Value::Int(6379)),
+                    (bulk("tls-port"), Value::Int(7379)),
+                    (bulk("endpoint"), bulk("")),
+                    (bulk("role"), bulk("master")),
+                    (bulk("health"), bulk("online")),
+                ])]),
+            ),
+        ])]);
+
+        let slots = parse_shards(view, Some(TlsMode::Secure), "node").unwrap();
+        assert_eq!(slots[0].master, "node:7379");
+    }
+
+    #[test]
+    fn parse_shards_fails_on_cluster_slots_response() {
+        let view = Value::Array(vec![slot_value(0, 4000, "node", 6379)]);
+        assert!(parse_shards(view, None, "").is_err());
+    }
+
     #[test]
     fn parse_slots_returns_slots_with_host_name_if_missing() {
         let view = Value::Array(vec![slot_value(0, 4000, "", 6379)]);
@@ -6,13 +6,19 @@ use futures_util::{
 };
 #[cfg(feature = "aio")]
 use std::pin::Pin;
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use std::time::Duration;
 use std::{fmt, io};
 
+#[cfg(feature = "cluster")]
+use crate::cluster_routing::RoutingInfo;
+#[cfg(feature = "cluster")]
+use crate::cluster_scan::ClusterScan;
 use crate::connection::ConnectionLike;
 use crate::pipeline::Pipeline;
-use crate::types::{from_owned_redis_value, FromRedisValue, RedisResult, RedisWrite, ToRedisArgs};
+use crate::types::{
+    from_owned_redis_value, FromRedisValue, RedisResult, RedisWrite, ToRedisArgs, Value,
+};
 
 /// An argument to a redis command
 #[derive(Clone)]
@@ -35,15 +41,15 @@ pub enum Arg<D> {
 ///     .set_client_side_ttl(ttl);
 /// let command = Cmd::new().arg("GET").arg("key").set_cache_config(config);
 /// ```
-#[cfg(feature = "cache-aio")]
-#[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+#[cfg(feature = "cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
 #[derive(Clone)]
 pub struct CommandCacheConfig {
     pub(crate) enable_cache: bool,
     pub(crate) client_side_ttl: Option<Duration>,
 }
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 impl CommandCacheConfig {
     /// Creates new CommandCacheConfig with enable_cache as true and without client_side_ttl.
     pub fn new() -> Self {
@@ -66,7 +72,7 @@ impl CommandCacheConfig {
         self
     }
 }
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 impl Default for CommandCacheConfig {
     fn default() -> Self {
         Self::new()
@@ -79,10 +85,10 @@ pub struct Cmd {
     pub(crate) data: Vec<u8>,
     // Arg::Simple contains the offset that marks the end of the argument
     args: Vec<Arg<usize>>,
-    cursor: Option<u64>,
+    pub(crate) cursor: Option<u64>,
     // If it's true command's response won't be read from socket. Useful for Pub/Sub.
     no_response: bool,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     cache: Option<CommandCacheConfig>,
 }
 
@@ -122,8 +128,51 @@ impl<T: FromRedisValue> Iterator for Iter<'_, T> {
 /// Represents a safe(r) redis iterator.
 struct CheckedIter<'a, T: FromRedisValue> {
     batch: std::vec::IntoIter<RedisResult<T>>,
-    con: &'a mut (dyn ConnectionLike + 'a),
-    cmd: Cmd,
+    source: IterSource<'a>,
+}
+
+#[cfg(feature = "cluster")]
+type ClusterRoute<'a> = Box<dyn FnMut(&Cmd, RoutingInfo) -> RedisResult<Value> + 'a>;
+
+/// Fetches the batches of an iterator.
+enum IterSource<'a> {
+    /// Command with a cursor argument, which is sent to a single connection.
+    Cursor {
+        con: &'a mut (dyn ConnectionLike + 'a),
+        cmd: Cmd,
+    },
+    /// `SCAN` of every primary in the cluster, `route` sends a command to the given route.
+    #[cfg(feature = "cluster")]
+    ClusterScan {
+        scan: Box<ClusterScan>,
+        route: ClusterRoute<'a>,
+    },
+}
+
+impl IterSource<'_> {
+    /// Returns the next batch of items, or `None` if the iteration is finished.
+    fn next_batch(&mut self) -> Option<RedisResult<Vec<Value>>> {
+        match self {
+            IterSource::Cursor { con, cmd } => {
+                if cmd.cursor? == 0 {
+                    return None;
+                }
+                Some(
+                    con.req_packed_command(&cmd.get_packed_command())
+                        .and_then(from_owned_redis_value::<(u64, Vec<Value>)>)
+                        .map(|(cursor, items)| {
+                            cmd.cursor = Some(cursor);
+                            items
+                        }),
+                )
+            }
+            #[cfg(feature = "cluster")]
+            IterSource::ClusterScan { scan, route } => {
+                let (cmd, routing) = scan.next_request()?;
+                Some(scan.handle_response(route(&cmd, routing)))
+            }
+        }
+    }
 }
 
 impl<T: FromRedisValue> Iterator for CheckedIter<'_, T> {
@@ -140,21 +189,31 @@ impl<T: FromRedisValue> Iterator for CheckedIter<'_, T> {
                 return Some(value);
             };
 
-            if self.cmd.cursor? == 0 {
-                return None;
-            }
-
-            let (cursor, batch) = match self
-                .con
-                .req_packed_command(&self.cmd.get_packed_command())
-                .and_then(from_owned_redis_value::<(u64, _)>)
-            {
-                Ok((cursor, values)) => (cursor, T::from_each_owned_redis_values(values)),
+            match self.source.next_batch()? {
+                Ok(items) => self.batch = T::from_each_owned_redis_values(items).into_iter(),
                 Err(e) => return Some(Err(e)),
-            };
+            }
+        }
+    }
+}
 
-            self.cmd.cursor = Some(cursor);
-            self.batch = batch.into_iter();
+#[cfg(feature = "cluster")]
+#[allow(deprecated)]
+impl<'a, T: FromRedisValue> Iter<'a, T> {
+    /// Creates an iterator over the keys of every primary in the cluster, `cmd` must be a `SCAN` command.
+    pub(crate) fn cluster_scan(
+        cmd: Cmd,
+        primaries: Vec<String>,
+        route: impl FnMut(&Cmd, RoutingInfo) -> RedisResult<Value> + 'a,
+    ) -> Self {
+        Iter {
+            iter: CheckedIter {
+                batch: Vec::new().into_iter(),
+                source: IterSource::ClusterScan {
+                    scan: Box::new(ClusterScan::new(cmd, primaries)),
+                    route: Box::new(route),
+                },
+            },
         }
     }
 }
@@ -166,8 +225,56 @@ use crate::aio::ConnectionLike as AsyncConnection;
 #[cfg(feature = "aio")]
 struct AsyncIterInner<'a, T: FromRedisValue + 'a> {
     batch: std::vec::IntoIter<RedisResult<T>>,
-    con: &'a mut (dyn AsyncConnection + Send + 'a),
-    cmd: Cmd,
+    source: AsyncIterSource<'a>,
+}
+
+#[cfg(feature = "cluster-async")]
+type AsyncClusterRoute<'a> =
+    Box<dyn FnMut(Cmd, RoutingInfo) -> BoxFuture<'a, RedisResult<Value>> + Send + 'a>;
+
+/// Fetches the batches of an async iterator.
+#[cfg(feature = "aio")]
+enum AsyncIterSource<'a> {
+    /// Command with a cursor argument, which is sent to a single connection.
+    Cursor {
+        con: &'a mut (dyn AsyncConnection + Send + 'a),
+        cmd: Cmd,
+    },
+    /// `SCAN` of every primary in the cluster, `route` sends a command to the given route.
+    #[cfg(feature = "cluster-async")]
+    ClusterScan {
+        scan: Box<ClusterScan>,
+        route: AsyncClusterRoute<'a>,
+    },
+}
+
+#[cfg(feature = "aio")]
+impl AsyncIterSource<'_> {
+    /// Returns the next batch of items, or `None` if the iteration is finished.
+    async fn next_batch(&mut self) -> Option<RedisResult<Vec<Value>>> {
+        match self {
+            AsyncIterSource::Cursor { con, cmd } => {
+                if cmd.cursor? == 0 {
+                    return None;
+                }
+                Some(
+                    con.req_packed_command(cmd)
+                        .await
+                        .and_then(from_owned_redis_value::<(u64, Vec<Value>)>)
+                        .map(|(cursor, items)| {
+                            cmd.cursor = Some(cursor);
+                            items
+                        }),
+                )
+            }
+            #[cfg(feature = "cluster-async")]
+            AsyncIterSource::ClusterScan { scan, route } => {
+                let (cmd, routing) = scan.next_request()?;
+                let response = route(cmd, routing).await;
+                Some(scan.handle_response(response))
+            }
+        }
+    }
 }
 
 /// Represents the state of AsyncIter
@@ -202,22 +309,31 @@ impl<'a, T: FromRedisValue + 'a> AsyncIterInner<'a, T> {
                 return Some(v);
             };
 
-            if self.cmd.cursor? == 0 {
-                return None;
-            }
-
-            let (cursor, batch) = match self
-                .con
-                .req_packed_command(&self.cmd)
-                .await
-                .and_then(from_owned_redis_value::<(u64, _)>)
-            {
-                Ok((cursor, items)) => (cursor, T::from_each_owned_redis_values(items)),
+            match self.source.next_batch().await? {
+                Ok(items) => self.batch = T::from_each_owned_redis_values(items).into_iter(),
                 Err(e) => return Some(Err(e)),
-            };
+            }
+        }
+    }
+}
 
-            self.cmd.cursor = Some(cursor);
-            self.batch = batch.into_iter();
+#[cfg(feature = "cluster-async")]
+#[allow(deprecated)]
+impl<'a, T: FromRedisValue + 'a> AsyncIter<'a, T> {
+    /// Creates an iterator over the keys of every primary in the cluster, `cmd` must be a `SCAN` command.
+    pub(crate) fn cluster_scan(
+        cmd: Cmd,
+        primaries: Vec<String>,
+        route: impl FnMut(Cmd, RoutingInfo) -> BoxFuture<'a, RedisResult<Value>> + Send + 'a,
+    ) -> Self {
+        AsyncIter {
+            inner: IterOrFuture::Iter(AsyncIterInner {
+                batch: Vec::new().into_iter(),
+                source: AsyncIterSource::ClusterScan {
+                    scan: Box::new(ClusterScan::new(cmd, primaries)),
+                    route: Box::new(route),
+                },
+            }),
         }
     }
 }
@@ -518,7 +634,7 @@ impl Cmd {
             args: vec![],
             cursor: None,
             no_response: false,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache: None,
         }
     }
@@ -530,7 +646,7 @@ impl Cmd {
             args: Vec::with_capacity(arg_count),
             cursor: None,
             no_response: false,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache: None,
         }
     }
@@ -565,7 +681,7 @@ impl Cmd {
         self.args.clear();
         self.cursor = None;
         self.no_response = false;
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         {
             self.cache = None;
         }
@@ -713,8 +829,7 @@ impl Cmd {
         Ok(Iter {
             iter: CheckedIter {
                 batch: batch.into_iter(),
-                con,
-                cmd: self,
+                source: IterSource::Cursor { con, cmd: self },
             },
         })
     }
@@ -747,8 +862,7 @@ impl Cmd {
         Ok(AsyncIter {
             inner: IterOrFuture::Iter(AsyncIterInner {
                 batch: batch.into_iter(),
-                con,
-                cmd: self,
+                source: AsyncIterSource::Cursor { con, cmd: self },
             }),
         })
     }
@@ -804,7 +918,7 @@ impl Cmd {
     }
 
     // Get a reference to the argument at `idx`
-    #[cfg(any(feature = "cluster", feature = "cache-aio"))]
+    #[cfg(any(feature = "cluster", feature = "cache"))]
     pub(crate) fn arg_idx(&self, idx: usize) -> Option<&[u8]> {
         if idx >= self.args.len() {
             return None;
@@ -842,14 +956,14 @@ impl Cmd {
     }
 
     /// Changes caching behaviour for this specific command.
-    #[cfg(feature = "cache-aio")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
     pub fn set_cache_config(&mut self, command_cache_config: CommandCacheConfig) -> &mut Cmd {
         self.cache = Some(command_cache_config);
         self
     }
 
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     #[inline]
     pub(crate) fn get_cache_config(&self) -> &Option<CommandCacheConfig> {
         &self.cache
@@ -925,7 +1039,7 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     fn test_cmd_clean_cache_aio() {
         let mut cmd = Cmd::new();
         cmd.arg("key").arg("value");
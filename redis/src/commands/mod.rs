@@ -33,9 +33,12 @@ use crate::streams;
 
 #[cfg(feature = "acl")]
 use crate::acl;
+
+#[cfg(feature = "sentinel")]
+use crate::sentinel;
 use crate::{RedisConnectionInfo, Value};
 
-#[cfg(any(feature = "cluster", feature = "cache-aio"))]
+#[cfg(feature = "cluster")]
 pub(crate) fn is_readonly_cmd(cmd: &[u8]) -> bool {
     matches!(
         cmd,
@@ -2477,6 +2480,94 @@ implement_commands! {
         cmd("XTRIM").arg(key).arg(options)
     }
 
+    // sentinel commands
+
+    /// Returns the state of the master monitored with the given name.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_master<N: ToRedisArgs>(master_name: N) -> (sentinel::SentinelMasterInfo) {
+        cmd("SENTINEL").arg("MASTER").arg(master_name)
+    }
+
+    /// Returns the replicas of the master monitored with the given name.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_replicas<N: ToRedisArgs>(master_name: N) -> (Vec<sentinel::ReplicaInfo>) {
+        // `SLAVES` for compatibility with older redis versions
+        cmd("SENTINEL").arg("SLAVES").arg(master_name)
+    }
+
+    /// Returns the other sentinels monitoring the master with the given name.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_sentinels<N: ToRedisArgs>(master_name: N) -> (Vec<sentinel::SentinelInfo>) {
+        cmd("SENTINEL").arg("SENTINELS").arg(master_name)
+    }
+
+    /// Returns the address of the master monitored with the given name, or `None` if the
+    /// name isn't monitored. During a failover this is the address of the promoted replica.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_get_master_addr_by_name<N: ToRedisArgs>(master_name: N) -> (Option<(String, u16)>) {
+        cmd("SENTINEL").arg("GET-MASTER-ADDR-BY-NAME").arg(master_name)
+    }
+
+    /// Checks whether the sentinels currently reachable are enough to reach the quorum and
+    /// to authorize a failover of the master with the given name.
+    /// Fails with an error describing the problem otherwise.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_ckquorum<N: ToRedisArgs>(master_name: N) -> (String) {
+        cmd("SENTINEL").arg("CKQUORUM").arg(master_name)
+    }
+
+    /// Forces a failover of the master with the given name, without asking the other
+    /// sentinels for agreement.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_failover<N: ToRedisArgs>(master_name: N) -> (()) {
+        cmd("SENTINEL").arg("FAILOVER").arg(master_name)
+    }
+
+    /// Starts monitoring the master at the given address under the given name.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_monitor<N: ToRedisArgs, H: ToRedisArgs>(master_name: N, ip: H, port: u16, quorum: usize) -> (()) {
+        cmd("SENTINEL").arg("MONITOR").arg(master_name).arg(ip).arg(port).arg(quorum)
+    }
+
+    /// Stops monitoring the master with the given name.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_remove<N: ToRedisArgs>(master_name: N) -> (()) {
+        cmd("SENTINEL").arg("REMOVE").arg(master_name)
+    }
+
+    /// Changes a configuration option of the master with the given name, e.g. `down-after-milliseconds`.
+    /// Only supported by sentinels.
+    /// [Redis Docs](https://redis.io/docs/latest/operate/oss_and_stack/management/sentinel/#sentinel-commands)
+    #[cfg(feature = "sentinel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
+    fn sentinel_set<N: ToRedisArgs, O: ToRedisArgs, V: ToRedisArgs>(master_name: N, option: O, value: V) -> (()) {
+        cmd("SENTINEL").arg("SET").arg(master_name).arg(option).arg(value)
+    }
+
     // script commands
 
     /// Adds a prepared script command to the pipeline.
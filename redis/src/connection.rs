@@ -8,6 +8,9 @@ use std::path::PathBuf;
 use std::str::{from_utf8, FromStr};
 use std::time::{Duration, Instant};
 
+#[cfg(feature = "cache")]
+use crate::caching::{CacheManager, CacheStatistics, PrepareCacheResult};
+use crate::client::ConnectionConfig;
 use crate::cmd::{cmd, pipe, Cmd};
 use crate::io::tcp::{stream_with_settings, TcpSettings};
 use crate::parser::Parser;
@@ -652,6 +655,9 @@ pub struct Connection {
     /// The number of messages that are expected to be returned from the server,
     /// but the user no longer waits for - answers for requests that already returned a transient error.
     messages_to_skip: usize,
+
+    #[cfg(feature = "cache")]
+    cache_manager: Option<CacheManager>,
 }
 
 /// Represents a pubsub connection.
@@ -1096,6 +1102,39 @@ pub fn connect(
     connection_info: &ConnectionInfo,
     timeout: Option<Duration>,
 ) -> RedisResult<Connection> {
+    let mut config = ConnectionConfig::new();
+    if let Some(timeout) = timeout {
+        config = config.set_connection_timeout(timeout);
+    }
+    connect_with_config(connection_info, &config)
+}
+
+pub(crate) fn connect_with_config(
+    connection_info: &ConnectionInfo,
+    config: &ConnectionConfig,
+) -> RedisResult<Connection> {
+    #[cfg(feature = "cache")]
+    let cache_manager = match &config.cache {
+        Some(cache) => {
+            if connection_info.redis.protocol == ProtocolVersion::RESP2 {
+                return Err(RedisError::from((
+                    ErrorKind::InvalidClientConfig,
+                    "Can only enable client side caching in a connection using RESP3",
+                )));
+            }
+            Some(match cache {
+                crate::client::Cache::Config(cache_config) => {
+                    CacheManager::new(cache_config.clone())
+                        .with_scope(&connection_info.addr.to_string(), connection_info.redis.db)
+                }
+                #[cfg(any(feature = "aio", feature = "cluster"))]
+                crate::client::Cache::Manager(cache_manager) => cache_manager.clone(),
+            })
+        }
+        None => None,
+    };
+
+    let timeout = config.connection_timeout;
     let start = Instant::now();
     let con: ActualConnection = ActualConnection::new(&connection_info.addr, timeout)?;
 
@@ -1114,8 +1153,8 @@ pub fn connect(
     let con = setup_connection(
         con,
         &connection_info.redis,
-        #[cfg(feature = "cache-aio")]
-        None,
+        #[cfg(feature = "cache")]
+        cache_manager,
     )?;
 
     // remove the temporary timeout.
@@ -1129,14 +1168,14 @@ pub(crate) struct ConnectionSetupComponents {
     resp3_auth_cmd_idx: Option<usize>,
     resp2_auth_cmd_idx: Option<usize>,
     select_cmd_idx: Option<usize>,
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     cache_cmd_idx: Option<usize>,
 }
 
 pub(crate) fn connection_setup_pipeline(
     connection_info: &RedisConnectionInfo,
     check_username: bool,
-    #[cfg(feature = "cache-aio")] cache_config: Option<crate::caching::CacheConfig>,
+    #[cfg(feature = "cache")] cache_config: Option<&crate::caching::CacheConfig>,
 ) -> (crate::Pipeline, ConnectionSetupComponents) {
     let mut last_cmd_index = 0;
 
@@ -1155,7 +1194,7 @@ pub(crate) fn connection_setup_pipeline(
         authenticate_with_resp3_cmd_index.is_none() && connection_info.password.is_some(),
     );
     let select_db_cmd_index = get_next_command_index(connection_info.db != 0);
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     let cache_cmd_index = get_next_command_index(
         connection_info.protocol != ProtocolVersion::RESP2 && cache_config.is_some(),
     );
@@ -1193,16 +1232,24 @@ pub(crate) fn connection_setup_pipeline(
         .arg(env!("CARGO_PKG_VERSION"))
         .ignore();
 
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     if cache_cmd_index.is_some() {
         let cache_config = cache_config.expect(
             "It's expected to have cache_config if cache_cmd_index is Some, please create an issue about this.",
         );
         pipeline.cmd("CLIENT").arg("TRACKING").arg("ON");
-        match cache_config.mode {
-            crate::caching::CacheMode::All => {}
-            crate::caching::CacheMode::OptIn => {
-                pipeline.arg("OPTIN");
+        if let Some(prefixes) = &cache_config.broadcast_prefixes {
+            // OPTIN can't be used together with BCAST, opt-in is handled only on client side.
+            pipeline.arg("BCAST");
+            for prefix in prefixes {
+                pipeline.arg("PREFIX").arg(prefix.as_slice());
+            }
+        } else {
+            match cache_config.mode {
+                crate::caching::CacheMode::All => {}
+                crate::caching::CacheMode::OptIn => {
+                    pipeline.arg("OPTIN");
+                }
             }
         }
     }
@@ -1213,7 +1260,7 @@ pub(crate) fn connection_setup_pipeline(
             resp3_auth_cmd_idx: authenticate_with_resp3_cmd_index,
             resp2_auth_cmd_idx: authenticate_with_resp2_cmd_index,
             select_cmd_idx: select_db_cmd_index,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_cmd_idx: cache_cmd_index,
         },
     )
@@ -1281,7 +1328,7 @@ fn check_db_select(value: &Value) -> RedisResult<()> {
     }
 }
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 fn check_caching(result: &Value) -> RedisResult<()> {
     match result {
         Value::Okay => Ok(()),
@@ -1299,7 +1346,7 @@ pub(crate) fn check_connection_setup(
         resp3_auth_cmd_idx,
         resp2_auth_cmd_idx,
         select_cmd_idx,
-        #[cfg(feature = "cache-aio")]
+        #[cfg(feature = "cache")]
         cache_cmd_idx,
     }: ConnectionSetupComponents,
 ) -> RedisResult<AuthResult> {
@@ -1327,7 +1374,7 @@ pub(crate) fn check_connection_setup(
         check_db_select(value)?;
     }
 
-    #[cfg(feature = "cache-aio")]
+    #[cfg(feature = "cache")]
     if let Some(index) = cache_cmd_idx {
         let Some(value) = results.get(index) else {
             return Err((ErrorKind::ClientError, "Missing Caching response").into());
@@ -1353,7 +1400,7 @@ fn execute_connection_pipeline(
 fn setup_connection(
     con: ActualConnection,
     connection_info: &RedisConnectionInfo,
-    #[cfg(feature = "cache-aio")] cache_config: Option<crate::caching::CacheConfig>,
+    #[cfg(feature = "cache")] cache_manager: Option<CacheManager>,
 ) -> RedisResult<Connection> {
     let mut rv = Connection {
         con,
@@ -1363,14 +1410,18 @@ fn setup_connection(
         protocol: connection_info.protocol,
         push_sender: None,
         messages_to_skip: 0,
+        #[cfg(feature = "cache")]
+        cache_manager: None,
     };
+    #[cfg(feature = "cache")]
+    let cache_config = cache_manager.as_ref().map(|cm| cm.cache_config.as_ref());
 
     if execute_connection_pipeline(
         &mut rv,
         connection_setup_pipeline(
             connection_info,
             true,
-            #[cfg(feature = "cache-aio")]
+            #[cfg(feature = "cache")]
             cache_config,
         ),
     )? == AuthResult::ShouldRetryWithoutUsername
@@ -1380,12 +1431,18 @@ fn setup_connection(
             connection_setup_pipeline(
                 connection_info,
                 false,
-                #[cfg(feature = "cache-aio")]
+                #[cfg(feature = "cache")]
                 cache_config,
             ),
         )?;
     }
 
+    // Cache manager is set after setup, so responses of the setup pipeline aren't processed by it.
+    #[cfg(feature = "cache")]
+    {
+        rv.cache_manager = cache_manager;
+    }
+
     Ok(rv)
 }
 
@@ -1426,6 +1483,19 @@ pub trait ConnectionLike {
         self.req_packed_command(&pcmd)
     }
 
+    /// Sends a [Pipeline] into the TCP socket and reads `count` responses from it,
+    /// after skipping `offset` responses.
+    /// Important - this function is meant for internal usage, same as `req_packed_commands`.
+    #[doc(hidden)]
+    fn req_pipeline(
+        &mut self,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.req_packed_commands(&pipeline.get_packed_pipeline(), offset, count)
+    }
+
     /// Returns the database this connection is bound to.  Note that this
     /// information might be unreliable because it's initially cached and
     /// also might be incorrect if the connection like object is not
@@ -1610,6 +1680,10 @@ impl Connection {
 
     fn try_send(&self, value: &RedisResult<Value>) {
         if let Ok(Value::Push { kind, data }) = value {
+            #[cfg(feature = "cache")]
+            if let Some(cache_manager) = &self.cache_manager {
+                cache_manager.handle_push_value(kind, data);
+            }
             self.send_push(PushInfo {
                 kind: kind.clone(),
                 data: data.clone(),
@@ -1702,6 +1776,60 @@ impl Connection {
         self.push_sender = Some(sender);
     }
 
+    /// Gets [`CacheStatistics`] for current connection if caching is enabled.
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn get_cache_statistics(&self) -> Option<CacheStatistics> {
+        self.cache_manager.as_ref().map(|cm| cm.statistics())
+    }
+
+    /// Removes given keys from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_keys<K: ToRedisArgs>(&self, keys: K) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.invalidate_keys(&keys.to_redis_args());
+        }
+    }
+
+    /// Removes keys matching the glob-style pattern from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn invalidate_cache_pattern(&self, pattern: impl AsRef<[u8]>) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.invalidate_pattern(pattern.as_ref());
+        }
+    }
+
+    /// Removes every value from the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn flush_cache(&self) {
+        if let Some(cache_manager) = &self.cache_manager {
+            cache_manager.flush();
+        }
+    }
+
+    /// Reads given keys with `GET` and stores their values in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn prefetch_cache_keys<K: ToRedisArgs>(&mut self, keys: K) -> RedisResult<()> {
+        let commands = crate::caching::cmd::prefetch_key_commands(keys.to_redis_args());
+        self.prefetch_cache_commands(&commands)
+    }
+
+    /// Sends given commands and stores their replies in the client side cache, see [managing the cache](crate::caching#managing-the-cache).
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
+    pub fn prefetch_cache_commands(&mut self, commands: &[Cmd]) -> RedisResult<()> {
+        if self.cache_manager.is_none() || commands.is_empty() {
+            return Ok(());
+        }
+        let pipeline = crate::caching::cmd::prefetch_pipeline(commands);
+        let _: Vec<Value> = pipeline.query(self)?;
+        Ok(())
+    }
+
     fn send_bytes(&mut self, bytes: &[u8]) -> RedisResult<Value> {
         let result = self.con.send_bytes(bytes);
         if self.protocol != ProtocolVersion::RESP2 {
@@ -1718,11 +1846,29 @@ impl Connection {
 impl ConnectionLike for Connection {
     /// Sends a [Cmd] into the TCP socket and reads a single response from it.
     fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
-        let pcmd = cmd.get_packed_command();
         if self.pubsub {
             self.exit_pubsub()?;
         }
 
+        #[cfg(feature = "cache")]
+        if let Some(cache_manager) = self.cache_manager.clone() {
+            match cache_manager.get_cached_cmd(cmd) {
+                PrepareCacheResult::Cached(value) => return Ok(value),
+                PrepareCacheResult::NotCached(cacheable_command) => {
+                    let mut pipeline = crate::Pipeline::new();
+                    cacheable_command.pack_command(&cache_manager, &mut pipeline);
+
+                    let replies = self.req_packed_commands(
+                        &pipeline.get_packed_pipeline(),
+                        0,
+                        pipeline.commands.len(),
+                    )?;
+                    return cacheable_command.resolve(&cache_manager, replies.into_iter());
+                }
+                _ => (),
+            }
+        }
+        let pcmd = cmd.get_packed_command();
         self.send_bytes(&pcmd)?;
         if cmd.is_no_response() {
             return Ok(Value::Nil);
@@ -1809,6 +1955,29 @@ impl ConnectionLike for Connection {
         first_err.map_or(Ok(rv), Err)
     }
 
+    fn req_pipeline(
+        &mut self,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        #[cfg(feature = "cache")]
+        if let Some(cache_manager) = self.cache_manager.clone() {
+            let (
+                cacheable_pipeline,
+                packed_pipeline,
+                (skipped_response_count, expected_response_count),
+            ) = cache_manager.get_cached_pipeline(pipeline);
+            let replies = self.req_packed_commands(
+                &packed_pipeline.get_packed_pipeline(),
+                skipped_response_count,
+                expected_response_count,
+            )?;
+            return cacheable_pipeline.resolve(&cache_manager, Value::Array(replies));
+        }
+        self.req_packed_commands(&pipeline.get_packed_pipeline(), offset, count)
+    }
+
     fn get_db(&self) -> i64 {
         self.db
     }
@@ -1844,6 +2013,15 @@ where
         self.deref_mut().req_command(cmd)
     }
 
+    fn req_pipeline(
+        &mut self,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        self.deref_mut().req_pipeline(pipeline, offset, count)
+    }
+
     fn get_db(&self) -> i64 {
         self.deref().get_db()
     }
@@ -0,0 +1,50 @@
+use crate::aio::MultiplexedConnection;
+use crate::types::closed_connection_error;
+use crate::{Client, Cmd, ErrorKind, RedisError};
+use deadpool::managed::{Manager, Metrics, RecycleError, RecycleResult};
+
+#[cfg(feature = "cluster-async")]
+use crate::{cluster::ClusterClient, cluster_async::ClusterConnection};
+
+macro_rules! impl_deadpool_manager {
+    ($client:ty, $connection:ty, $get_conn:expr) => {
+        impl Manager for $client {
+            type Type = $connection;
+            type Error = RedisError;
+
+            async fn create(&self) -> Result<Self::Type, Self::Error> {
+                $get_conn(self).await
+            }
+
+            async fn recycle(
+                &self,
+                conn: &mut Self::Type,
+                _: &Metrics,
+            ) -> RecycleResult<Self::Error> {
+                if conn.is_closed() {
+                    return Err(RecycleError::Backend(closed_connection_error()));
+                }
+                let pong: String = Cmd::ping().query_async(conn).await?;
+                match pong.as_str() {
+                    "PONG" => Ok(()),
+                    _ => Err(RecycleError::Backend(
+                        (ErrorKind::ResponseError, "ping request").into(),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_deadpool_manager!(
+    Client,
+    MultiplexedConnection,
+    Client::get_multiplexed_async_connection
+);
+
+#[cfg(feature = "cluster-async")]
+impl_deadpool_manager!(
+    ClusterClient,
+    ClusterConnection,
+    ClusterClient::get_async_connection
+);
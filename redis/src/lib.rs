@@ -84,6 +84,21 @@
 //! cloneable and can be used safely from multiple threads, so a single connection can be easily
 //! reused. For automatic reconnections consider using `ConnectionManager` with the `connection-manager` feature.
 //! Async cluster connections also don't require pooling and are thread-safe and reusable.
+//! If a pool is still needed, e.g. to spread load over multiple connections, the `bb8` and `deadpool` features
+//! implement their managers for `Client` and `ClusterClient`.
+//!
+//! ```rust,no_run
+//! # #[cfg(all(feature = "deadpool", feature = "tokio-comp"))]
+//! # async fn do_something() {
+//! use redis::AsyncCommands;
+//!
+//! let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+//! let pool = deadpool::managed::Pool::<redis::Client>::builder(client).build().unwrap();
+//! let mut conn = pool.get().await.unwrap();
+//!
+//! let _: () = conn.set("KEY", "VALUE").await.unwrap();
+//! # }
+//! ```
 //!
 //! ## Optional Features
 //!
@@ -98,6 +113,7 @@
 //! * `script`: enables script support (enabled by default)
 //! * `streams`: enables high-level interface for interaction with Redis streams (enabled by default)
 //! * `r2d2`: enables r2d2 connection pool support (optional)
+//! * `bb8`, `deadpool`: enable async connection pool support, together with an async runtime feature (optional)
 //! * `ahash`: enables ahash map/set support & uses ahash internally (+7-10% performance) (optional)
 //! * `cluster`: enables redis cluster support (optional)
 //! * `cluster-async`: enables async redis cluster support (optional)
@@ -108,6 +124,7 @@
 //! * `uuid`: enables type conversion to UUID (optional)
 //! * `sentinel`: enables high-level interfaces for communication with Redis sentinels (optional)
 //! * `json`: enables high-level interfaces for communication with the JSON module (optional)
+//! * `cache`: enables **experimental** client side caching for Connection and ClusterConnection (optional)
 //! * `cache-aio`: enables **experimental** client side caching for MultiplexedConnection, ConnectionManager and async ClusterConnection (optional)
 //! * `disable-client-setinfo`: disables the `CLIENT SETINFO` handshake during connection initialization
 //!
@@ -573,7 +590,8 @@ let primary = sentinel.get_async_connection().await.unwrap();
 #[cfg(feature = "aio")]
 pub use crate::client::AsyncConnectionConfig;
 pub use crate::client::Client;
-#[cfg(feature = "cache-aio")]
+pub use crate::client::ConnectionConfig;
+#[cfg(feature = "cache")]
 pub use crate::cmd::CommandCacheConfig;
 pub use crate::cmd::{cmd, pack_command, pipe, Arg, Cmd, Iter};
 pub use crate::commands::{
@@ -670,6 +688,15 @@ mod subscription_tracker;
 #[cfg(feature = "cluster")]
 mod cluster_topology;
 
+#[cfg(feature = "cluster")]
+mod cluster_scan;
+
+#[cfg(feature = "cluster")]
+mod cluster_read_strategy;
+
+#[cfg(feature = "cluster")]
+mod cluster_node_health;
+
 #[cfg(feature = "cluster")]
 #[cfg_attr(docsrs, doc(cfg(feature = "cluster")))]
 pub mod cluster;
@@ -695,6 +722,10 @@ mod r2d2;
 #[cfg_attr(docsrs, doc(cfg(all(feature = "bb8", feature = "aio"))))]
 mod bb8;
 
+#[cfg(all(feature = "deadpool", feature = "aio"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "deadpool", feature = "aio"))))]
+mod deadpool;
+
 #[cfg(feature = "streams")]
 #[cfg_attr(docsrs, doc(cfg(feature = "streams")))]
 pub mod streams;
@@ -707,6 +738,11 @@ pub mod cluster_async;
 #[cfg_attr(docsrs, doc(cfg(feature = "sentinel")))]
 pub mod sentinel;
 
+#[cfg(feature = "sentinel")]
+mod sentinel_replica_selector;
+#[cfg(feature = "sentinel")]
+mod sentinel_types;
+
 #[cfg(feature = "tls-rustls")]
 mod tls;
 
@@ -714,8 +750,8 @@ mod tls;
 #[cfg_attr(docsrs, doc(cfg(feature = "tls-rustls")))]
 pub use crate::tls::{ClientTlsConfig, TlsCertificates};
 
-#[cfg(feature = "cache-aio")]
-#[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+#[cfg(feature = "cache")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
 pub mod caching;
 
 mod client;
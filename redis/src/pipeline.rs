@@ -1,6 +1,6 @@
 #![macro_use]
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use crate::cmd::CommandCacheConfig;
 use crate::cmd::{cmd, cmd_len, Cmd};
 use crate::connection::ConnectionLike;
@@ -72,7 +72,7 @@ impl Pipeline {
         self
     }
 
-    #[cfg(feature = "aio")]
+    #[cfg(any(feature = "aio", feature = "cluster"))]
     pub(crate) fn is_transaction(&self) -> bool {
         self.transaction_mode
     }
@@ -95,19 +95,11 @@ impl Pipeline {
     }
 
     fn execute_pipelined(&self, con: &mut dyn ConnectionLike) -> RedisResult<Value> {
-        self.make_pipeline_results(con.req_packed_commands(
-            &encode_pipeline(&self.commands, false),
-            0,
-            self.commands.len(),
-        )?)
+        self.make_pipeline_results(con.req_pipeline(self, 0, self.commands.len())?)
     }
 
-    fn execute_transaction(&self, con: &mut dyn ConnectionLike) -> RedisResult<Value> {
-        let mut resp = con.req_packed_commands(
-            &encode_pipeline(&self.commands, true),
-            self.commands.len() + 1,
-            1,
-        )?;
+    pub(crate) fn execute_transaction(&self, con: &mut dyn ConnectionLike) -> RedisResult<Value> {
+        let mut resp = con.req_pipeline(self, self.commands.len() + 1, 1)?;
 
         match resp.pop() {
             Some(Value::Nil) => Ok(Value::Nil),
@@ -364,8 +356,8 @@ implement_pipeline_commands!(Pipeline);
 // Defines caching related functions for Pipeline, ClusterPipeline isn't supported yet.
 impl Pipeline {
     /// Changes caching behaviour for latest command in the pipeline.
-    #[cfg(feature = "cache-aio")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "cache-aio")))]
+    #[cfg(feature = "cache")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cache")))]
     pub fn set_cache_config(&mut self, command_cache_config: CommandCacheConfig) -> &mut Self {
         let cmd = self.get_last_command();
         cmd.set_cache_config(command_cache_config);
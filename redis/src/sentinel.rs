@@ -124,20 +124,44 @@
 //! let client = builder.build().unwrap();
 //! ```
 //!
+//! The sentinels given at construction don't need to be all the sentinels of a deployment: a `Sentinel` can
+//! learn the other sentinels monitoring a master with [Sentinel::discover_sentinels], and refresh its list
+//! periodically with [Sentinel::set_discovery_interval], so that it keeps working when sentinels are replaced.
+//!
+//! # Example
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use redis::sentinel::Sentinel;
+//!
+//! let mut sentinel = Sentinel::build(vec!["redis://127.0.0.1:26379/"]).unwrap();
+//! sentinel.discover_sentinels("master_name").unwrap();
+//! sentinel.set_discovery_interval(Duration::from_secs(60));
+//! let master = sentinel.master_for("master_name", None).unwrap();
+//! ```
+//!
 
 #[cfg(feature = "aio")]
 use crate::aio::MultiplexedConnection as AsyncConnection;
 #[cfg(feature = "aio")]
-use futures_util::StreamExt;
+use futures_util::{FutureExt, StreamExt};
 use rand::Rng;
-#[cfg(feature = "r2d2")]
+use std::sync::Arc;
+#[cfg(any(feature = "r2d2", feature = "aio"))]
 use std::sync::Mutex;
-use std::{collections::HashMap, num::NonZeroUsize};
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
 
 #[cfg(feature = "aio")]
 use crate::aio::MultiplexedConnection;
 #[cfg(feature = "aio")]
 use crate::client::AsyncConnectionConfig;
+pub use crate::sentinel_replica_selector::{
+    LowestLagReplica, RandomSentinelReplica, ReplicaSelector,
+};
+pub use crate::sentinel_types::{ReplicaInfo, SentinelInfo, SentinelMasterInfo};
 #[cfg(feature = "tls-rustls")]
 use crate::tls::retrieve_tls_certificates;
 #[cfg(feature = "tls-rustls")]
@@ -147,17 +171,27 @@ use crate::{
     ErrorKind, FromRedisValue, IntoConnectionInfo, ProtocolVersion, RedisConnectionInfo,
     RedisError, Role, TlsMode,
 };
+#[cfg(feature = "aio")]
+use crate::{
+    types::{RedisFuture, ServerErrorKind, Value},
+    Pipeline,
+};
 
 /// The Sentinel type, serves as a special purpose client which builds other clients on
 /// demand.
 pub struct Sentinel {
     sentinels_connection_info: Vec<ConnectionInfo>,
+    /// The sentinels the client was built with, which are kept after discovering other sentinels.
+    seed_sentinels_connection_info: Vec<ConnectionInfo>,
     connections_cache: Vec<Option<Connection>>,
     #[cfg(feature = "aio")]
     async_connections_cache: Vec<Option<AsyncConnection>>,
     replica_start_index: usize,
     #[cfg(feature = "tls-rustls")]
     certs: Option<TlsCertificates>,
+    discovery_interval: Option<Duration>,
+    last_discovery: Option<Instant>,
+    replica_selector: Option<Arc<dyn ReplicaSelector>>,
 }
 
 /// Holds the connection information that a sentinel should use when connecting to the
@@ -228,6 +262,13 @@ fn sentinel_replicas_cmd(master_name: &str) -> crate::Cmd {
     cmd
 }
 
+fn sentinel_sentinels_cmd(master_name: &str) -> crate::Cmd {
+    let mut cmd = crate::cmd("SENTINEL");
+    cmd.arg("SENTINELS");
+    cmd.arg(master_name);
+    cmd
+}
+
 fn is_master_valid(master_info: &HashMap<String, String>, service_name: &str) -> bool {
     master_info.get("name").map(|s| s.as_str()) == Some(service_name)
         && master_info.contains_key("ip")
@@ -247,6 +288,44 @@ fn is_replica_valid(replica_info: &HashMap<String, String>) -> bool {
         && replica_info["port"].parse::<u16>().is_ok()
 }
 
+fn is_sentinel_valid(sentinel_info: &HashMap<String, String>) -> bool {
+    sentinel_info.contains_key("ip")
+        && sentinel_info.contains_key("port")
+        && sentinel_info.get("flags").is_some_and(|flags| {
+            !flags.contains("s_down")
+                && !flags.contains("o_down")
+                && !flags.contains("disconnected")
+        })
+        && sentinel_info["port"].parse::<u16>().is_ok()
+}
+
+/// Creates the connection info of the peers reported by a sentinel, which are connected to with the same TLS
+/// settings and credentials as the sentinel which reported them.
+fn peer_sentinels_connection_info(
+    reporter: &ConnectionInfo,
+    peers: Vec<HashMap<String, String>>,
+) -> impl Iterator<Item = ConnectionInfo> + '_ {
+    valid_addrs(peers, is_sentinel_valid).map(|(ip, port)| {
+        let addr = match &reporter.addr {
+            ConnectionAddr::TcpTls {
+                insecure,
+                tls_params,
+                ..
+            } => ConnectionAddr::TcpTls {
+                host: ip,
+                port,
+                insecure: *insecure,
+                tls_params: tls_params.clone(),
+            },
+            _ => ConnectionAddr::Tcp(ip, port),
+        };
+        ConnectionInfo {
+            addr,
+            redis: reporter.redis.clone(),
+        }
+    })
+}
+
 /// Generates a random value in the 0..max range.
 fn random_replica_index(max: NonZeroUsize) -> usize {
     rand::rng().random_range(0..max.into())
@@ -670,11 +749,15 @@ impl Sentinel {
             async_connections_cache.resize_with(sentinels_connection_info.len(), Default::default);
 
             Ok(Sentinel {
+                seed_sentinels_connection_info: sentinels_connection_info.clone(),
                 sentinels_connection_info,
                 connections_cache,
                 async_connections_cache,
                 replica_start_index: random_replica_index(NonZeroUsize::new(1000000).unwrap()),
                 certs,
+                discovery_interval: None,
+                last_discovery: None,
+                replica_selector: None,
             })
         }
 
@@ -684,33 +767,219 @@ impl Sentinel {
             async_connections_cache.resize_with(sentinels_connection_info.len(), Default::default);
 
             Ok(Sentinel {
+                seed_sentinels_connection_info: sentinels_connection_info.clone(),
                 sentinels_connection_info,
                 connections_cache,
                 async_connections_cache,
                 replica_start_index: random_replica_index(NonZeroUsize::new(1000000).unwrap()),
+                discovery_interval: None,
+                last_discovery: None,
+                replica_selector: None,
             })
         }
 
         #[cfg(all(not(feature = "aio"), feature = "tls-rustls"))]
         {
             Ok(Sentinel {
+                seed_sentinels_connection_info: sentinels_connection_info.clone(),
                 sentinels_connection_info,
                 connections_cache,
                 replica_start_index: random_replica_index(NonZeroUsize::new(1000000).unwrap()),
                 certs,
+                discovery_interval: None,
+                last_discovery: None,
+                replica_selector: None,
             })
         }
 
         #[cfg(all(not(feature = "aio"), not(feature = "tls-rustls")))]
         {
             Ok(Sentinel {
+                seed_sentinels_connection_info: sentinels_connection_info.clone(),
                 sentinels_connection_info,
                 connections_cache,
                 replica_start_index: random_replica_index(NonZeroUsize::new(1000000).unwrap()),
+                discovery_interval: None,
+                last_discovery: None,
+                replica_selector: None,
             })
         }
     }
 
+    /// Returns the connection info of the sentinels which are queried, in the order they are tried.
+    pub fn sentinels_connection_info(&self) -> &[ConnectionInfo] {
+        &self.sentinels_connection_info
+    }
+
+    /// Makes the sentinel discover its peers at most once every `interval`, when it's asked for the address of
+    /// a master or replica. See [Sentinel::discover_sentinels].
+    pub fn set_discovery_interval(&mut self, interval: Duration) {
+        self.discovery_interval = Some(interval);
+    }
+
+    /// Sets the policy which chooses the replica that [Sentinel::replica_for] and [Sentinel::async_replica_for]
+    /// connect to, instead of a random replica.
+    pub fn set_replica_selector(&mut self, selector: impl ReplicaSelector) {
+        self.replica_selector = Some(Arc::new(selector));
+    }
+
+    #[cfg(not(feature = "tls-rustls"))]
+    fn replica_connection_info(
+        &self,
+        replica: &ReplicaInfo,
+        node_connection_info: &SentinelNodeConnectionInfo,
+    ) -> RedisResult<ConnectionInfo> {
+        node_connection_info.create_connection_info(replica.ip().to_string(), replica.port())
+    }
+
+    #[cfg(feature = "tls-rustls")]
+    fn replica_connection_info(
+        &self,
+        replica: &ReplicaInfo,
+        node_connection_info: &SentinelNodeConnectionInfo,
+    ) -> RedisResult<ConnectionInfo> {
+        node_connection_info.create_connection_info(
+            replica.ip().to_string(),
+            replica.port(),
+            &self.certs,
+        )
+    }
+
+    /// Returns the order in which the replicas chosen by the selector are tried. When rotating, the order starts
+    /// at the next replica on every call.
+    fn replica_order(
+        &mut self,
+        selector: &dyn ReplicaSelector,
+        replicas: &[ReplicaInfo],
+        rotate: bool,
+    ) -> Vec<usize> {
+        let mut order = selector.select_replicas(replicas);
+        if rotate && !order.is_empty() {
+            self.replica_start_index = (self.replica_start_index + 1) % order.len();
+            order.rotate_left(self.replica_start_index);
+        }
+        order
+    }
+
+    /// Connects to the first replica in the order of the selector which is still a replica.
+    fn select_replica(
+        &mut self,
+        selector: &dyn ReplicaSelector,
+        service_name: &str,
+        node_connection_info: &SentinelNodeConnectionInfo,
+        rotate: bool,
+    ) -> RedisResult<Client> {
+        let replicas = self.get_sentinel_replicas(service_name)?;
+        let replicas: Vec<ReplicaInfo> = replicas
+            .into_iter()
+            .filter(is_replica_valid)
+            .filter_map(ReplicaInfo::from_fields)
+            .collect();
+        for index in self.replica_order(selector, &replicas, rotate) {
+            let Some(replica) = replicas.get(index) else {
+                continue;
+            };
+            let connection_info = self.replica_connection_info(replica, node_connection_info)?;
+            if get_node_role(&connection_info)
+                .is_ok_and(|role| matches!(role, Role::Replica { .. }))
+            {
+                return Client::open(connection_info);
+            }
+        }
+        fail!((
+            ErrorKind::NoValidReplicasFoundBySentinel,
+            "No valid replica found in sentinel for given name",
+        ))
+    }
+
+    fn is_discovery_due(&mut self) -> bool {
+        let Some(interval) = self.discovery_interval else {
+            return false;
+        };
+        if self
+            .last_discovery
+            .is_some_and(|last_discovery| last_discovery.elapsed() < interval)
+        {
+            return false;
+        }
+        self.last_discovery = Some(Instant::now());
+        true
+    }
+
+    /// Replaces the list of sentinels with the sentinel at `reporter` and the peers it reported, followed by the
+    /// sentinels the client was built with, keeping the cached connections to the sentinels which remain in the
+    /// list.
+    fn replace_sentinels(&mut self, reporter: usize, peers: Vec<HashMap<String, String>>) {
+        let reporter = self.sentinels_connection_info[reporter].clone();
+        let mut sentinels_connection_info = vec![];
+        for connection_info in std::iter::once(reporter.clone())
+            .chain(peer_sentinels_connection_info(&reporter, peers))
+            .chain(self.seed_sentinels_connection_info.iter().cloned())
+        {
+            if !sentinels_connection_info
+                .iter()
+                .any(|known: &ConnectionInfo| known.addr == connection_info.addr)
+            {
+                sentinels_connection_info.push(connection_info);
+            }
+        }
+
+        let previous_index = |connection_info: &ConnectionInfo| {
+            self.sentinels_connection_info
+                .iter()
+                .position(|previous| previous.addr == connection_info.addr)
+        };
+        let indices: Vec<Option<usize>> = sentinels_connection_info
+            .iter()
+            .map(previous_index)
+            .collect();
+        self.connections_cache = indices
+            .iter()
+            .map(|index| index.and_then(|index| self.connections_cache[index].take()))
+            .collect();
+        #[cfg(feature = "aio")]
+        {
+            self.async_connections_cache = indices
+                .iter()
+                .map(|index| index.and_then(|index| self.async_connections_cache[index].take()))
+                .collect();
+        }
+        self.sentinels_connection_info = sentinels_connection_info;
+    }
+
+    /// Asks the sentinels for the other sentinels monitoring the given master (using the command
+    /// SENTINEL SENTINELS), and replaces the list of sentinels with the first sentinel which answers and its
+    /// peers, followed by the sentinels it was built with. This keeps the sentinel working when the sentinels it
+    /// was built with are replaced by sentinels with new addresses.
+    pub fn discover_sentinels(&mut self, service_name: &str) -> RedisResult<()> {
+        let mut last_err = None;
+        for index in 0..self.sentinels_connection_info.len() {
+            match try_single_sentinel(
+                sentinel_sentinels_cmd(service_name),
+                &self.sentinels_connection_info[index],
+                &mut self.connections_cache[index],
+            ) {
+                Ok(peers) => {
+                    self.replace_sentinels(index, peers);
+                    return Ok(());
+                }
+                Err(err) => {
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        // We can unwrap here because we know there is at least one connection info.
+        Err(last_err.expect("There should be at least one connection info"))
+    }
+
+    fn discover_sentinels_if_due(&mut self, service_name: &str) {
+        if self.is_discovery_due() {
+            // Discovery failures are ignored, since the current sentinels are still used.
+            let _ = self.discover_sentinels(service_name);
+        }
+    }
+
     /// Try to execute the given command in each sentinel, returning the result of the
     /// first one that executes without errors. If all return errors, we return the
     /// error of the last attempt.
@@ -802,17 +1071,28 @@ impl Sentinel {
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.discover_sentinels_if_due(service_name);
         let connection_info =
             self.find_master_address(service_name, node_connection_info.unwrap_or_default())?;
         Client::open(connection_info)
     }
 
-    /// Connects to a randomly chosen replica of the given master name.
+    /// Connects to a replica of the given master name, chosen by the replica selector of the sentinel, or
+    /// randomly if none was set.
     pub fn replica_for(
         &mut self,
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.discover_sentinels_if_due(service_name);
+        if let Some(selector) = self.replica_selector.clone() {
+            return self.select_replica(
+                selector.as_ref(),
+                service_name,
+                node_connection_info.unwrap_or_default(),
+                false,
+            );
+        }
         let addresses = self
             .find_valid_replica_addresses(service_name, node_connection_info.unwrap_or_default())?;
         let start_index = NonZeroUsize::new(addresses.len()).map(random_replica_index);
@@ -823,11 +1103,21 @@ impl Sentinel {
     /// There is no guarantee that we'll actually be connecting to a different replica
     /// in the next call, but in a static set of replicas (no replicas added or
     /// removed), on average we'll choose each replica the same number of times.
+    /// If a replica selector is set, only the replicas it chooses are rotated through.
     pub fn replica_rotate_for(
         &mut self,
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.discover_sentinels_if_due(service_name);
+        if let Some(selector) = self.replica_selector.clone() {
+            return self.select_replica(
+                selector.as_ref(),
+                service_name,
+                node_connection_info.unwrap_or_default(),
+                true,
+            );
+        }
         let addresses = self
             .find_valid_replica_addresses(service_name, node_connection_info.unwrap_or_default())?;
         if !addresses.is_empty() {
@@ -862,6 +1152,69 @@ impl Sentinel {
         Err(last_err.expect("There should be at least one connection info"))
     }
 
+    /// The async version of [Sentinel::discover_sentinels].
+    pub async fn async_discover_sentinels(&mut self, service_name: &str) -> RedisResult<()> {
+        let mut last_err = None;
+        for index in 0..self.sentinels_connection_info.len() {
+            match async_try_single_sentinel(
+                sentinel_sentinels_cmd(service_name),
+                &self.sentinels_connection_info[index],
+                &mut self.async_connections_cache[index],
+            )
+            .await
+            {
+                Ok(peers) => {
+                    self.replace_sentinels(index, peers);
+                    return Ok(());
+                }
+                Err(err) => {
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        // We can unwrap here because we know there is at least one connection info.
+        Err(last_err.expect("There should be at least one connection info"))
+    }
+
+    async fn async_select_replica(
+        &mut self,
+        selector: &dyn ReplicaSelector,
+        service_name: &str,
+        node_connection_info: &SentinelNodeConnectionInfo,
+        rotate: bool,
+    ) -> RedisResult<Client> {
+        let replicas = self.async_get_sentinel_replicas(service_name).await?;
+        let replicas: Vec<ReplicaInfo> = replicas
+            .into_iter()
+            .filter(is_replica_valid)
+            .filter_map(ReplicaInfo::from_fields)
+            .collect();
+        for index in self.replica_order(selector, &replicas, rotate) {
+            let Some(replica) = replicas.get(index) else {
+                continue;
+            };
+            let connection_info = self.replica_connection_info(replica, node_connection_info)?;
+            if async_determine_slave_from_role_or_info_replication(&connection_info)
+                .await
+                .unwrap_or(false)
+            {
+                return Client::open(connection_info);
+            }
+        }
+        fail!((
+            ErrorKind::NoValidReplicasFoundBySentinel,
+            "No valid replica found in sentinel for given name",
+        ))
+    }
+
+    async fn async_discover_sentinels_if_due(&mut self, service_name: &str) {
+        if self.is_discovery_due() {
+            // Discovery failures are ignored, since the current sentinels are still used.
+            let _ = self.async_discover_sentinels(service_name).await;
+        }
+    }
+
     async fn async_get_sentinel_masters(&mut self) -> RedisResult<Vec<HashMap<String, String>>> {
         self.async_try_all_sentinels(sentinel_masters_cmd()).await
     }
@@ -921,18 +1274,31 @@ impl Sentinel {
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.async_discover_sentinels_if_due(service_name).await;
         let address = self
             .async_find_master_address(service_name, node_connection_info.unwrap_or_default())
             .await?;
         Client::open(address)
     }
 
-    /// Connects to a randomly chosen replica of the given master name.
+    /// Connects to a replica of the given master name, chosen by the replica selector of the sentinel, or
+    /// randomly if none was set.
     pub async fn async_replica_for(
         &mut self,
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.async_discover_sentinels_if_due(service_name).await;
+        if let Some(selector) = self.replica_selector.clone() {
+            return self
+                .async_select_replica(
+                    selector.as_ref(),
+                    service_name,
+                    node_connection_info.unwrap_or_default(),
+                    false,
+                )
+                .await;
+        }
         let addresses = self
             .async_find_valid_replica_addresses(
                 service_name,
@@ -947,11 +1313,23 @@ impl Sentinel {
     /// There is no guarantee that we'll actually be connecting to a different replica
     /// in the next call, but in a static set of replicas (no replicas added or
     /// removed), on average we'll choose each replica the same number of times.
+    /// If a replica selector is set, only the replicas it chooses are rotated through.
     pub async fn async_replica_rotate_for(
         &mut self,
         service_name: &str,
         node_connection_info: Option<&SentinelNodeConnectionInfo>,
     ) -> RedisResult<Client> {
+        self.async_discover_sentinels_if_due(service_name).await;
+        if let Some(selector) = self.replica_selector.clone() {
+            return self
+                .async_select_replica(
+                    selector.as_ref(),
+                    service_name,
+                    node_connection_info.unwrap_or_default(),
+                    true,
+                )
+                .await;
+        }
         let addresses = self
             .async_find_valid_replica_addresses(
                 service_name,
@@ -1076,6 +1454,29 @@ impl SentinelClient {
         let client = self.get_client()?;
         client.get_connection()
     }
+
+    #[cfg(feature = "connection-manager")]
+    pub(crate) fn sentinels_connection_info(&self) -> &[ConnectionInfo] {
+        &self.sentinel.sentinels_connection_info
+    }
+
+    #[cfg(feature = "connection-manager")]
+    pub(crate) fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    #[cfg(feature = "connection-manager")]
+    pub(crate) fn server_type(&self) -> &SentinelServerType {
+        &self.server_type
+    }
+
+    #[cfg(feature = "connection-manager")]
+    pub(crate) fn redis_connection_info(&self) -> RedisConnectionInfo {
+        self.node_connection_info
+            .redis_connection_info
+            .clone()
+            .unwrap_or_default()
+    }
 }
 
 /// To enable async support you need to chose one of the supported runtimes and active its
@@ -1118,6 +1519,187 @@ impl SentinelClient {
             .get_multiplexed_async_connection_with_config(config)
             .await
     }
+
+    /// Turns the client into a [SentinelMultiplexedConnection], a cloneable connection which asks the sentinels
+    /// for the address of the server again whenever the connection fails.
+    ///
+    /// ```rust,no_run
+    /// # async fn run() -> redis::RedisResult<()> {
+    /// use redis::sentinel::{SentinelClientBuilder, SentinelServerType};
+    /// use redis::{AsyncConnectionConfig, ConnectionAddr};
+    ///
+    /// let nodes = vec![ConnectionAddr::Tcp(String::from("127.0.0.1"), 26379)];
+    /// let client = SentinelClientBuilder::new(nodes, String::from("master1"), SentinelServerType::Master)?
+    ///     .build()?;
+    /// let mut con = client
+    ///     .into_multiplexed_connection(AsyncConnectionConfig::new())
+    ///     .await?;
+    /// let mut other_con = con.clone();
+    /// redis::cmd("SET").arg("key").arg("value").exec_async(&mut con).await?;
+    /// let value: String = redis::cmd("GET").arg("key").query_async(&mut other_con).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn into_multiplexed_connection(
+        mut self,
+        config: AsyncConnectionConfig,
+    ) -> RedisResult<SentinelMultiplexedConnection> {
+        let connection = self.get_async_connection_with_config(&config).await?;
+        let db = self
+            .node_connection_info
+            .redis_connection_info
+            .as_ref()
+            .map_or(0, |info| info.db);
+        let follows_master = matches!(self.server_type, SentinelServerType::Master);
+        Ok(SentinelMultiplexedConnection(Arc::new(
+            SentinelMultiplexedConnectionInner {
+                client: tokio::sync::Mutex::new(self),
+                config,
+                current: Mutex::new(CurrentConnection {
+                    generation: 0,
+                    connection,
+                }),
+                follows_master,
+                db,
+            },
+        )))
+    }
+}
+
+/// An async connection to the server of a [SentinelClient], which can be cloned and shared like a
+/// [MultiplexedConnection].
+///
+/// When a request fails because the connection to the server was lost, the error is returned and the
+/// sentinels are asked for the current address of the server, so that the following requests are sent to the
+/// server which replaced it. When a connection to a master receives a `READONLY` error, the master was demoted
+/// by a failover: the connection re-resolves the master and sends the request again, since the replica didn't
+/// execute it.
+#[cfg(feature = "aio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aio")))]
+#[derive(Clone)]
+pub struct SentinelMultiplexedConnection(Arc<SentinelMultiplexedConnectionInner>);
+
+#[cfg(feature = "aio")]
+struct SentinelMultiplexedConnectionInner {
+    /// Held while reconnecting, so that concurrent failures only cause a single reconnection.
+    client: tokio::sync::Mutex<SentinelClient>,
+    config: AsyncConnectionConfig,
+    current: Mutex<CurrentConnection>,
+    follows_master: bool,
+    db: i64,
+}
+
+#[cfg(feature = "aio")]
+struct CurrentConnection {
+    /// Increased on every reconnection.
+    generation: u64,
+    connection: MultiplexedConnection,
+}
+
+#[cfg(feature = "aio")]
+impl SentinelMultiplexedConnection {
+    fn current(&self) -> (u64, MultiplexedConnection) {
+        let current = self.0.current.lock().unwrap();
+        (current.generation, current.connection.clone())
+    }
+
+    /// Replaces the connection of `generation` with a connection to the server the sentinels currently
+    /// report, unless another request already did.
+    async fn reconnect(&self, generation: u64) -> RedisResult<()> {
+        let mut client = self.0.client.lock().await;
+        if self.0.current.lock().unwrap().generation != generation {
+            return Ok(());
+        }
+        let connection = client
+            .get_async_connection_with_config(&self.0.config)
+            .await?;
+        *self.0.current.lock().unwrap() = CurrentConnection {
+            generation: generation + 1,
+            connection,
+        };
+        Ok(())
+    }
+
+    /// Whether the result shows that the connection is to a master which was demoted to a replica.
+    fn is_demoted_master_result(&self, result: Result<&[Value], &RedisError>) -> bool {
+        self.0.follows_master && is_readonly_result(result)
+    }
+
+    /// Sends an already encoded (packed) command into the TCP socket and
+    /// reads the single response from it.
+    pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let (generation, mut connection) = self.current();
+        let result = connection.send_packed_command(cmd).await;
+        if self.is_demoted_master_result(result.as_ref().map(std::slice::from_ref)) {
+            self.reconnect(generation).await?;
+            return self.current().1.send_packed_command(cmd).await;
+        }
+        if let Err(err) = &result {
+            if err.is_unrecoverable_error() {
+                let _ = self.reconnect(generation).await;
+            }
+        }
+        result
+    }
+
+    /// Sends multiple already encoded (packed) command into the TCP socket
+    /// and reads `count` responses from it.  This is used to implement
+    /// pipelining.
+    pub async fn send_packed_commands(
+        &mut self,
+        cmd: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let (generation, mut connection) = self.current();
+        let result = connection.send_packed_commands(cmd, offset, count).await;
+        if self.is_demoted_master_result(result.as_ref().map(Vec::as_slice)) {
+            self.reconnect(generation).await?;
+            return self
+                .current()
+                .1
+                .send_packed_commands(cmd, offset, count)
+                .await;
+        }
+        if let Err(err) = &result {
+            if err.is_unrecoverable_error() {
+                let _ = self.reconnect(generation).await;
+            }
+        }
+        result
+    }
+}
+
+/// Whether the replies or the error show that a write was sent to a replica, like a master which was demoted
+/// by a failover. Replicas answer writes with READONLY, which is returned as a reply rather than as an error.
+#[cfg(feature = "aio")]
+pub(crate) fn is_readonly_result(result: Result<&[Value], &RedisError>) -> bool {
+    match result {
+        Ok(replies) => replies.iter().any(
+            |reply| matches!(reply, Value::ServerError(err) if err.kind() == Some(ServerErrorKind::ReadOnly)),
+        ),
+        Err(err) => err.kind() == ErrorKind::ReadOnly,
+    }
+}
+
+#[cfg(feature = "aio")]
+impl crate::aio::ConnectionLike for SentinelMultiplexedConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        (async move { self.send_packed_command(cmd).await }).boxed()
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        (async move { self.send_packed_commands(cmd, offset, count).await }).boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.db
+    }
 }
 
 struct BuilderConnectionParams {
@@ -1140,6 +1722,8 @@ pub struct SentinelClientBuilder {
     server_type: SentinelServerType,
     client_to_redis_params: BuilderConnectionParams,
     client_to_sentinel_params: BuilderConnectionParams,
+    sentinel_discovery_interval: Option<Duration>,
+    replica_selector: Option<Arc<dyn ReplicaSelector>>,
 }
 
 impl SentinelClientBuilder {
@@ -1174,6 +1758,8 @@ impl SentinelClientBuilder {
                 #[cfg(feature = "tls-rustls")]
                 certificates: None,
             },
+            sentinel_discovery_interval: None,
+            replica_selector: None,
         })
     }
 
@@ -1289,14 +1875,33 @@ impl SentinelClientBuilder {
             })
             .collect();
 
-        SentinelClient::build_inner(
+        let mut client = SentinelClient::build_inner(
             sentinels,
             self.service_name,
             Some(client_to_redis_connection_info),
             self.server_type,
             #[cfg(feature = "tls-rustls")]
             self.client_to_redis_params.certificates,
-        )
+        )?;
+        if let Some(interval) = self.sentinel_discovery_interval {
+            client.sentinel.set_discovery_interval(interval);
+        }
+        client.sentinel.replica_selector = self.replica_selector;
+        Ok(client)
+    }
+
+    /// Sets the policy which chooses the replica that a client of [SentinelServerType::Replica] connects to.
+    /// See [Sentinel::set_replica_selector].
+    pub fn set_replica_selector(mut self, selector: impl ReplicaSelector) -> SentinelClientBuilder {
+        self.replica_selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Makes the client discover the sentinels monitoring the service at most once every `interval`, and
+    /// replace the configured sentinels with them. See [Sentinel::discover_sentinels].
+    pub fn set_sentinel_discovery_interval(mut self, interval: Duration) -> SentinelClientBuilder {
+        self.sentinel_discovery_interval = Some(interval);
+        self
     }
 
     /// Set tls mode for the connection to redis
@@ -0,0 +1,194 @@
+//! Policies which choose the replica that a [`crate::sentinel::Sentinel`] connects to.
+
+use std::time::Duration;
+
+use rand::{rng, seq::SliceRandom};
+
+use crate::sentinel_types::ReplicaInfo;
+
+/// Chooses the replicas which [`crate::sentinel::Sentinel::replica_for`] and
+/// [`crate::sentinel::Sentinel::replica_rotate_for`] connect to.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use redis::sentinel::{LowestLagReplica, Sentinel};
+///
+/// let mut sentinel = Sentinel::build(vec!["redis://127.0.0.1:26379/"]).unwrap();
+/// sentinel.set_replica_selector(LowestLagReplica::new().set_max_lag(1024 * 1024));
+/// let replica = sentinel.replica_for("master_name", None).unwrap();
+/// ```
+pub trait ReplicaSelector: Send + Sync + 'static {
+    /// Returns the indices of the replicas in `replicas` to connect to, in order of preference.
+    /// Replicas whose index isn't returned are never connected to. `replicas` only contains replicas which the
+    /// sentinel doesn't consider to be down.
+    ///
+    /// Sentinels don't report the replication offset of the master, so the lag of a replica is best measured
+    /// against the highest [ReplicaInfo::replication_offset] of `replicas`.
+    fn select_replicas(&self, replicas: &[ReplicaInfo]) -> Vec<usize>;
+}
+
+/// Tries the replicas in a random order. This is the policy used when no selector is set.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomSentinelReplica;
+
+impl ReplicaSelector for RandomSentinelReplica {
+    fn select_replicas(&self, replicas: &[ReplicaInfo]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..replicas.len()).collect();
+        indices.shuffle(&mut rng());
+        indices
+    }
+}
+
+/// Tries the replicas with the smallest replication lag first, and skips replicas which are disconnected from
+/// their master or lag too far behind. The lag of a replica is how many bytes of the replication stream it is
+/// behind the most up to date replica.
+///
+/// Replicas with the same lag are ordered by their priority, and replicas with a priority of 0, which are often
+/// reserved for other uses, are tried last.
+#[derive(Debug, Default, Clone)]
+pub struct LowestLagReplica {
+    max_lag: Option<u64>,
+    max_master_link_down_time: Option<Duration>,
+}
+
+impl LowestLagReplica {
+    /// Creates a selector which skips the replicas that are disconnected from their master, but not the ones
+    /// which lag behind.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the lag, in bytes of the replication stream, above which replicas are skipped.
+    pub fn set_max_lag(mut self, max_lag: u64) -> Self {
+        self.max_lag = Some(max_lag);
+        self
+    }
+
+    /// Sets how long a replica may be disconnected from its master before it's skipped. By default
+    /// disconnected replicas are always skipped.
+    pub fn set_max_master_link_down_time(mut self, down_time: Duration) -> Self {
+        self.max_master_link_down_time = Some(down_time);
+        self
+    }
+
+    fn is_eligible(&self, replica: &ReplicaInfo, lag: u64) -> bool {
+        let link_ok = replica.is_master_link_up()
+            || self
+                .max_master_link_down_time
+                .zip(replica.master_link_down_time())
+                .is_some_and(|(max, down_time)| down_time <= max);
+        link_ok
+            && !replica.flags().contains("disconnected")
+            && self.max_lag.map_or(true, |max_lag| lag <= max_lag)
+    }
+}
+
+impl ReplicaSelector for LowestLagReplica {
+    fn select_replicas(&self, replicas: &[ReplicaInfo]) -> Vec<usize> {
+        let max_offset = replicas
+            .iter()
+            .filter_map(ReplicaInfo::replication_offset)
+            .max()
+            .unwrap_or_default();
+        // Replicas whose offset isn't reported are treated as lagging the most.
+        let lag = |index: usize| {
+            replicas[index]
+                .replication_offset()
+                .map_or(u64::MAX, |offset| max_offset - offset)
+        };
+        let mut indices: Vec<usize> = (0..replicas.len())
+            .filter(|index| self.is_eligible(&replicas[*index], lag(*index)))
+            .collect();
+        indices.sort_by_key(|index| {
+            // Replicas whose priority isn't reported are tried after the others with the same lag.
+            let priority = replicas[*index].priority();
+            (
+                lag(*index),
+                priority == Some(0),
+                priority.unwrap_or(u32::MAX),
+            )
+        });
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn replicas(replies: Vec<HashMap<String, String>>) -> Vec<ReplicaInfo> {
+        replies
+            .into_iter()
+            .map(|reply| ReplicaInfo::from_fields(reply).unwrap())
+            .collect()
+    }
+
+    fn reply(
+        port: &str,
+        link_status: &str,
+        down_time: &str,
+        priority: &str,
+        offset: &str,
+    ) -> HashMap<String, String> {
+        [
+            ("ip", "127.0.0.1"),
+            ("port", port),
+            ("flags", "slave"),
+            ("master-link-status", link_status),
+            ("master-link-down-time", down_time),
+            ("slave-priority", priority),
+            ("slave-repl-offset", offset),
+        ]
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_lowest_lag_prefers_up_to_date_replicas() {
+        let replicas = replicas(vec![
+            reply("6380", "ok", "0", "100", "500"),
+            reply("6381", "ok", "0", "0", "1000"),
+            reply("6382", "ok", "0", "100", "1000"),
+            reply("6383", "err", "1000", "100", "1000"),
+        ]);
+        let selector = LowestLagReplica::new();
+        assert_eq!(selector.select_replicas(&replicas), vec![2, 1, 0]);
+
+        let selector = LowestLagReplica::new()
+            .set_max_lag(100)
+            .set_max_master_link_down_time(Duration::from_secs(2));
+        assert_eq!(selector.select_replicas(&replicas), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_lowest_lag_tries_replicas_without_offset_last() {
+        let mut without_offset = reply("6380", "ok", "0", "100", "0");
+        without_offset.remove("slave-repl-offset");
+        without_offset.remove("slave-priority");
+        let replicas = replicas(vec![without_offset, reply("6381", "ok", "0", "100", "500")]);
+        assert_eq!(
+            LowestLagReplica::new().select_replicas(&replicas),
+            vec![1, 0]
+        );
+        assert_eq!(
+            LowestLagReplica::new()
+                .set_max_lag(100)
+                .select_replicas(&replicas),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_random_tries_every_replica() {
+        let replicas = replicas(vec![
+            reply("6380", "ok", "0", "100", "500"),
+            reply("6381", "err", "1000", "100", "1000"),
+        ]);
+        let mut selected = RandomSentinelReplica.select_replicas(&replicas);
+        selected.sort();
+        assert_eq!(selected, vec![0, 1]);
+    }
+}
@@ -0,0 +1,291 @@
+//! Defines the replies of the `SENTINEL` commands.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::types::{ErrorKind, FromRedisValue, RedisError, RedisResult, Value};
+
+macro_rules! not_convertible_error {
+    ($v:expr, $det:expr) => {
+        RedisError::from((
+            ErrorKind::TypeError,
+            "Response type not convertible",
+            format!("{:?} (response was {:?})", $det, $v),
+        ))
+    };
+}
+
+/// Parses a numeric field, which is None if the server omitted it.
+fn number<T: std::str::FromStr>(fields: &HashMap<String, String>, field: &str) -> Option<T> {
+    fields.get(field).and_then(|value| value.parse().ok())
+}
+
+fn address(fields: &HashMap<String, String>) -> Option<(String, u16)> {
+    let ip = fields.get("ip")?.clone();
+    let port = fields.get("port")?.parse().ok()?;
+    Some((ip, port))
+}
+
+/// Parses a reply which describes a single server, which is a map of fields with an `ip` and a `port`.
+fn parse_server<T>(
+    v: &Value,
+    from_fields: impl FnOnce(HashMap<String, String>) -> Option<T>,
+) -> RedisResult<T> {
+    let fields = HashMap::<String, String>::from_redis_value(v)?;
+    from_fields(fields).ok_or_else(|| not_convertible_error!(v, "Expect the fields of a server"))
+}
+
+/// A master monitored by a sentinel, as reported by `SENTINEL MASTER`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelMasterInfo {
+    name: String,
+    ip: String,
+    port: u16,
+    fields: HashMap<String, String>,
+}
+
+impl SentinelMasterInfo {
+    fn from_fields(fields: HashMap<String, String>) -> Option<Self> {
+        let (ip, port) = address(&fields)?;
+        Some(SentinelMasterInfo {
+            name: fields.get("name")?.clone(),
+            ip,
+            port,
+            fields,
+        })
+    }
+
+    /// The name the master is monitored with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The IP address of the master.
+    pub fn ip(&self) -> &str {
+        &self.ip
+    }
+
+    /// The port of the master.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The flags the sentinel reported for the master, e.g. `master,s_down`.
+    pub fn flags(&self) -> &str {
+        self.field("flags").unwrap_or_default()
+    }
+
+    /// Whether the sentinel considers the master to be subjectively or objectively down.
+    pub fn is_down(&self) -> bool {
+        self.flags().contains("s_down") || self.flags().contains("o_down")
+    }
+
+    /// The number of replicas of the master (`num-slaves`).
+    pub fn num_replicas(&self) -> Option<usize> {
+        number(&self.fields, "num-slaves")
+    }
+
+    /// The number of other sentinels monitoring the master (`num-other-sentinels`).
+    pub fn num_other_sentinels(&self) -> Option<usize> {
+        number(&self.fields, "num-other-sentinels")
+    }
+
+    /// The number of sentinels which need to agree that the master is down before it's failed over.
+    pub fn quorum(&self) -> Option<usize> {
+        number(&self.fields, "quorum")
+    }
+
+    /// Returns the raw value of any field of the reply, e.g. `config-epoch`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+}
+
+impl FromRedisValue for SentinelMasterInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        parse_server(v, Self::from_fields)
+    }
+}
+
+/// A replica of a master, as reported by `SENTINEL REPLICAS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaInfo {
+    ip: String,
+    port: u16,
+    fields: HashMap<String, String>,
+}
+
+impl ReplicaInfo {
+    pub(crate) fn from_fields(fields: HashMap<String, String>) -> Option<Self> {
+        let (ip, port) = address(&fields)?;
+        Some(ReplicaInfo { ip, port, fields })
+    }
+
+    /// The IP address of the replica.
+    pub fn ip(&self) -> &str {
+        &self.ip
+    }
+
+    /// The port of the replica.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The flags the sentinel reported for the replica, e.g. `slave,disconnected`.
+    pub fn flags(&self) -> &str {
+        self.field("flags").unwrap_or_default()
+    }
+
+    /// Whether the replica is connected to its master (`master-link-status`).
+    pub fn is_master_link_up(&self) -> bool {
+        self.field("master-link-status") == Some("ok")
+    }
+
+    /// How long the replica has been disconnected from its master (`master-link-down-time`).
+    pub fn master_link_down_time(&self) -> Option<Duration> {
+        number(&self.fields, "master-link-down-time").map(Duration::from_millis)
+    }
+
+    /// The priority of the replica in failovers (`slave-priority`). Lower values are promoted first, and 0 means
+    /// that the replica is never promoted.
+    pub fn priority(&self) -> Option<u32> {
+        number(&self.fields, "slave-priority")
+    }
+
+    /// The replication offset of the replica (`slave-repl-offset`).
+    pub fn replication_offset(&self) -> Option<u64> {
+        number(&self.fields, "slave-repl-offset")
+    }
+
+    /// Returns the raw value of any field of the reply, e.g. `role-reported`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+}
+
+impl FromRedisValue for ReplicaInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        parse_server(v, Self::from_fields)
+    }
+}
+
+/// Another sentinel monitoring the same master, as reported by `SENTINEL SENTINELS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentinelInfo {
+    ip: String,
+    port: u16,
+    fields: HashMap<String, String>,
+}
+
+impl SentinelInfo {
+    fn from_fields(fields: HashMap<String, String>) -> Option<Self> {
+        let (ip, port) = address(&fields)?;
+        Some(SentinelInfo { ip, port, fields })
+    }
+
+    /// The IP address of the sentinel.
+    pub fn ip(&self) -> &str {
+        &self.ip
+    }
+
+    /// The port of the sentinel.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The run ID of the sentinel.
+    pub fn run_id(&self) -> Option<&str> {
+        self.field("runid")
+    }
+
+    /// The flags the reporting sentinel has for the sentinel, e.g. `sentinel,disconnected`.
+    pub fn flags(&self) -> &str {
+        self.field("flags").unwrap_or_default()
+    }
+
+    /// Returns the raw value of any field of the reply, e.g. `last-hello-message`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+}
+
+impl FromRedisValue for SentinelInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        parse_server(v, Self::from_fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(fields: &[(&str, &str)]) -> Value {
+        Value::Array(
+            fields
+                .iter()
+                .flat_map(|(field, value)| [*field, *value])
+                .map(|item| Value::BulkString(item.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_master_info() {
+        let master = SentinelMasterInfo::from_redis_value(&reply(&[
+            ("name", "mymaster"),
+            ("ip", "10.0.0.1"),
+            ("port", "6379"),
+            ("flags", "master,s_down"),
+            ("num-slaves", "2"),
+            ("num-other-sentinels", "2"),
+            ("quorum", "2"),
+            ("config-epoch", "7"),
+        ]))
+        .unwrap();
+        assert_eq!(master.name(), "mymaster");
+        assert_eq!((master.ip(), master.port()), ("10.0.0.1", 6379));
+        assert!(master.is_down());
+        assert_eq!(master.num_replicas(), Some(2));
+        assert_eq!(master.num_other_sentinels(), Some(2));
+        assert_eq!(master.quorum(), Some(2));
+        assert_eq!(master.field("config-epoch"), Some("7"));
+    }
+
+    #[test]
+    fn test_parse_replicas() {
+        let replicas: Vec<ReplicaInfo> =
+            FromRedisValue::from_redis_value(&Value::Array(vec![reply(&[
+                ("ip", "10.0.0.2"),
+                ("port", "6380"),
+                ("master-link-status", "err"),
+                ("master-link-down-time", "1500"),
+                ("slave-priority", "100"),
+                ("slave-repl-offset", "42"),
+            ])]))
+            .unwrap();
+        let replica = &replicas[0];
+        assert_eq!(replica.port(), 6380);
+        assert!(!replica.is_master_link_up());
+        assert_eq!(
+            replica.master_link_down_time(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(replica.priority(), Some(100));
+        assert_eq!(replica.replication_offset(), Some(42));
+    }
+
+    #[test]
+    fn test_omitted_fields_are_none() {
+        let replica =
+            ReplicaInfo::from_redis_value(&reply(&[("ip", "10.0.0.2"), ("port", "6380")])).unwrap();
+        assert_eq!(replica.master_link_down_time(), None);
+        assert_eq!(replica.priority(), None);
+        assert_eq!(replica.replication_offset(), None);
+    }
+
+    #[test]
+    fn test_server_without_address_is_not_convertible() {
+        let err = SentinelInfo::from_redis_value(&reply(&[("ip", "10.0.0.3")])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
+}
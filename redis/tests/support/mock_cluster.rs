@@ -1,6 +1,6 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
 
@@ -11,7 +11,7 @@ use redis::{
 
 use {
     once_cell::sync::Lazy,
-    redis::{IntoConnectionInfo, RedisResult, Value},
+    redis::{parse_redis_value, IntoConnectionInfo, RedisResult, Value},
 };
 
 #[cfg(feature = "cluster-async")]
@@ -31,6 +31,8 @@ static HANDLERS: Lazy<RwLock<HashMap<String, Handler>>> = Lazy::new(Default::def
 pub struct MockConnection {
     pub handler: Handler,
     pub port: u16,
+    /// Replies of the last pipeline passed to `send_packed_command`, returned by `recv_response`.
+    pub pending_responses: Arc<Mutex<VecDeque<RedisResult<Value>>>>,
 }
 
 #[cfg(feature = "cluster-async")]
@@ -56,6 +58,7 @@ impl cluster_async::Connect for MockConnection {
                 .unwrap_or_else(|| panic!("Handler `{name}` were not installed"))
                 .clone(),
             port,
+            pending_responses: Default::default(),
         }))
     }
 }
@@ -79,10 +82,21 @@ impl cluster::Connect for MockConnection {
                 .unwrap_or_else(|| panic!("Handler `{name}` were not installed"))
                 .clone(),
             port,
+            pending_responses: Default::default(),
         })
     }
 
-    fn send_packed_command(&mut self, _cmd: &[u8]) -> RedisResult<()> {
+    // The handler answers a sent pipeline with an array holding the reply of each command.
+    fn send_packed_command(&mut self, cmd: &[u8]) -> RedisResult<()> {
+        let res = (self.handler)(cmd, self.port).expect_err("Handler did not specify a response");
+        let replies = match res? {
+            Value::Array(replies) => replies,
+            reply => vec![reply],
+        };
+        self.pending_responses
+            .lock()
+            .unwrap()
+            .extend(replies.into_iter().map(Ok));
         Ok(())
     }
 
@@ -95,7 +109,11 @@ impl cluster::Connect for MockConnection {
     }
 
     fn recv_response(&mut self) -> RedisResult<Value> {
-        Ok(Value::Nil)
+        self.pending_responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(Ok(Value::Nil))
     }
 }
 
@@ -108,9 +126,16 @@ pub fn contains_slice(xs: &[u8], ys: &[u8]) -> bool {
     false
 }
 
+/// Servers older than Redis 7 don't support `CLUSTER SHARDS`, so the client falls back to `CLUSTER SLOTS`.
+pub fn unknown_shards_subcommand() -> RedisResult<Value> {
+    parse_redis_value(b"-ERR unknown subcommand 'SHARDS'\r\n")
+}
+
 pub fn respond_startup(name: &str, cmd: &[u8]) -> Result<(), RedisResult<Value>> {
     if contains_slice(cmd, b"PING") {
         Err(Ok(Value::SimpleString("OK".into())))
+    } else if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+        Err(unknown_shards_subcommand())
     } else if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
         Err(Ok(Value::Array(vec![Value::Array(vec![
             Value::Int(0),
@@ -176,29 +201,28 @@ pub fn respond_startup_with_replica_using_config(
     ]);
     if contains_slice(cmd, b"PING") {
         Err(Ok(Value::SimpleString("OK".into())))
+    } else if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+        Err(unknown_shards_subcommand())
     } else if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
         let slots = slots_config
             .into_iter()
             .map(|slot_config| {
-                let replicas = slot_config
-                    .replica_ports
-                    .into_iter()
-                    .flat_map(|replica_port| {
-                        vec![
-                            Value::BulkString(name.as_bytes().to_vec()),
-                            Value::Int(replica_port as i64),
-                        ]
-                    })
-                    .collect();
-                Value::Array(vec![
+                let replicas = slot_config.replica_ports.into_iter().map(|replica_port| {
+                    Value::Array(vec![
+                        Value::BulkString(name.as_bytes().to_vec()),
+                        Value::Int(replica_port as i64),
+                    ])
+                });
+                let mut slot = vec![
                     Value::Int(slot_config.slot_range.start as i64),
                     Value::Int(slot_config.slot_range.end as i64),
                     Value::Array(vec![
                         Value::BulkString(name.as_bytes().to_vec()),
                         Value::Int(slot_config.primary_port as i64),
                     ]),
-                    Value::Array(replicas),
-                ])
+                ];
+                slot.extend(replicas);
+                Value::Array(slot)
             })
             .collect();
         Err(Ok(Value::Array(slots)))
@@ -14,7 +14,7 @@ use std::{
 };
 use std::{io, thread::sleep, time::Duration};
 
-#[cfg(feature = "cache-aio")]
+#[cfg(feature = "cache")]
 use redis::caching::CacheConfig;
 #[cfg(feature = "tls-rustls")]
 use redis::{ClientTlsConfig, TlsCertificates};
@@ -295,6 +295,16 @@ impl TestContext {
         self.client.get_multiplexed_async_connection().await
     }
 
+    #[cfg(feature = "cache")]
+    pub fn connection_with_cache_config(
+        &self,
+        cache_config: CacheConfig,
+    ) -> redis::RedisResult<redis::Connection> {
+        self.client.get_connection_with_config(
+            &redis::ConnectionConfig::new().set_cache_config(cache_config),
+        )
+    }
+
     #[cfg(all(feature = "aio", feature = "cache-aio"))]
     pub fn async_connection_with_cache(
         &self,
@@ -2193,4 +2193,37 @@ mod basic_async {
         )
         .unwrap();
     }
+    #[cfg(feature = "deadpool")]
+    mod deadpool_test {
+        use super::*;
+        use deadpool::managed::Pool;
+
+        #[rstest]
+        #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+        #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+        fn test_pool_replaces_broken_connection(#[case] runtime: RuntimeType) {
+            let ctx = TestContext::new();
+            let pool = Pool::<redis::Client>::builder(ctx.client.clone())
+                .max_size(1)
+                .build()
+                .unwrap();
+            block_on_all(
+                async move {
+                    let mut conn = pool.get().await.unwrap();
+                    let _: () = conn.set("key", "value").await?;
+                    kill_client_async(&mut *conn, &ctx.client).await?;
+                    drop(conn);
+
+                    // Killed connection fails the health check on recycle, so a new one is created.
+                    let mut conn = pool.get().await.unwrap();
+                    let value: String = conn.get("key").await?;
+                    assert_eq!(value, "value");
+                    assert_eq!(pool.status().size, 1);
+                    Ok::<_, RedisError>(())
+                },
+                runtime,
+            )
+            .unwrap();
+        }
+    }
 }
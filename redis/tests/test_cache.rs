@@ -5,13 +5,16 @@ use futures_time::task::sleep;
 #[cfg(feature = "cluster-async")]
 use redis::cluster_routing::*;
 use redis::CommandCacheConfig;
-use redis::{caching::CacheConfig, AsyncCommands, ProtocolVersion, RedisError};
+use redis::{
+    caching::{CacheConfig, InvalidationEvent},
+    AsyncCommands, ProtocolVersion, RedisError,
+};
 #[cfg(feature = "json")]
 use redis_test::server::Module;
 use rstest::rstest;
 #[cfg(feature = "json")]
 use serde_json::json;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 mod support;
 
@@ -806,6 +809,51 @@ fn test_cache_async_cluster_mget(#[case] runtime: RuntimeType) {
     .unwrap();
 }
 
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+#[cfg(feature = "cluster-async")]
+fn test_cache_async_cluster_prefetch(#[case] runtime: RuntimeType) {
+    let ctx = TestClusterContext::new_with_cluster_client_builder(|builder| {
+        builder.cache_config(CacheConfig::default())
+    });
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx.async_connection().await;
+
+            // These keys belong to different slots, so they can't be read in a single pipeline.
+            let keys = ["key_1", "key_2", "key_3"];
+            assert_ne!(get_slot(b"key_1"), get_slot(b"key_2"));
+            assert_ne!(get_slot(b"key_2"), get_slot(b"key_3"));
+            for (value, key) in keys.iter().enumerate() {
+                let _: () = con.set(key, value).await?;
+            }
+            let _: () = con.hset("hash_1", "field", 42).await?;
+
+            con.prefetch_cache_keys(&keys).await?;
+            con.prefetch_cache_commands(&[redis::Cmd::hget("hash_1", "field")])
+                .await?;
+            assert_hit!(&con, 0);
+            assert_miss!(&con, 4);
+
+            for (value, key) in keys.iter().enumerate() {
+                let res: usize = redis::cmd("GET").arg(key).query_async(&mut con).await?;
+                assert_eq!(res, value);
+            }
+            let res: i32 = con.hget("hash_1", "field").await?;
+            assert_eq!(res, 42);
+            assert_hit!(&con, 4);
+            assert_miss!(&con, 4);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
 // Support function for testing pipelines
 fn get_pipe(atomic: bool) -> redis::Pipeline {
     if atomic {
@@ -987,6 +1035,472 @@ fn test_cache_async_cluster_slot_change(
     .unwrap();
 }
 
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_broadcast_prefixes(
+    #[case] runtime: RuntimeType,
+    #[values(true, false)] test_with_optin: bool,
+) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut cache_config = CacheConfig::new().set_broadcast_prefixes(["cfg:"]);
+            if test_with_optin {
+                cache_config = cache_config.set_mode(redis::caching::CacheMode::OptIn);
+            }
+            let mut con = ctx.async_connection_with_cache_config(cache_config).await?;
+
+            let _: Option<String> = get_cmd("GET", test_with_optin)
+                .arg("cfg:key_1")
+                .query_async(&mut con)
+                .await?;
+            let _: Option<String> = get_cmd("GET", test_with_optin)
+                .arg("cfg:key_1")
+                .query_async(&mut con)
+                .await?;
+            assert_hit!(&con, 1);
+            assert_miss!(&con, 1);
+
+            // Keys outside of prefixes must not be cached.
+            let _: Option<String> = get_cmd("GET", test_with_optin)
+                .arg("other:key_1")
+                .query_async(&mut con)
+                .await?;
+            let _: Option<String> = get_cmd("GET", test_with_optin)
+                .arg("other:key_1")
+                .query_async(&mut con)
+                .await?;
+            assert_hit!(&con, 1);
+            assert_miss!(&con, 1);
+
+            let _: () = get_cmd("SET", test_with_optin)
+                .arg("cfg:key_1")
+                .arg("1")
+                .query_async(&mut con)
+                .await?;
+            sleep(Duration::from_millis(50).into()).await; // Give time for push message to be received after invalidating cfg:key_1.
+            assert_invalidate!(&con, 1);
+
+            let val: String = get_cmd("GET", test_with_optin)
+                .arg("cfg:key_1")
+                .query_async(&mut con)
+                .await?;
+            assert_eq!(val, "1");
+            assert_hit!(&con, 1);
+            assert_miss!(&con, 2);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+fn test_cache_sync_basic(#[values(true, false)] test_with_optin: bool) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    let cache_config = if test_with_optin {
+        CacheConfig::new().set_mode(redis::caching::CacheMode::OptIn)
+    } else {
+        CacheConfig::default()
+    };
+    let mut con = ctx.connection_with_cache_config(cache_config).unwrap();
+
+    let val: Option<String> = get_cmd("GET", test_with_optin)
+        .arg("key_1")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(val, None);
+    assert_hit!(&con, 0);
+    assert_miss!(&con, 1);
+
+    let val: Option<String> = get_cmd("GET", test_with_optin)
+        .arg("key_1")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(val, None);
+    assert_hit!(&con, 1);
+    assert_miss!(&con, 1);
+
+    let _: () = get_cmd("SET", test_with_optin)
+        .arg("key_1")
+        .arg("1")
+        .query(&mut con)
+        .unwrap();
+    // Sync connection handles push messages only while reading a response from the server.
+    let _: () = redis::cmd("PING").query(&mut con).unwrap();
+    assert_invalidate!(&con, 1);
+
+    let val: String = get_cmd("GET", test_with_optin)
+        .arg("key_1")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(val, "1");
+    assert_hit!(&con, 1);
+    assert_miss!(&con, 2);
+}
+
+#[rstest]
+fn test_cache_sync_pipeline(#[values(true, false)] atomic: bool) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    let mut con = ctx
+        .connection_with_cache_config(CacheConfig::default())
+        .unwrap();
+
+    let mut pipe = redis::pipe();
+    if atomic {
+        pipe.atomic();
+    }
+    pipe.cmd("SET")
+        .arg("key_1")
+        .arg(42)
+        .ignore()
+        .cmd("GET")
+        .arg("key_1")
+        .cmd("GET")
+        .arg("key_2");
+
+    let res: (i64, Option<i64>) = pipe.query(&mut con).unwrap();
+    assert_eq!(res, (42, None));
+    assert_hit!(&con, 0);
+    assert_miss!(&con, 2);
+
+    let res: (i64, Option<i64>) = redis::pipe()
+        .cmd("GET")
+        .arg("key_1")
+        .cmd("GET")
+        .arg("key_2")
+        .query(&mut con)
+        .unwrap();
+    assert_eq!(res, (42, None));
+    assert_hit!(&con, 2);
+    assert_miss!(&con, 2);
+}
+
+#[test]
+#[cfg(feature = "cluster")]
+fn test_cache_sync_cluster() {
+    let ctx = TestClusterContext::new_with_cluster_client_builder(|builder| {
+        builder.cache_config(CacheConfig::default())
+    });
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    let mut con = ctx.connection();
+
+    let _: () = redis::cmd("SET")
+        .arg("key_1")
+        .arg(41)
+        .query(&mut con)
+        .unwrap();
+    let val: Option<usize> = redis::cmd("GET").arg("key_1").query(&mut con).unwrap();
+    assert_eq!(val, Some(41));
+    assert_hit!(&con, 0);
+    assert_miss!(&con, 1);
+
+    let val: Option<usize> = redis::cmd("GET").arg("key_1").query(&mut con).unwrap();
+    assert_eq!(val, Some(41));
+    assert_hit!(&con, 1);
+    assert_miss!(&con, 1);
+
+    let _: () = redis::cmd("SET")
+        .arg("key_1")
+        .arg(42)
+        .query(&mut con)
+        .unwrap();
+    // Invalidation is handled when node connections read a response from the server,
+    // EXISTS is routed to the node which owns the key.
+    let _: bool = redis::cmd("EXISTS").arg("key_1").query(&mut con).unwrap();
+    let val: Option<usize> = redis::cmd("GET").arg("key_1").query(&mut con).unwrap();
+    assert_eq!(val, Some(42));
+    assert_hit!(&con, 1);
+    assert_miss!(&con, 2);
+    assert_invalidate!(&con, 1);
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_collection_commands(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx.async_connection_with_cache().await?;
+            let _: () = redis::pipe()
+                .cmd("SADD")
+                .arg("set_1")
+                .arg("a")
+                .ignore()
+                .cmd("RPUSH")
+                .arg("list_1")
+                .arg("b")
+                .ignore()
+                .query_async(&mut con)
+                .await?;
+
+            for _ in 0..2 {
+                let members: Vec<String> = redis::cmd("SMEMBERS")
+                    .arg("set_1")
+                    .query_async(&mut con)
+                    .await?;
+                assert_eq!(members, vec!["a"]);
+                let items: Vec<String> = redis::cmd("LRANGE")
+                    .arg("list_1")
+                    .arg(0)
+                    .arg(-1)
+                    .query_async(&mut con)
+                    .await?;
+                assert_eq!(items, vec!["b"]);
+            }
+            assert_hit!(&con, 2);
+            assert_miss!(&con, 2);
+
+            // Random replies must not be cached.
+            let _: Option<String> = redis::cmd("SRANDMEMBER")
+                .arg("set_1")
+                .query_async(&mut con)
+                .await?;
+            assert_hit!(&con, 2);
+            assert_miss!(&con, 2);
+
+            let _: () = redis::cmd("SADD")
+                .arg("set_1")
+                .arg("c")
+                .query_async(&mut con)
+                .await?;
+            sleep(Duration::from_millis(50).into()).await; // Give time for push message to be received after invalidating set_1.
+            assert_invalidate!(&con, 1);
+            let members: HashSet<String> = redis::cmd("SMEMBERS")
+                .arg("set_1")
+                .query_async(&mut con)
+                .await?;
+            assert_eq!(members, HashSet::from(["a".to_string(), "c".to_string()]));
+            assert_miss!(&con, 3);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_invalidation_sender(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let mut con = ctx
+                .async_connection_with_cache_config(CacheConfig::new().set_invalidation_sender(tx))
+                .await?;
+            let _: Option<String> = redis::cmd("GET").arg("key_1").query_async(&mut con).await?;
+            let _: () = redis::cmd("SET")
+                .arg("key_1")
+                .arg(1)
+                .query_async(&mut con)
+                .await?;
+            sleep(Duration::from_millis(50).into()).await; // Give time for push message to be received after invalidating key_1.
+            assert_eq!(
+                rx.try_recv().unwrap(),
+                InvalidationEvent::Keys(vec![b"key_1".to_vec()])
+            );
+
+            let _: () = redis::cmd("FLUSHDB").query_async(&mut con).await?;
+            sleep(Duration::from_millis(50).into()).await;
+            assert_eq!(rx.try_recv().unwrap(), InvalidationEvent::Flush);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_prefetch_and_manual_invalidation(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx
+                .async_connection_with_cache_config(
+                    CacheConfig::new().set_mode(redis::caching::CacheMode::OptIn),
+                )
+                .await?;
+            let _: () = con
+                .mset(&[("user:1", 1), ("user:2", 2), ("cfg:1", 3)])
+                .await?;
+
+            con.prefetch_cache_keys(&["user:1", "user:2", "cfg:1"])
+                .await?;
+            assert_miss!(&con, 3);
+
+            let val: i32 = get_cmd("GET", true)
+                .arg("user:1")
+                .query_async(&mut con)
+                .await?;
+            assert_eq!(val, 1);
+            assert_hit!(&con, 1);
+
+            con.invalidate_cache_keys("user:1");
+            con.invalidate_cache_pattern("cfg:*");
+            for key in ["user:1", "user:2", "cfg:1"] {
+                let _: i32 = get_cmd("GET", true).arg(key).query_async(&mut con).await?;
+            }
+            // Only user:2 must be served from the cache.
+            assert_hit!(&con, 2);
+            assert_miss!(&con, 5);
+
+            con.flush_cache();
+            let _: i32 = get_cmd("GET", true)
+                .arg("user:2")
+                .query_async(&mut con)
+                .await?;
+            assert_hit!(&con, 2);
+            assert_miss!(&con, 6);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_stale_while_revalidate(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx
+                .async_connection_with_cache_config(
+                    CacheConfig::new()
+                        .set_default_client_ttl(Duration::from_millis(50))
+                        .set_stale_while_revalidate(Duration::from_secs(10)),
+                )
+                .await?;
+            let mut get_key = redis::cmd("GET");
+            get_key.arg("key_1");
+            let _: () = con.set("key_1", 1).await?;
+            let val: i32 = get_key.query_async(&mut con).await?;
+            assert_eq!(val, 1);
+            assert_miss!(&con, 1);
+
+            sleep(Duration::from_millis(60).into()).await;
+            // Expired value is served while it's refreshed in background.
+            let val: i32 = get_key.query_async(&mut con).await?;
+            assert_eq!(val, 1);
+            sleep(Duration::from_millis(20).into()).await;
+            let val: i32 = get_key.query_async(&mut con).await?;
+            assert_eq!(val, 1);
+            assert_hit!(&con, 3);
+            // Refresh request misses the cache by reading from the server.
+            assert_miss!(&con, 1);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_nil_ttl(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx
+                .async_connection_with_cache_config(
+                    CacheConfig::new().set_nil_ttl(Duration::from_millis(50)),
+                )
+                .await?;
+            for _ in 0..2 {
+                let val: Option<i32> = redis::cmd("GET").arg("key_1").query_async(&mut con).await?;
+                assert_eq!(val, None);
+            }
+            assert_hit!(&con, 1);
+            assert_miss!(&con, 1);
+
+            sleep(Duration::from_millis(60).into()).await;
+            let _: Option<i32> = redis::cmd("GET").arg("key_1").query_async(&mut con).await?;
+            assert_hit!(&con, 1);
+            assert_miss!(&con, 2);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
+#[rstest]
+#[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+#[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+fn test_cache_detailed_statistics(#[case] runtime: RuntimeType) {
+    let ctx = TestContext::new();
+    if ctx.protocol == ProtocolVersion::RESP2 {
+        return;
+    }
+    block_on_all(
+        async move {
+            let mut con = ctx
+                .async_connection_with_cache_config(
+                    CacheConfig::new()
+                        .set_command_statistics(true)
+                        .set_prefix_statistics(["user:"]),
+                )
+                .await?;
+            for _ in 0..3 {
+                let _: Option<String> = redis::cmd("GET")
+                    .arg("user:1")
+                    .query_async(&mut con)
+                    .await?;
+            }
+            let _: Option<String> = redis::cmd("HGET")
+                .arg("cfg")
+                .arg("name")
+                .query_async(&mut con)
+                .await?;
+
+            let statistics = con.get_cache_statistics().unwrap();
+            assert_eq!(statistics.entry_count, 2);
+            assert_eq!(statistics.eviction, 0);
+            assert_eq!(statistics.commands["GET"].hit, 2);
+            assert_eq!(statistics.commands["GET"].miss, 1);
+            assert_eq!(statistics.commands["HGET"].miss, 1);
+            assert_eq!(statistics.prefixes["user:"].hit_ratio(), 2.0 / 3.0);
+            Ok::<_, RedisError>(())
+        },
+        runtime,
+    )
+    .unwrap();
+}
+
 // Support function for testing cases where CacheMode::All == CacheMode::OptIn
 fn get_cmd(name: &str, enable_opt_in: bool) -> redis::Cmd {
     let mut cmd = redis::cmd(name);
@@ -4,13 +4,16 @@ mod support;
 #[cfg(test)]
 mod cluster {
     use std::sync::{
-        atomic::{self, AtomicI32, Ordering},
+        atomic::{self, AtomicBool, AtomicI32, Ordering},
         Arc,
     };
 
     use crate::support::*;
     use redis::{
-        cluster::{cluster_pipe, ClusterClient, ClusterConnection},
+        cluster::{
+            cluster_pipe, AzAffinityReplica, CircuitBreakerConfig, ClusterClient,
+            ClusterConnection, ReadFromReplicaStrategy,
+        },
         cluster_routing::{MultipleNodeRoutingInfo, RoutingInfo, SingleNodeRoutingInfo},
         cmd, parse_redis_value, Commands, ConnectionLike, ErrorKind, ProtocolVersion, RedisError,
         Value,
@@ -497,6 +500,9 @@ mod cluster {
             if contains_slice(cmd, b"PING") {
                 return Err(Ok(Value::SimpleString("OK".into())));
             }
+            if contains_slice(cmd, b"SHARDS") {
+                return Err(unknown_shards_subcommand());
+            }
 
             let i = requests.fetch_add(1, atomic::Ordering::SeqCst);
 
@@ -630,6 +636,245 @@ mod cluster {
         assert_eq!(value, Ok(Some(123)));
     }
 
+    #[test]
+    fn test_cluster_read_from_replicas_in_same_availability_zone() {
+        let name = "test_cluster_read_from_replicas_in_same_availability_zone";
+
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .read_from_replicas_with(AzAffinityReplica::new("az-a")),
+            name,
+            move |cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(
+                    name,
+                    cmd,
+                    Some(vec![MockSlotRange {
+                        primary_port: 6379,
+                        replica_ports: vec![6380, 6381],
+                        slot_range: (0..16383),
+                    }]),
+                )?;
+                if contains_slice(cmd, b"INFO") {
+                    let availability_zone = if port == 6380 { "az-b" } else { "az-a" };
+                    return Err(Ok(Value::BulkString(
+                        format!("# Server\r\navailability_zone:{availability_zone}\r\n")
+                            .into_bytes(),
+                    )));
+                }
+                match port {
+                    6381 => Err(Ok(Value::BulkString(b"123".to_vec()))),
+                    _ => panic!("Wrong node"),
+                }
+            },
+        );
+
+        for _ in 0..3 {
+            let value = cmd("GET").arg("test").query::<Option<i32>>(&mut connection);
+            assert_eq!(value, Ok(Some(123)));
+        }
+    }
+
+    #[test]
+    fn test_cluster_latency_is_only_recorded_for_replica_reads() {
+        let name = "test_cluster_latency_is_only_recorded_for_replica_reads";
+
+        struct RecordingStrategy {
+            recorded: Arc<std::sync::Mutex<Vec<String>>>,
+        }
+
+        impl ReadFromReplicaStrategy for RecordingStrategy {
+            fn select_replica(&self, _replicas: &[String]) -> usize {
+                0
+            }
+
+            fn uses_latency(&self) -> bool {
+                true
+            }
+
+            fn record_latency(&self, address: &str, _latency: std::time::Duration) {
+                self.recorded.lock().unwrap().push(address.to_string());
+            }
+        }
+
+        let recorded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .read_from_replicas_with(RecordingStrategy {
+                    recorded: recorded.clone(),
+                }),
+            name,
+            move |cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(
+                    name,
+                    cmd,
+                    Some(vec![MockSlotRange {
+                        primary_port: 6379,
+                        replica_ports: vec![6380],
+                        slot_range: (0..16383),
+                    }]),
+                )?;
+                match port {
+                    6379 => Err(Ok(Value::Okay)),
+                    6380 if contains_slice(cmd, b"XREAD") => Err(Ok(Value::Nil)),
+                    6380 => Err(Ok(Value::BulkString(b"123".to_vec()))),
+                    _ => panic!("Wrong node"),
+                }
+            },
+        );
+
+        cmd("SET")
+            .arg("test")
+            .arg(123)
+            .exec(&mut connection)
+            .unwrap();
+        let value = cmd("GET").arg("test").query::<Option<i32>>(&mut connection);
+        assert_eq!(value, Ok(Some(123)));
+        cmd("XREAD")
+            .arg("BLOCK")
+            .arg(0)
+            .arg("STREAMS")
+            .arg("test")
+            .arg("$")
+            .exec(&mut connection)
+            .unwrap();
+
+        assert_eq!(
+            *recorded.lock().unwrap(),
+            vec![format!("{name}:6380")],
+            "Only the read served by the replica must be recorded, without the blocking read"
+        );
+    }
+
+    #[test]
+    fn test_cluster_topology_returns_slot_map() {
+        let name = "test_cluster_topology_returns_slot_map";
+        let MockEnv {
+            connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup_with_replica_using_config(
+                name,
+                cmd,
+                Some(vec![
+                    MockSlotRange {
+                        primary_port: 6379,
+                        replica_ports: vec![6381],
+                        slot_range: (0..8191),
+                    },
+                    MockSlotRange {
+                        primary_port: 6380,
+                        replica_ports: vec![],
+                        slot_range: (8192..16383),
+                    },
+                ]),
+            )?;
+            Ok(())
+        });
+
+        let topology = connection.topology();
+        let slot_ranges: Vec<_> = topology
+            .slot_ranges()
+            .iter()
+            .map(|range| {
+                (
+                    range.start(),
+                    range.end(),
+                    range.primary(),
+                    range.replicas(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            slot_ranges,
+            vec![
+                (
+                    0,
+                    8191,
+                    &*format!("{name}:6379"),
+                    &[format!("{name}:6381")][..]
+                ),
+                (8192, 16383, &*format!("{name}:6380"), &[][..]),
+            ]
+        );
+        assert_eq!(
+            topology.slot_range_for_key(b"foo").unwrap().primary(),
+            format!("{name}:6380")
+        );
+        // Ids are only reported by CLUSTER SHARDS.
+        assert_eq!(topology.node_id(&format!("{name}:6379")), None);
+    }
+
+    #[test]
+    fn test_cluster_discovers_topology_with_cluster_shards() {
+        let name = "test_cluster_discovers_topology_with_cluster_shards";
+
+        let node = |port: i64, role: &str, health: &str| {
+            Value::Array(
+                [
+                    ("id", Value::BulkString(format!("id-{port}").into_bytes())),
+                    ("port", Value::Int(port)),
+                    ("endpoint", Value::BulkString(name.as_bytes().to_vec())),
+                    ("role", Value::BulkString(role.as_bytes().to_vec())),
+                    ("health", Value::BulkString(health.as_bytes().to_vec())),
+                ]
+                .into_iter()
+                .flat_map(|(field, value)| [Value::BulkString(field.as_bytes().to_vec()), value])
+                .collect(),
+            )
+        };
+        let shards = Value::Array(vec![Value::Array(vec![
+            Value::BulkString(b"slots".to_vec()),
+            Value::Array(vec![Value::Int(0), Value::Int(16383)]),
+            Value::BulkString(b"nodes".to_vec()),
+            Value::Array(vec![
+                node(6379, "master", "online"),
+                node(6380, "replica", "loading"),
+                node(6381, "replica", "online"),
+            ]),
+        ])]);
+
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .read_from_replicas(),
+            name,
+            move |cmd: &[u8], port| {
+                if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+                    return Err(Ok(shards.clone()));
+                }
+                if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
+                    panic!("CLUSTER SLOTS shouldn't be used if CLUSTER SHARDS is supported");
+                }
+                respond_startup(name, cmd)?;
+                match port {
+                    // The loading replica doesn't serve reads.
+                    6381 => Err(Ok(Value::BulkString(b"123".to_vec()))),
+                    _ => panic!("Wrong node"),
+                }
+            },
+        );
+
+        for _ in 0..3 {
+            let value = cmd("GET").arg("test").query::<Option<i32>>(&mut connection);
+            assert_eq!(value, Ok(Some(123)));
+        }
+    }
+
     #[test]
     fn test_cluster_replica_read() {
         let name = "test_cluster_replica_read";
@@ -858,6 +1103,85 @@ mod cluster {
         );
     }
 
+    #[test]
+    fn test_cluster_scan_iterates_all_primaries() {
+        let name = "test_cluster_scan_iterates_all_primaries";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |received_cmd: &[u8], port| {
+            respond_startup_two_nodes(name, received_cmd)?;
+            let cmd_str = std::str::from_utf8(received_cmd).unwrap();
+            if !cmd_str.contains("SCAN") {
+                return Ok(());
+            }
+            // Each primary returns its keys in two batches.
+            let (cursor, key) = if cmd_str.ends_with("\r\n0\r\n") {
+                ("7", format!("first-{port}"))
+            } else {
+                ("0", format!("second-{port}"))
+            };
+            Err(Ok(Value::Array(vec![
+                Value::BulkString(cursor.as_bytes().to_vec()),
+                Value::Array(vec![Value::BulkString(key.into_bytes())]),
+            ])))
+        });
+
+        let iter = connection.scan().unwrap();
+        #[cfg(feature = "safe_iterators")]
+        let iter = iter.map(std::result::Result::unwrap);
+        let mut keys: Vec<String> = iter.collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec!["first-6379", "first-6380", "second-6379", "second-6380"]
+        );
+    }
+
+    #[test]
+    fn test_cluster_scan_retries_primary_which_fails_mid_scan() {
+        let name = "test_cluster_scan_retries_primary_which_fails_mid_scan";
+        let failed = AtomicBool::new(false);
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |received_cmd: &[u8], port| {
+            respond_startup_two_nodes(name, received_cmd)?;
+            let cmd_str = std::str::from_utf8(received_cmd).unwrap();
+            if !cmd_str.contains("SCAN") {
+                return Ok(());
+            }
+            let (cursor, key) = if cmd_str.ends_with("\r\n0\r\n") {
+                ("7", format!("first-{port}"))
+            } else if port == 6380 && !failed.swap(true, Ordering::SeqCst) {
+                // The primary fails once after returning its first batch.
+                return Err(Err(RedisError::from((
+                    ErrorKind::ResponseError,
+                    "primary failure",
+                ))));
+            } else {
+                ("0", format!("second-{port}"))
+            };
+            Err(Ok(Value::Array(vec![
+                Value::BulkString(cursor.as_bytes().to_vec()),
+                Value::Array(vec![Value::BulkString(key.into_bytes())]),
+            ])))
+        });
+
+        // The error isn't returned, since the primary still owns its slots and the scan is retried.
+        let iter = connection.scan().unwrap();
+        #[cfg(feature = "safe_iterators")]
+        let iter = iter.map(std::result::Result::unwrap);
+        let mut keys: Vec<String> = iter.collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec!["first-6379", "first-6380", "second-6379", "second-6380"]
+        );
+    }
+
     #[test]
     fn test_cluster_split_multi_shard_command_and_combine_arrays_of_values() {
         let name = "test_cluster_split_multi_shard_command_and_combine_arrays_of_values";
@@ -895,6 +1219,191 @@ mod cluster {
         assert_eq!(result, vec!["foo-6382", "bar-6380", "baz-6380"]);
     }
 
+    #[test]
+    fn test_cluster_split_multi_shard_command_retries_redirected_sub_commands() {
+        let name = "test_cluster_split_multi_shard_command_retries_redirected_sub_commands";
+        let redirected = AtomicBool::new(false);
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")]).retries(1),
+            name,
+            move |received_cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, received_cmd, None)?;
+                let cmd_str = std::str::from_utf8(received_cmd).unwrap();
+                let deleted = ["foo", "bar", "baz"]
+                    .iter()
+                    .filter(|key| cmd_str.contains(*key))
+                    .count();
+                match port {
+                    // The slot of "foo" moved, while "bar" and "baz" are still served by 6379.
+                    6381 if !redirected.swap(true, Ordering::SeqCst) => Err(Err(RedisError::from(
+                        (ErrorKind::Moved, "Moved", format!("12182 {name}:6379")),
+                    ))),
+                    6379 => Err(Ok(Value::Int(deleted as i64))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        let deleted: i64 = cmd("DEL")
+            .arg("foo")
+            .arg("bar")
+            .arg("baz")
+            .query(&mut connection)
+            .unwrap();
+        assert_eq!(deleted, 3);
+    }
+
+    #[test]
+    fn test_cluster_pipeline_reports_the_result_of_every_command() {
+        let name = "test_cluster_pipeline_reports_the_result_of_every_command";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")]).retries(0),
+            name,
+            move |received_cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, received_cmd, None)?;
+                let moved =
+                    parse_redis_value(format!("-MOVED 12182 {name}:6379\r\n").as_bytes()).unwrap();
+                match port {
+                    6381 => Err(Ok(Value::Array(vec![moved.clone(), moved]))),
+                    6379 => Err(Ok(Value::Array(vec![
+                        Value::BulkString(b"1".to_vec()),
+                        Value::BulkString(b"2".to_vec()),
+                    ]))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        let results = cluster_pipe()
+            .set("foo", 1)
+            .get("bar")
+            .get("baz")
+            .get("foo")
+            .query_per_command(&mut connection)
+            .unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &Value::BulkString(b"1".to_vec())
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap(),
+            &Value::BulkString(b"2".to_vec())
+        );
+        for idx in [0, 3] {
+            let err = results[idx].as_ref().unwrap_err();
+            assert!(err.is_moved(), "{err}");
+            assert!(!err.is_connection_error());
+            assert_eq!(err.node(), Some(&*format!("{name}:6381")));
+        }
+    }
+
+    #[test]
+    fn test_cluster_pipeline_reports_connection_errors_per_command() {
+        let name = "test_cluster_pipeline_reports_connection_errors_per_command";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")]).retries(0),
+            name,
+            move |received_cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, received_cmd, None)?;
+                match port {
+                    6381 => Err(Err(RedisError::from(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionReset,
+                        "mock-io-error",
+                    )))),
+                    6379 => Err(Ok(Value::Array(vec![Value::Okay]))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        let results = cluster_pipe()
+            .get("foo")
+            .set("bar", 1)
+            .query_per_command(&mut connection)
+            .unwrap();
+
+        let err = results[0].as_ref().unwrap_err();
+        assert!(err.is_connection_error(), "{err}");
+        assert!(!err.is_moved() && !err.is_ask());
+        assert_eq!(err.node(), Some(&*format!("{name}:6381")));
+        assert_eq!(results[1].as_ref().unwrap(), &Value::Okay);
+    }
+
+    #[test]
+    fn test_cluster_atomic_pipeline_has_no_per_command_results() {
+        let name = "test_cluster_atomic_pipeline_has_no_per_command_results";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |received_cmd: &[u8], _| {
+            respond_startup(name, received_cmd)?;
+            panic!("The pipeline must not be sent");
+        });
+
+        let err = cluster_pipe()
+            .atomic()
+            .get("foo")
+            .query_per_command(&mut connection)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+    }
+
+    #[test]
+    fn test_cluster_reads_skip_replica_with_open_circuit() {
+        let name = "test_cluster_reads_skip_replica_with_open_circuit";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .read_from_replicas()
+                .circuit_breaker(
+                    CircuitBreakerConfig::new()
+                        .set_min_requests(1)
+                        .set_error_rate_threshold(0.1),
+                ),
+            name,
+            move |received_cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, received_cmd, None)?;
+                match port {
+                    6382 => Err(Err(RedisError::from(std::io::Error::new(
+                        std::io::ErrorKind::ConnectionReset,
+                        "mock-io-error",
+                    )))),
+                    6381 => Err(Ok(Value::BulkString(b"123".to_vec()))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        // The only replica of slot 12182 fails, which opens its circuit.
+        let err = connection.get::<_, String>("foo").unwrap_err();
+        assert!(err.is_io_error(), "{err}");
+        assert!(connection.node_health()[&format!("{name}:6382")].is_circuit_open());
+
+        // Reads are routed to the primary until the replica is tried again.
+        let value: String = connection.get("foo").unwrap();
+        assert_eq!(value, "123");
+        assert!(!connection.node_health()[&format!("{name}:6381")].is_circuit_open());
+    }
+
     #[test]
     fn test_cluster_route_correctly_on_packed_transaction_with_single_node_requests() {
         let name = "test_cluster_route_correctly_on_packed_transaction_with_single_node_requests";
@@ -987,6 +1496,89 @@ mod cluster {
         assert_eq!(result, expected_result);
     }
 
+    #[test]
+    fn test_cluster_transaction_is_retried_as_a_unit_after_redirection() {
+        let name = "test_cluster_transaction_is_retried_as_a_unit_after_redirection";
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests_clone = requests.clone();
+
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(1)
+                .read_from_replicas(),
+            name,
+            move |cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, cmd, None)?;
+                assert!(contains_slice(cmd, b"MULTI") && contains_slice(cmd, b"EXEC"));
+                requests_clone.lock().unwrap().push(port);
+                match port {
+                    // The transaction only reads, but is still sent to the primary of slot 12182.
+                    // The connection reports the redirection of the queued commands as an error.
+                    6381 => Err(parse_redis_value(
+                        format!("-MOVED 12182 {name}:6379\r\n").as_bytes(),
+                    )
+                    .and_then(Value::extract_error)),
+                    6379 => Err(Ok(Value::Array(vec![
+                        Value::SimpleString("OK".to_string()),
+                        Value::SimpleString("QUEUED".to_string()),
+                        Value::SimpleString("QUEUED".to_string()),
+                        // The responses which are read after skipping the queued responses.
+                        Value::Array(vec![Value::Array(vec![
+                            Value::BulkString(b"1".to_vec()),
+                            Value::BulkString(b"2".to_vec()),
+                        ])]),
+                    ]))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        let result: (i32, i32) = cluster_pipe()
+            .atomic()
+            .get("{foo}1")
+            .get("{foo}2")
+            .query(&mut connection)
+            .unwrap();
+        assert_eq!(result, (1, 2));
+        assert_eq!(*requests.lock().unwrap(), vec![6381, 6379]);
+    }
+
+    #[test]
+    fn test_cluster_transaction_with_cross_slot_keys_is_not_sent() {
+        let name = "test_cluster_transaction_with_cross_slot_keys_is_not_sent";
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup_two_nodes(name, cmd)?;
+            panic!("Unexpected request: {}", String::from_utf8_lossy(cmd));
+        });
+
+        let err = cluster_pipe()
+            .atomic()
+            .set("foo", "bar")
+            .get("baz")
+            .query::<Value>(&mut connection)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        assert_eq!(err.detail(), Some("`foo` (slot 12182), `baz` (slot 4813)"));
+
+        #[cfg(feature = "script")]
+        {
+            let err = redis::Script::new("return 1")
+                .key("foo")
+                .key("baz")
+                .invoke::<Value>(&mut connection)
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        }
+    }
+
     #[test]
     fn test_cluster_can_be_created_with_partial_slot_coverage() {
         let name = "test_cluster_can_be_created_with_partial_slot_coverage";
@@ -18,7 +18,7 @@ mod cluster_async {
 
     use redis::{
         aio::{ConnectionLike, MultiplexedConnection},
-        cluster::ClusterClient,
+        cluster::{CircuitBreakerConfig, ClusterClient, SlotRange},
         cluster_async::Connect,
         cluster_routing::{MultipleNodeRoutingInfo, RoutingInfo, SingleNodeRoutingInfo},
         cmd, from_owned_redis_value, parse_redis_value, pipe, AsyncCommands, Cmd, ErrorKind,
@@ -896,6 +896,10 @@ mod cluster_async {
                     return Err(Ok(Value::SimpleString("OK".into())));
                 }
 
+                if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+                    return Err(unknown_shards_subcommand());
+                }
+
                 if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
                     return Err(Ok(Value::Array(vec![
                         Value::Array(vec![
@@ -999,9 +1003,9 @@ mod cluster_async {
             },
         );
 
-        // 4 - MockEnv creates a sync & async connections, each calling CLUSTER SLOTS once & PING per node.
+        // 6 - MockEnv creates a sync & async connections, each calling CLUSTER SHARDS, CLUSTER SLOTS once & PING per node.
         // If we add more nodes or more setup calls, this number should increase.
-        assert_eq!(connection_count_clone.load(Ordering::Relaxed), 4);
+        assert_eq!(connection_count_clone.load(Ordering::Relaxed), 6);
 
         let value = runtime.block_on(connection.route_command(
             &cmd("ECHO"),
@@ -1026,8 +1030,8 @@ mod cluster_async {
         ));
 
         assert_eq!(value, Ok(Value::BulkString(b"PONG".to_vec())));
-        // 5 - because of the 4 above, and then another PING for new connections.
-        assert_eq!(connection_count_clone.load(Ordering::Relaxed), 5);
+        // 7 - because of the 6 above, and then another PING for new connections.
+        assert_eq!(connection_count_clone.load(Ordering::Relaxed), 7);
     }
 
     #[test]
@@ -1368,6 +1372,539 @@ mod cluster_async {
         assert_eq!(*found_ports.lock().unwrap(), expected_ports);
     }
 
+    #[test]
+    fn test_async_cluster_discovers_topology_with_cluster_shards() {
+        let name = "test_async_cluster_discovers_topology_with_cluster_shards";
+
+        let node = |port: i64, role: &str, health: &str| {
+            Value::Array(
+                [
+                    ("id", Value::BulkString(format!("id-{port}").into_bytes())),
+                    ("port", Value::Int(port)),
+                    ("endpoint", Value::BulkString(name.as_bytes().to_vec())),
+                    ("role", Value::BulkString(role.as_bytes().to_vec())),
+                    ("health", Value::BulkString(health.as_bytes().to_vec())),
+                ]
+                .into_iter()
+                .flat_map(|(field, value)| [Value::BulkString(field.as_bytes().to_vec()), value])
+                .collect(),
+            )
+        };
+        let shards = Value::Array(vec![Value::Array(vec![
+            Value::BulkString(b"slots".to_vec()),
+            Value::Array(vec![Value::Int(0), Value::Int(16383)]),
+            Value::BulkString(b"nodes".to_vec()),
+            Value::Array(vec![
+                node(6379, "master", "online"),
+                node(6380, "replica", "loading"),
+                node(6381, "replica", "online"),
+            ]),
+        ])]);
+
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .read_from_replicas(),
+            name,
+            move |cmd: &[u8], port| {
+                if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+                    return Err(Ok(shards.clone()));
+                }
+                if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
+                    panic!("CLUSTER SLOTS shouldn't be used if CLUSTER SHARDS is supported");
+                }
+                respond_startup(name, cmd)?;
+                match port {
+                    // The loading replica doesn't serve reads.
+                    6381 => Err(Ok(Value::BulkString(b"123".to_vec()))),
+                    _ => panic!("Wrong node"),
+                }
+            },
+        );
+
+        for _ in 0..3 {
+            let value = runtime.block_on(
+                cmd("GET")
+                    .arg("test")
+                    .query_async::<Option<i32>>(&mut connection),
+            );
+            assert_eq!(value, Ok(Some(123)));
+        }
+        let topology = runtime.block_on(connection.topology());
+        assert_eq!(
+            topology.node_id(&format!("{name}:6381")),
+            Some("id-6381"),
+            "Node ids must be read from CLUSTER SHARDS"
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_does_not_retry_unsupported_cluster_shards() {
+        let name = "test_async_cluster_does_not_retry_unsupported_cluster_shards";
+        let moved = AtomicBool::new(false);
+        let shards_queries = Arc::new(AtomicU16::new(0));
+        let shards_queries_clone = shards_queries.clone();
+        let slots_queries = Arc::new(AtomicU16::new(0));
+        let slots_queries_clone = slots_queries.clone();
+
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], port| {
+            if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+                shards_queries_clone.fetch_add(1, Ordering::SeqCst);
+            }
+            if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SLOTS") {
+                slots_queries_clone.fetch_add(1, Ordering::SeqCst);
+            }
+            respond_startup(name, cmd)?;
+            match port {
+                6379 if !moved.swap(true, Ordering::SeqCst) => Err(parse_redis_value(
+                    format!("-MOVED 123 {name}:6379\r\n").as_bytes(),
+                )),
+                6379 => Err(Ok(Value::Int(123))),
+                _ => panic!("Wrong node"),
+            }
+        });
+
+        let initial_shards_queries = shards_queries.load(Ordering::SeqCst);
+        let initial_slots_queries = slots_queries.load(Ordering::SeqCst);
+        let value = runtime.block_on(
+            cmd("GET")
+                .arg("test")
+                .query_async::<Option<i32>>(&mut connection),
+        );
+        assert_eq!(value, Ok(Some(123)));
+        // The refresh after the redirection only used CLUSTER SLOTS.
+        assert!(slots_queries.load(Ordering::SeqCst) > initial_slots_queries);
+        assert_eq!(
+            shards_queries.load(Ordering::SeqCst),
+            initial_shards_queries
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_retries_cluster_shards_after_transient_error() {
+        let name = "test_async_cluster_retries_cluster_shards_after_transient_error";
+        let moved = AtomicBool::new(false);
+        let shards_queries = Arc::new(AtomicU16::new(0));
+        let shards_queries_clone = shards_queries.clone();
+
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], port| {
+            if contains_slice(cmd, b"CLUSTER") && contains_slice(cmd, b"SHARDS") {
+                shards_queries_clone.fetch_add(1, Ordering::SeqCst);
+                return Err(parse_redis_value(
+                    b"-LOADING Redis is loading the dataset in memory\r\n",
+                ));
+            }
+            respond_startup(name, cmd)?;
+            match port {
+                6379 if !moved.swap(true, Ordering::SeqCst) => Err(parse_redis_value(
+                    format!("-MOVED 123 {name}:6379\r\n").as_bytes(),
+                )),
+                6379 => Err(Ok(Value::Int(123))),
+                _ => panic!("Wrong node"),
+            }
+        });
+
+        let initial_shards_queries = shards_queries.load(Ordering::SeqCst);
+        let value = runtime.block_on(
+            cmd("GET")
+                .arg("test")
+                .query_async::<Option<i32>>(&mut connection),
+        );
+        assert_eq!(value, Ok(Some(123)));
+        // The refresh after the redirection still tried CLUSTER SHARDS, before falling back to CLUSTER SLOTS.
+        assert!(
+            shards_queries.load(Ordering::SeqCst) > initial_shards_queries,
+            "CLUSTER SHARDS must not be skipped after a transient error"
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_periodic_topology_checks_refresh_slot_map() {
+        let name = "test_async_cluster_periodic_topology_checks_refresh_slot_map";
+        let replica_added = Arc::new(AtomicBool::new(false));
+        let replica_added_clone = replica_added.clone();
+        let slot_maps = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let slot_maps_clone = slot_maps.clone();
+
+        let MockEnv {
+            runtime,
+            async_connection: _connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .periodic_topology_checks(Duration::from_millis(10))
+                .slot_map_change_listener(move |slot_ranges: &[SlotRange]| {
+                    slot_maps_clone.lock().unwrap().push(
+                        slot_ranges
+                            .iter()
+                            .map(|range| range.replicas().len())
+                            .collect::<Vec<_>>(),
+                    );
+                }),
+            name,
+            move |cmd: &[u8], _port| {
+                let replica_ports = if replica_added_clone.load(Ordering::SeqCst) {
+                    vec![6381]
+                } else {
+                    vec![]
+                };
+                respond_startup_with_replica_using_config(
+                    name,
+                    cmd,
+                    Some(vec![
+                        MockSlotRange {
+                            primary_port: 6379,
+                            replica_ports,
+                            slot_range: (0..8191),
+                        },
+                        MockSlotRange {
+                            primary_port: 6380,
+                            replica_ports: vec![],
+                            slot_range: (8192..16383),
+                        },
+                    ]),
+                )?;
+                Ok(())
+            },
+        );
+
+        let wait_for_slot_maps = |expected: Vec<Vec<usize>>| {
+            let deadline = std::time::Instant::now() + Duration::from_secs(5);
+            while *slot_maps.lock().unwrap() != expected {
+                assert!(
+                    std::time::Instant::now() < deadline,
+                    "Slot maps {:?} weren't changed to {expected:?}",
+                    slot_maps.lock().unwrap()
+                );
+                runtime.block_on(sleep(Duration::from_millis(10).into()));
+            }
+        };
+        wait_for_slot_maps(vec![vec![0, 0]]);
+
+        replica_added.store(true, Ordering::SeqCst);
+        wait_for_slot_maps(vec![vec![0, 0], vec![1, 0]]);
+    }
+
+    #[test]
+    fn test_async_cluster_refresh_uses_topology_agreed_by_most_nodes() {
+        let name = "test_async_cluster_refresh_uses_topology_agreed_by_most_nodes";
+        let moved = AtomicBool::new(false);
+        let topology_queries = Arc::new(AtomicU16::new(0));
+        let topology_queries_clone = topology_queries.clone();
+
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(1)
+                .topology_consensus_nodes(3),
+            name,
+            move |cmd: &[u8], port| {
+                if contains_slice(cmd, b"SLOTS") {
+                    topology_queries_clone.fetch_add(1, Ordering::SeqCst);
+                }
+                // The node on port 6381 has a stale view, in which it still serves all slots.
+                let primary_port = if port == 6381 { 6381 } else { 6379 };
+                let replica_ports = [6379, 6380, 6381]
+                    .into_iter()
+                    .filter(|replica_port| *replica_port != primary_port)
+                    .collect();
+                respond_startup_with_replica_using_config(
+                    name,
+                    cmd,
+                    Some(vec![MockSlotRange {
+                        primary_port,
+                        replica_ports,
+                        slot_range: (0..16383),
+                    }]),
+                )?;
+                match port {
+                    6379 if !moved.swap(true, Ordering::SeqCst) => Err(parse_redis_value(
+                        format!("-MOVED 123 {name}:6379\r\n").as_bytes(),
+                    )),
+                    6379 => Err(Ok(Value::Int(123))),
+                    _ => panic!("Wrong node"),
+                }
+            },
+        );
+
+        let initial_queries = topology_queries.load(Ordering::SeqCst);
+        for _ in 0..3 {
+            let value = runtime.block_on(
+                cmd("GET")
+                    .arg("test")
+                    .query_async::<Option<i32>>(&mut connection),
+            );
+            assert_eq!(value, Ok(Some(123)));
+        }
+        // The refresh after the redirection queried all three nodes.
+        assert_eq!(topology_queries.load(Ordering::SeqCst), initial_queries + 3);
+    }
+
+    #[test]
+    fn test_async_cluster_topology_returns_slot_map() {
+        let name = "test_async_cluster_topology_returns_slot_map";
+        let MockEnv {
+            runtime,
+            async_connection: connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup_two_nodes(name, cmd)?;
+            Ok(())
+        });
+
+        let topology = runtime.block_on(connection.topology());
+        assert_eq!(
+            topology.primaries(),
+            vec![format!("{name}:6379"), format!("{name}:6380")]
+        );
+        assert_eq!(
+            topology.slot_range_for_key(b"foo").unwrap().primary(),
+            format!("{name}:6380")
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_refreshes_slots_when_circuit_of_primary_opens() {
+        let name = "test_async_cluster_refreshes_slots_when_circuit_of_primary_opens";
+        let slot_requests = Arc::new(AtomicU32::new(0));
+        let slot_requests_clone = slot_requests.clone();
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(0)
+                .circuit_breaker(CircuitBreakerConfig::new().set_consecutive_timeouts(2)),
+            name,
+            move |cmd: &[u8], port| {
+                if contains_slice(cmd, b"SLOTS") {
+                    slot_requests_clone.fetch_add(1, Ordering::Relaxed);
+                }
+                respond_startup_two_nodes(name, cmd)?;
+                match port {
+                    6380 => Err(Err(RedisError::from(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "mock-timeout",
+                    )))),
+                    _ => Err(Ok(Value::Okay)),
+                }
+            },
+        );
+        let initial_slot_requests = slot_requests.load(Ordering::Relaxed);
+
+        let _ = runtime.block_on(cmd("GET").arg("foo").query_async::<Value>(&mut connection));
+        assert_eq!(slot_requests.load(Ordering::Relaxed), initial_slot_requests);
+        let _ = runtime.block_on(cmd("GET").arg("foo").query_async::<Value>(&mut connection));
+        let health = connection.node_health();
+        assert_eq!(health[&format!("{name}:6380")].consecutive_timeouts(), 2);
+        assert!(health[&format!("{name}:6380")].is_circuit_open());
+
+        runtime
+            .block_on(cmd("SET").arg("bar").arg(1).exec_async(&mut connection))
+            .unwrap();
+        assert!(slot_requests.load(Ordering::Relaxed) > initial_slot_requests);
+    }
+
+    #[test]
+    fn test_async_cluster_transaction_is_sent_to_primary_of_its_keys() {
+        let name = "test_async_cluster_transaction_is_sent_to_primary_of_its_keys";
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")]).read_from_replicas(),
+            name,
+            move |cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, cmd, None)?;
+                // The transaction only reads, but is still sent to the primary of slot 12182.
+                assert_eq!(port, 6381);
+                // The mock connection returns the response of the last command as the result of `EXEC`.
+                Err(Ok(Value::Array(vec![Value::Int(1), Value::Int(2)])))
+            },
+        );
+
+        let result = runtime.block_on(
+            redis::pipe()
+                .atomic()
+                .get("{foo}1")
+                .get("{foo}2")
+                .query_async::<(i32, i32)>(&mut connection),
+        );
+        assert_eq!(result, Ok((1, 2)));
+    }
+
+    fn check_transaction_is_retried_as_a_unit_after_redirection(
+        name: &'static str,
+        redirect: &'static str,
+    ) {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests_clone = requests.clone();
+
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .retries(1)
+                .read_from_replicas(),
+            name,
+            move |cmd: &[u8], port| {
+                respond_startup_with_replica_using_config(name, cmd, None)?;
+                if contains_slice(cmd, b"ASKING") {
+                    return Err(Ok(Value::Okay));
+                }
+                requests_clone.lock().unwrap().push(port);
+                match port {
+                    // The transaction only reads, but is still sent to the primary of slot 12182.
+                    6381 => Err(parse_redis_value(
+                        format!("-{redirect} 12182 {name}:6379\r\n").as_bytes(),
+                    )
+                    .and_then(Value::extract_error)),
+                    // The mock connection returns the response of the last command as the result of `EXEC`.
+                    6379 => Err(Ok(Value::Array(vec![Value::Int(1), Value::Int(2)]))),
+                    _ => panic!("Wrong node: {port}"),
+                }
+            },
+        );
+
+        let result = runtime.block_on(
+            redis::pipe()
+                .atomic()
+                .get("{foo}1")
+                .get("{foo}2")
+                .query_async::<(i32, i32)>(&mut connection),
+        );
+        assert_eq!(result, Ok((1, 2)));
+        // The redirection of the first command fails the transaction, which is then sent again as a whole
+        // to the node it's redirected to.
+        assert_eq!(*requests.lock().unwrap(), vec![6381, 6379, 6379]);
+    }
+
+    #[test]
+    fn test_async_cluster_transaction_is_retried_as_a_unit_after_moved() {
+        check_transaction_is_retried_as_a_unit_after_redirection(
+            "test_async_cluster_transaction_is_retried_as_a_unit_after_moved",
+            "MOVED",
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_transaction_is_retried_as_a_unit_after_ask() {
+        check_transaction_is_retried_as_a_unit_after_redirection(
+            "test_async_cluster_transaction_is_retried_as_a_unit_after_ask",
+            "ASK",
+        );
+    }
+
+    #[test]
+    fn test_async_cluster_transaction_with_cross_slot_keys_is_not_sent() {
+        let name = "test_async_cluster_transaction_with_cross_slot_keys_is_not_sent";
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup_two_nodes(name, cmd)?;
+            panic!("Unexpected request: {}", String::from_utf8_lossy(cmd));
+        });
+
+        let err = runtime
+            .block_on(
+                redis::pipe()
+                    .atomic()
+                    .set("foo", "bar")
+                    .get("baz")
+                    .query_async::<Value>(&mut connection),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        assert_eq!(err.detail(), Some("`foo` (slot 12182), `baz` (slot 4813)"));
+
+        #[cfg(feature = "script")]
+        {
+            let err = runtime
+                .block_on(
+                    redis::Script::new("return 1")
+                        .key("foo")
+                        .key("baz")
+                        .invoke_async::<Value>(&mut connection),
+                )
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::CrossSlot);
+        }
+    }
+
+    #[test]
+    fn test_async_cluster_scan_iterates_all_primaries() {
+        let name = "test_async_cluster_scan_iterates_all_primaries";
+        let MockEnv {
+            runtime,
+            async_connection: mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::new(name, move |received_cmd: &[u8], port| {
+            respond_startup_two_nodes(name, received_cmd)?;
+            let cmd_str = std::str::from_utf8(received_cmd).unwrap();
+            if !cmd_str.contains("SCAN") {
+                return Ok(());
+            }
+            // Each primary returns its keys in two batches.
+            let (cursor, key) = if cmd_str.ends_with("\r\n0\r\n") {
+                ("7", format!("first-{port}"))
+            } else {
+                ("0", format!("second-{port}"))
+            };
+            Err(Ok(Value::Array(vec![
+                Value::BulkString(cursor.as_bytes().to_vec()),
+                Value::Array(vec![Value::BulkString(key.into_bytes())]),
+            ])))
+        });
+
+        let mut keys = runtime.block_on(async {
+            let mut iter = connection.scan::<String>().await.unwrap();
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                #[cfg(feature = "safe_iterators")]
+                let key = key.unwrap();
+                keys.push(key);
+            }
+            keys
+        });
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec!["first-6379", "first-6380", "second-6379", "second-6380"]
+        );
+    }
+
     #[test]
     fn test_async_cluster_fan_out_to_all_primaries() {
         test_async_cluster_fan_out(
@@ -4,10 +4,13 @@ mod support;
 use std::collections::HashMap;
 
 use crate::support::*;
-use redis::sentinel::SentinelClientBuilder;
+use redis::sentinel::{
+    LowestLagReplica, ReplicaInfo, ReplicaSelector, SentinelClientBuilder, SentinelInfo,
+    SentinelMasterInfo,
+};
 use redis::{
     sentinel::{Sentinel, SentinelClient, SentinelNodeConnectionInfo},
-    Client, Connection, ConnectionAddr, ConnectionInfo, Role,
+    Client, Commands, Connection, ConnectionAddr, ConnectionInfo, IntoConnectionInfo, Role,
 };
 
 fn parse_replication_info(value: &str) -> HashMap<&str, &str> {
@@ -245,6 +248,152 @@ fn test_sentinel_server_down() {
     );
 }
 
+#[test]
+fn test_sentinel_replica_selector() {
+    let master_name = "master1";
+    let mut context = TestSentinelContext::new(2, 3, 3);
+    let node_conn_info = context.sentinel_node_connection_info();
+    let sentinel = context.sentinel_mut();
+    let master_client = sentinel
+        .master_for(master_name, Some(&node_conn_info))
+        .unwrap();
+
+    sentinel.set_replica_selector(LowestLagReplica::new().set_max_lag(1024 * 1024));
+    for _ in 0..10 {
+        let mut replica_con = sentinel
+            .replica_for(master_name, Some(&node_conn_info))
+            .unwrap()
+            .get_connection()
+            .unwrap();
+        assert_connection_is_replica_of_correct_master(&mut replica_con, &master_client);
+    }
+
+    // A selector which excludes every replica leaves no replica to connect to.
+    struct NoReplica;
+    impl ReplicaSelector for NoReplica {
+        fn select_replicas(&self, replicas: &[ReplicaInfo]) -> Vec<usize> {
+            assert!(!replicas.is_empty());
+            vec![]
+        }
+    }
+    sentinel.set_replica_selector(NoReplica);
+    let err = sentinel
+        .replica_for(master_name, Some(&node_conn_info))
+        .unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::NoValidReplicasFoundBySentinel);
+    let err = sentinel
+        .replica_rotate_for(master_name, Some(&node_conn_info))
+        .unwrap_err();
+    assert_eq!(err.kind(), redis::ErrorKind::NoValidReplicasFoundBySentinel);
+}
+
+#[test]
+fn test_sentinel_discovers_peer_sentinels() {
+    let master_name = "master1";
+    let mut context = TestSentinelContext::new(2, 3, 3);
+    let node_conn_info = context.sentinel_node_connection_info();
+    let all_sentinels = context.sentinels_connection_info().clone();
+
+    // A sentinel the client was built with is kept, even if the other sentinels don't report it.
+    let unknown_sentinel = "redis://127.0.0.1:1".into_connection_info().unwrap();
+    let mut sentinel =
+        Sentinel::build(vec![all_sentinels[0].clone(), unknown_sentinel.clone()]).unwrap();
+    sentinel.discover_sentinels(master_name).unwrap();
+
+    let mut discovered: Vec<_> = sentinel
+        .sentinels_connection_info()
+        .iter()
+        .map(|info| info.addr.to_string())
+        .collect();
+    let mut expected: Vec<_> = all_sentinels
+        .iter()
+        .chain([&unknown_sentinel])
+        .map(|info| info.addr.to_string())
+        .collect();
+    discovered.sort();
+    expected.sort();
+    assert_eq!(discovered, expected);
+
+    // The sentinel keeps working with the discovered sentinels after the configured one is gone.
+    context.cluster.sentinel_servers[0].stop();
+    let master_client = sentinel
+        .master_for(master_name, Some(&node_conn_info))
+        .unwrap();
+    let mut master_con = master_client.get_connection().unwrap();
+    assert_is_connection_to_master(&mut master_con);
+}
+
+#[test]
+fn test_sentinel_admin_commands() {
+    let master_name = "master1";
+    let context = TestSentinelContext::new(2, 3, 3);
+    let sentinel_client = Client::open(context.sentinels_connection_info()[0].clone()).unwrap();
+    let mut sentinel_con = sentinel_client.get_connection().unwrap();
+
+    let master: SentinelMasterInfo = sentinel_con.sentinel_master(master_name).unwrap();
+    assert_eq!(master.name(), master_name);
+    assert!(!master.is_down());
+    assert_eq!(master.num_replicas(), Some(3));
+    assert_eq!(master.num_other_sentinels(), Some(2));
+
+    let address: Option<(String, u16)> = sentinel_con
+        .sentinel_get_master_addr_by_name(master_name)
+        .unwrap();
+    assert_eq!(address, Some((master.ip().to_string(), master.port())));
+    let address: Option<(String, u16)> = sentinel_con
+        .sentinel_get_master_addr_by_name("unknown")
+        .unwrap();
+    assert_eq!(address, None);
+
+    let replicas: Vec<ReplicaInfo> = sentinel_con.sentinel_replicas(master_name).unwrap();
+    assert_eq!(replicas.len(), 3);
+    assert!(replicas.iter().all(ReplicaInfo::is_master_link_up));
+    let sentinels: Vec<SentinelInfo> = sentinel_con.sentinel_sentinels(master_name).unwrap();
+    assert_eq!(sentinels.len(), 2);
+    let quorum: String = sentinel_con.sentinel_ckquorum(master_name).unwrap();
+    assert!(quorum.starts_with("OK"));
+
+    let _: () = sentinel_con
+        .sentinel_set(master_name, "down-after-milliseconds", 1500)
+        .unwrap();
+    let master: SentinelMasterInfo = sentinel_con.sentinel_master(master_name).unwrap();
+    assert_eq!(master.field("down-after-milliseconds"), Some("1500"));
+
+    let _: () = sentinel_con.sentinel_remove(master_name).unwrap();
+    let removed: redis::RedisResult<SentinelMasterInfo> = sentinel_con.sentinel_master(master_name);
+    assert!(removed.is_err());
+    let _: () = sentinel_con
+        .sentinel_monitor(master_name, master.ip(), master.port(), 2)
+        .unwrap();
+    let master: SentinelMasterInfo = sentinel_con.sentinel_master(master_name).unwrap();
+    assert_eq!(master.quorum(), Some(2));
+}
+
+#[test]
+fn test_sentinel_client_refreshes_sentinels() {
+    let master_name = "master1";
+    let mut context = TestSentinelContext::new(2, 3, 3);
+    let first_sentinel = context.sentinels_connection_info()[0].addr.clone();
+
+    let mut master_client = SentinelClientBuilder::new(
+        vec![first_sentinel],
+        String::from(master_name),
+        redis::sentinel::SentinelServerType::Master,
+    )
+    .unwrap()
+    .set_sentinel_discovery_interval(std::time::Duration::from_secs(60))
+    .build()
+    .unwrap();
+
+    // The first request discovers the peers of the configured sentinel.
+    let mut master_con = master_client.get_connection().unwrap();
+    assert_is_connection_to_master(&mut master_con);
+
+    context.cluster.sentinel_servers[0].stop();
+    let mut master_con = master_client.get_connection().unwrap();
+    assert_is_connection_to_master(&mut master_con);
+}
+
 #[test]
 fn test_sentinel_client() {
     let master_name = "master1";
@@ -375,13 +524,13 @@ pub mod async_tests {
     use redis::{
         aio::MultiplexedConnection,
         sentinel::{Sentinel, SentinelClient, SentinelNodeConnectionInfo},
-        AsyncConnectionConfig, Client, ConnectionAddr, RedisError,
+        AsyncConnectionConfig, Client, ConnectionAddr, RedisError, RedisResult,
     };
     use rstest::rstest;
 
     use crate::{assert_is_master_role, assert_replica_role_and_master_addr, support::*};
 
-    async fn async_assert_is_connection_to_master(conn: &mut MultiplexedConnection) {
+    async fn async_assert_is_connection_to_master(conn: &mut impl redis::aio::ConnectionLike) {
         let info: String = redis::cmd("INFO")
             .arg("REPLICATION")
             .query_async(conn)
@@ -464,6 +613,75 @@ pub mod async_tests {
         }
     }
 
+    /// Waits until the sentinels report a master other than `old_master`, and returns its port.
+    async fn wait_for_new_master_port(
+        sentinel: &mut Sentinel,
+        master_name: &str,
+        node_conn_info: &SentinelNodeConnectionInfo,
+        old_master: &Client,
+    ) -> u16 {
+        loop {
+            let master = sentinel
+                .async_master_for(master_name, Some(node_conn_info))
+                .await;
+            if let Ok(master) = master {
+                match &master.get_connection_info().addr {
+                    addr if addr == &old_master.get_connection_info().addr => {}
+                    ConnectionAddr::Tcp(_, port) | ConnectionAddr::TcpTls { port, .. } => {
+                        return *port
+                    }
+                    addr => panic!("unexpected master address {addr:?}"),
+                }
+            }
+            futures_time::task::sleep(std::time::Duration::from_millis(100).into()).await;
+        }
+    }
+
+    /// Swaps the roles of the master and one of its replicas behind the back of the sentinels, so they don't
+    /// announce a new master, and the connections to the demoted master stay open.
+    async fn demote_master_without_failover(
+        context: &mut TestSentinelContext,
+        master_name: &str,
+    ) -> RedisResult<()> {
+        let node_conn_info = context.sentinel_node_connection_info();
+        let master = context
+            .sentinel_mut()
+            .async_master_for(master_name, Some(&node_conn_info))
+            .await?;
+        let replica = context
+            .sentinel_mut()
+            .async_replica_for(master_name, Some(&node_conn_info))
+            .await?;
+        let (host, port) = match &replica.get_connection_info().addr {
+            ConnectionAddr::Tcp(host, port) | ConnectionAddr::TcpTls { host, port, .. } => {
+                (host.clone(), *port)
+            }
+            addr => panic!("unexpected replica address {addr:?}"),
+        };
+        redis::cmd("REPLICAOF")
+            .arg("NO")
+            .arg("ONE")
+            .exec_async(&mut replica.get_multiplexed_async_connection().await?)
+            .await?;
+        redis::cmd("REPLICAOF")
+            .arg(host)
+            .arg(port)
+            .exec_async(&mut master.get_multiplexed_async_connection().await?)
+            .await
+    }
+
+    /// The port of the server the connection is connected to.
+    async fn connected_port(conn: &mut impl redis::aio::ConnectionLike) -> Option<u16> {
+        let info: String = redis::cmd("INFO")
+            .arg("SERVER")
+            .query_async(conn)
+            .await
+            .ok()?;
+        info.split("\r\n")
+            .find_map(|line| line.strip_prefix("tcp_port:"))
+            .and_then(|port| port.trim().parse().ok())
+    }
+
     #[rstest]
     #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
     #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
@@ -599,6 +817,39 @@ pub mod async_tests {
         .unwrap();
     }
 
+    #[rstest]
+    #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+    #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+    #[cfg_attr(feature = "smol-comp", case::smol(RuntimeType::Smol))]
+    fn test_sentinel_discovers_peer_sentinels_async(#[case] runtime: RuntimeType) {
+        let master_name = "master1";
+        let mut context = TestSentinelContext::new(2, 3, 3);
+        let node_conn_info = context.sentinel_node_connection_info();
+        let all_sentinels = context.sentinels_connection_info().clone();
+
+        block_on_all(
+            async move {
+                let mut sentinel = Sentinel::build(vec![all_sentinels[0].clone()])?;
+                sentinel.async_discover_sentinels(master_name).await?;
+                assert_eq!(
+                    sentinel.sentinels_connection_info().len(),
+                    all_sentinels.len()
+                );
+
+                context.cluster.sentinel_servers[0].stop();
+                let master_client = sentinel
+                    .async_master_for(master_name, Some(&node_conn_info))
+                    .await?;
+                let mut master_con = master_client.get_multiplexed_async_connection().await?;
+                async_assert_is_connection_to_master(&mut master_con).await;
+
+                Ok::<(), RedisError>(())
+            },
+            runtime,
+        )
+        .unwrap();
+    }
+
     #[rstest]
     #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
     #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
@@ -832,6 +1083,278 @@ pub mod async_tests {
         )
         .unwrap();
     }
+
+    #[rstest]
+    #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+    #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+    #[cfg_attr(feature = "smol-comp", case::smol(RuntimeType::Smol))]
+    fn test_sentinel_multiplexed_connection_resolves_master_after_failover(
+        #[case] runtime: RuntimeType,
+    ) {
+        let master_name = "master1";
+        let mut context = TestSentinelContext::new(2, 3, 3);
+        let master_client = SentinelClient::build(
+            context.sentinels_connection_info().clone(),
+            String::from(master_name),
+            Some(context.sentinel_node_connection_info()),
+            redis::sentinel::SentinelServerType::Master,
+        )
+        .unwrap();
+
+        block_on_all(
+            async move {
+                let mut con = master_client
+                    .into_multiplexed_connection(AsyncConnectionConfig::new())
+                    .await?;
+                let mut other_con = con.clone();
+                redis::cmd("SET")
+                    .arg("key")
+                    .arg("before")
+                    .exec_async(&mut con)
+                    .await?;
+                let value: String = redis::cmd("GET")
+                    .arg("key")
+                    .query_async(&mut other_con)
+                    .await?;
+                assert_eq!(value, "before");
+
+                let node_conn_info = context.sentinel_node_connection_info();
+                let old_master = context
+                    .sentinel_mut()
+                    .async_master_for(master_name, Some(&node_conn_info))
+                    .await?;
+                let mut sentinel_con =
+                    Client::open(context.sentinels_connection_info()[0].clone())?
+                        .get_multiplexed_async_connection()
+                        .await?;
+                redis::cmd("SENTINEL")
+                    .arg("FAILOVER")
+                    .arg(master_name)
+                    .exec_async(&mut sentinel_con)
+                    .await?;
+
+                let new_master_port = wait_for_new_master_port(
+                    context.sentinel_mut(),
+                    master_name,
+                    &node_conn_info,
+                    &old_master,
+                )
+                .await;
+
+                // The sentinels close the connections to the demoted master, which makes the connection resolve the
+                // new one.
+                let mut followed = false;
+                for _ in 0..100 {
+                    let written = redis::cmd("SET")
+                        .arg("key")
+                        .arg("after")
+                        .exec_async(&mut con)
+                        .await;
+                    if written.is_ok() && connected_port(&mut con).await == Some(new_master_port) {
+                        followed = true;
+                        break;
+                    }
+                    futures_time::task::sleep(std::time::Duration::from_millis(100).into()).await;
+                }
+                assert!(followed, "the connection didn't resolve the new master");
+
+                // The clone shares the resolved connection.
+                async_assert_is_connection_to_master(&mut other_con).await;
+                let value: String = redis::cmd("GET")
+                    .arg("key")
+                    .query_async(&mut other_con)
+                    .await?;
+                assert_eq!(value, "after");
+
+                Ok::<(), RedisError>(())
+            },
+            runtime,
+        )
+        .unwrap();
+    }
+
+    #[rstest]
+    #[cfg_attr(feature = "tokio-comp", case::tokio(RuntimeType::Tokio))]
+    #[cfg_attr(feature = "async-std-comp", case::async_std(RuntimeType::AsyncStd))]
+    #[cfg_attr(feature = "smol-comp", case::smol(RuntimeType::Smol))]
+    fn test_sentinel_multiplexed_connection_resolves_master_on_readonly_reply(
+        #[case] runtime: RuntimeType,
+    ) {
+        let master_name = "master1";
+        let mut context = TestSentinelContext::new(2, 3, 3);
+        let master_client = SentinelClient::build(
+            context.sentinels_connection_info().clone(),
+            String::from(master_name),
+            Some(context.sentinel_node_connection_info()),
+            redis::sentinel::SentinelServerType::Master,
+        )
+        .unwrap();
+
+        block_on_all(
+            async move {
+                let mut con = master_client
+                    .into_multiplexed_connection(AsyncConnectionConfig::new())
+                    .await?;
+                let client_id: i64 = redis::cmd("CLIENT").arg("ID").query_async(&mut con).await?;
+
+                demote_master_without_failover(&mut context, master_name).await?;
+
+                // The sentinels still report the demoted master, so the write is retried on a new connection
+                // to it, which is answered with READONLY again.
+                let written = redis::cmd("SET")
+                    .arg("key")
+                    .arg("value")
+                    .exec_async(&mut con)
+                    .await;
+                assert_eq!(written.unwrap_err().kind(), redis::ErrorKind::ReadOnly);
+                let new_client_id: i64 =
+                    redis::cmd("CLIENT").arg("ID").query_async(&mut con).await?;
+                assert_ne!(new_client_id, client_id);
+
+                Ok::<(), RedisError>(())
+            },
+            runtime,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "connection-manager")]
+    fn test_connection_manager_follows_sentinel_failover() {
+        let master_name = "master1";
+        let mut context = TestSentinelContext::new(2, 3, 3);
+        let master_client = SentinelClient::build(
+            context.sentinels_connection_info().clone(),
+            String::from(master_name),
+            Some(context.sentinel_node_connection_info()),
+            redis::sentinel::SentinelServerType::Master,
+        )
+        .unwrap();
+
+        block_on_all(
+            async move {
+                let mut manager = redis::aio::ConnectionManager::new_with_sentinel(
+                    master_client,
+                    redis::aio::ConnectionManagerConfig::new()
+                        .set_number_of_retries(10)
+                        .set_max_delay(1000),
+                )
+                .await?;
+                redis::cmd("SET")
+                    .arg("key")
+                    .arg("before")
+                    .exec_async(&mut manager)
+                    .await?;
+
+                let node_conn_info = context.sentinel_node_connection_info();
+                let old_master = context
+                    .sentinel_mut()
+                    .async_master_for(master_name, Some(&node_conn_info))
+                    .await?;
+                let mut sentinel_con =
+                    Client::open(context.sentinels_connection_info()[0].clone())?
+                        .get_multiplexed_async_connection()
+                        .await?;
+                redis::cmd("SENTINEL")
+                    .arg("FAILOVER")
+                    .arg(master_name)
+                    .exec_async(&mut sentinel_con)
+                    .await?;
+
+                let new_master_port = wait_for_new_master_port(
+                    context.sentinel_mut(),
+                    master_name,
+                    &node_conn_info,
+                    &old_master,
+                )
+                .await;
+
+                // The manager reconnects to the promoted replica, without being recreated.
+                let mut followed = false;
+                for _ in 0..100 {
+                    let written = redis::cmd("SET")
+                        .arg("key")
+                        .arg("after")
+                        .exec_async(&mut manager)
+                        .await;
+                    if written.is_ok()
+                        && connected_port(&mut manager).await == Some(new_master_port)
+                    {
+                        followed = true;
+                        break;
+                    }
+                    futures_time::task::sleep(std::time::Duration::from_millis(100).into()).await;
+                }
+                assert!(followed, "the manager didn't follow the failover");
+
+                let value: String = redis::cmd("GET")
+                    .arg("key")
+                    .query_async(&mut manager)
+                    .await?;
+                assert_eq!(value, "after");
+
+                Ok::<(), RedisError>(())
+            },
+            RuntimeType::Tokio,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "connection-manager")]
+    fn test_connection_manager_reconnects_when_master_is_demoted_without_failover() {
+        let master_name = "master1";
+        let mut context = TestSentinelContext::new(2, 3, 3);
+        let master_client = SentinelClient::build(
+            context.sentinels_connection_info().clone(),
+            String::from(master_name),
+            Some(context.sentinel_node_connection_info()),
+            redis::sentinel::SentinelServerType::Master,
+        )
+        .unwrap();
+
+        block_on_all(
+            async move {
+                let mut manager = redis::aio::ConnectionManager::new_with_sentinel(
+                    master_client,
+                    redis::aio::ConnectionManagerConfig::new(),
+                )
+                .await?;
+                let client_id: i64 = redis::cmd("CLIENT")
+                    .arg("ID")
+                    .query_async(&mut manager)
+                    .await?;
+
+                demote_master_without_failover(&mut context, master_name).await?;
+
+                let written = redis::cmd("SET")
+                    .arg("key")
+                    .arg("value")
+                    .exec_async(&mut manager)
+                    .await;
+                assert_eq!(written.unwrap_err().kind(), redis::ErrorKind::ReadOnly);
+
+                // The READONLY reply makes the manager reconnect.
+                let mut reconnected = false;
+                for _ in 0..100 {
+                    let new_client_id: RedisResult<i64> = redis::cmd("CLIENT")
+                        .arg("ID")
+                        .query_async(&mut manager)
+                        .await;
+                    if new_client_id.is_ok_and(|new_client_id| new_client_id != client_id) {
+                        reconnected = true;
+                        break;
+                    }
+                    futures_time::task::sleep(std::time::Duration::from_millis(100).into()).await;
+                }
+                assert!(reconnected, "the manager didn't reconnect");
+
+                Ok::<(), RedisError>(())
+            },
+            RuntimeType::Tokio,
+        )
+        .unwrap();
+    }
 }
 
 #[cfg(feature = "r2d2")]